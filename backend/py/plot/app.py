@@ -5,6 +5,7 @@ import pandas as pd
 from plotnine import (
     aes,
     element_text,
+    facet_grid,
     ggplot,
     ggtitle,
     geom_bar,
@@ -102,7 +103,9 @@ def aesthetics(d):
     }
 
 
-def add_shared(p, title=None, **kwargs):
+def add_shared(p, title=None, facet_x=None, facet_y=None, **kwargs):
+    if facet_x or facet_y:
+        p = p + facet_grid('{} ~ {}'.format(facet_y or '.', facet_x or '.'))
     if title:
         p = p + ggtitle(title)
     return p
@@ -14,6 +14,8 @@ def dbs():
 
 
 def db_urls():
+    if 'DATA_DB_URL' in env and 'META_DB_URL' in env:
+        return env['DATA_DB_URL'], env['META_DB_URL']
     if run_mode() == 'local':
         ip = '172.17.0.1'
         ext = 'data'
@@ -0,0 +1,40 @@
+-- casts that return NULL instead of failing, for dataviews that cast columns
+CREATE OR REPLACE FUNCTION try_cast_bigint(v anyelement) RETURNS BIGINT AS $$
+BEGIN
+    RETURN CAST(v AS BIGINT);
+EXCEPTION WHEN OTHERS THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION try_cast_double_precision(v anyelement) RETURNS DOUBLE PRECISION AS $$
+BEGIN
+    RETURN CAST(v AS DOUBLE PRECISION);
+EXCEPTION WHEN OTHERS THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION try_cast_date(v anyelement) RETURNS DATE AS $$
+BEGIN
+    RETURN CAST(v AS DATE);
+EXCEPTION WHEN OTHERS THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION try_cast_timestamp(v anyelement) RETURNS TIMESTAMP AS $$
+BEGIN
+    RETURN CAST(v AS TIMESTAMP);
+EXCEPTION WHEN OTHERS THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION try_cast_boolean(v anyelement) RETURNS BOOLEAN AS $$
+BEGIN
+    RETURN CAST(v AS BOOLEAN);
+EXCEPTION WHEN OTHERS THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
@@ -0,0 +1,38 @@
+-- status notifications, use manage_status_notifications('<tablename>');
+-- payloads are consumed by the subscriptions in src/subscription.rs
+CREATE OR REPLACE FUNCTION manage_status_notifications(_tbl regclass)
+RETURNS VOID AS $$
+BEGIN
+    EXECUTE format('CREATE TRIGGER notify_status_change AFTER UPDATE OF status
+                    ON %s FOR EACH ROW
+                    WHEN (OLD.status IS DISTINCT FROM NEW.status)
+                    EXECUTE PROCEDURE notify_status_change()', _tbl);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION notify_status_change() RETURNS trigger AS $$
+DECLARE
+    _analysis_uuid UUID;
+BEGIN
+    IF TG_TABLE_NAME = 'dataviews' THEN
+        _analysis_uuid := NEW.analysis_uuid;
+    ELSIF TG_TABLE_NAME != 'datasets' THEN
+        SELECT analysis_uuid INTO _analysis_uuid
+        FROM dataviews
+        WHERE uuid = NEW.dataview_uuid;
+    END IF;
+    PERFORM pg_notify('status_changes', json_build_object(
+        'table', TG_TABLE_NAME,
+        'uuid', NEW.uuid,
+        'analysis_uuid', _analysis_uuid,
+        'status', upper(NEW.status::TEXT)
+    )::TEXT);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT manage_status_notifications('datasets');
+SELECT manage_status_notifications('dataviews');
+SELECT manage_status_notifications('statistics');
+SELECT manage_status_notifications('plots');
+SELECT manage_status_notifications('models');
@@ -0,0 +1,38 @@
+CREATE TYPE JOB_KIND AS ENUM(
+  'upload_dataset',
+  'create_dataview',
+  'create_statistic',
+  'create_plot',
+  'create_model'
+);
+
+CREATE TABLE jobs (
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  uuid UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+  project_uuid UUID NOT NULL REFERENCES projects(uuid) ON DELETE CASCADE,
+  kind JOB_KIND NOT NULL,
+  payload JSON NOT NULL,
+  status STATUS DEFAULT 'queued' NOT NULL,
+  priority INTEGER DEFAULT 0 NOT NULL,
+  attempts INTEGER DEFAULT 0 NOT NULL,
+  max_attempts INTEGER DEFAULT 3 NOT NULL,
+  run_after TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  locked_at TIMESTAMPTZ,
+  locked_by TEXT,
+  last_error TEXT
+);
+CREATE INDEX jobs_created_at_idx ON jobs(created_at);
+CREATE INDEX jobs_updated_at_idx ON jobs(updated_at);
+CREATE INDEX jobs_queued_idx
+  ON jobs(priority DESC, created_at)
+  WHERE status = 'queued';
+CREATE INDEX jobs_running_idx
+  ON jobs(project_uuid, locked_at)
+  WHERE status = 'running';
+
+SELECT manage_updated_at('jobs');
+
+ALTER TABLE jobs
+  ADD CONSTRAINT at_least_one_attempt
+  CHECK (max_attempts > 0);
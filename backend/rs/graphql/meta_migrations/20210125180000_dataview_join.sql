@@ -0,0 +1,3 @@
+-- sqlx runs each migration in a transaction, where ADD VALUE needs postgres 12
+-- or later
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'join';
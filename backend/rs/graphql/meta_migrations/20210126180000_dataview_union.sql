@@ -0,0 +1,2 @@
+-- like the join migration, this needs postgres 12 or later
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'union';
@@ -0,0 +1,2 @@
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'pivot';
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'unpivot';
@@ -0,0 +1 @@
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'window';
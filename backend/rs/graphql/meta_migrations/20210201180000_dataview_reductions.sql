@@ -0,0 +1,3 @@
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'sample';
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'limit';
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'distinct';
@@ -0,0 +1,2 @@
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'drop_nulls';
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'impute';
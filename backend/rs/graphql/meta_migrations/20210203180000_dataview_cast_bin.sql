@@ -0,0 +1,2 @@
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'cast';
+ALTER TYPE DATAVIEW_OPERATION ADD VALUE 'bin';
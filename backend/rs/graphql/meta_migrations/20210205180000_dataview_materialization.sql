@@ -0,0 +1,2 @@
+ALTER TABLE dataviews ADD COLUMN materialized BOOLEAN DEFAULT FALSE NOT NULL;
+ALTER TABLE dataviews ADD COLUMN refreshed_at TIMESTAMPTZ;
@@ -0,0 +1 @@
+ALTER TABLE datasets ADD COLUMN n_rows BIGINT;
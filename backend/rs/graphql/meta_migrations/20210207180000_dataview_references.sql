@@ -0,0 +1,18 @@
+-- the datasets and dataviews that join and union views read from besides
+-- their parents, which can't be deleted until the dataviews reading from
+-- them are
+CREATE TABLE dataview_references (
+  dataview_uuid UUID NOT NULL REFERENCES dataviews(uuid) ON DELETE CASCADE,
+  dataset_uuid UUID REFERENCES datasets(uuid),
+  referenced_dataview_uuid UUID REFERENCES dataviews(uuid)
+);
+CREATE INDEX dataview_references_dataview_uuid_idx
+  ON dataview_references(dataview_uuid);
+CREATE INDEX dataview_references_dataset_uuid_idx
+  ON dataview_references(dataset_uuid);
+CREATE INDEX dataview_references_referenced_dataview_uuid_idx
+  ON dataview_references(referenced_dataview_uuid);
+
+ALTER TABLE dataview_references
+  ADD CONSTRAINT references_one_node
+  CHECK ((dataset_uuid IS NULL) <> (referenced_dataview_uuid IS NULL));
@@ -0,0 +1,14 @@
+-- dataviews are created in the API rather than by a job, and enum values
+-- can't be dropped, so the type is replaced without create_dataview
+DELETE FROM jobs WHERE kind = 'create_dataview';
+
+ALTER TYPE JOB_KIND RENAME TO OLD_JOB_KIND;
+CREATE TYPE JOB_KIND AS ENUM(
+  'upload_dataset',
+  'create_statistic',
+  'create_plot',
+  'create_model'
+);
+ALTER TABLE jobs
+  ALTER COLUMN kind TYPE JOB_KIND USING kind::TEXT::JOB_KIND;
+DROP TYPE OLD_JOB_KIND;
@@ -1,5 +1,8 @@
 use graphql::{
-    utils::{dataset_table_name, dataview_view_name, run_mode},
+    utils::{
+        dataset_table_name, dataview_view_name, materialized_view_name,
+        run_mode,
+    },
     Db, Secrets,
 };
 use lambda_http::{
@@ -60,13 +63,31 @@ async fn garbage_collect(secrets: &Secrets) -> Result<(), GenericError> {
     let bucket = "motoko-data";
     drop_unreferenced_datasets(&db).await?;
     drop_unreferenced_dataviews(&db).await?;
+    drop_unreferenced_materialized_views(&db).await?;
+    fail_dataviews_without_views(&db).await?;
+    fail_abandoned_datasets(&db).await?;
     delete_expired_refresh_tokens(&db).await?;
     delete_unreferenced_objects(&db, &s3, bucket, "plots").await?;
     delete_unreferenced_objects(&db, &s3, bucket, "models").await?;
     Ok(())
 }
 
+// relations are listed before the nodes they belong to, which are recorded
+// before their relations are created, so new relations are never taken for
+// unreferenced ones
 async fn drop_unreferenced_datasets(db: &Db) -> SQLxResult<()> {
+    let extant: HashSet<String> = query_scalar::<_, String>(
+        r#"
+        SELECT table_name
+        FROM information_schema.tables
+        WHERE table_type = 'BASE TABLE'
+        AND table_schema = 'public'
+        "#,
+    )
+    .fetch_all(&db.data)
+    .await?
+    .into_iter()
+    .collect();
     let live: HashSet<String> =
         query_scalar::<_, Uuid>("SELECT uuid FROM datasets")
             .fetch_all(&db.meta)
@@ -74,11 +95,22 @@ async fn drop_unreferenced_datasets(db: &Db) -> SQLxResult<()> {
             .iter()
             .map(dataset_table_name)
             .collect();
+    let to_drop: Vec<String> = extant.difference(&live).cloned().collect();
+    query(&format!(
+        "DROP TABLE IF EXISTS {} CASCADE",
+        to_drop.join(",")
+    ))
+    .execute(&db.data)
+    .await
+    .map(|_| ())
+}
+
+async fn drop_unreferenced_dataviews(db: &Db) -> SQLxResult<()> {
     let extant: HashSet<String> = query_scalar::<_, String>(
         r#"
         SELECT table_name
         FROM information_schema.tables
-        WHERE table_type = 'BASE TABLE'
+        WHERE table_type = 'VIEW'
         AND table_schema = 'public'
         "#,
     )
@@ -86,9 +118,16 @@ async fn drop_unreferenced_datasets(db: &Db) -> SQLxResult<()> {
     .await?
     .into_iter()
     .collect();
+    let live: HashSet<String> =
+        query_scalar::<_, Uuid>("SELECT uuid FROM dataviews")
+            .fetch_all(&db.meta)
+            .await?
+            .iter()
+            .map(dataview_view_name)
+            .collect();
     let to_drop: Vec<String> = extant.difference(&live).cloned().collect();
     query(&format!(
-        "DROP TABLE IF EXISTS {} CASCADE",
+        "DROP VIEW IF EXISTS {} CASCADE",
         to_drop.join(",")
     ))
     .execute(&db.data)
@@ -96,14 +135,43 @@ async fn drop_unreferenced_datasets(db: &Db) -> SQLxResult<()> {
     .map(|_| ())
 }
 
-async fn drop_unreferenced_dataviews(db: &Db) -> SQLxResult<()> {
+async fn drop_unreferenced_materialized_views(db: &Db) -> SQLxResult<()> {
+    let extant: HashSet<String> = query_scalar::<_, String>(
+        r#"
+        SELECT matviewname::TEXT
+        FROM pg_matviews
+        WHERE schemaname = 'public'
+        "#,
+    )
+    .fetch_all(&db.data)
+    .await?
+    .into_iter()
+    .collect();
+    // dataviews are marked materialized only once their copy is made, so
+    // every dataview's copy is live
     let live: HashSet<String> =
         query_scalar::<_, Uuid>("SELECT uuid FROM dataviews")
             .fetch_all(&db.meta)
             .await?
             .iter()
-            .map(dataview_view_name)
+            .map(materialized_view_name)
             .collect();
+    let to_drop: Vec<String> = extant.difference(&live).cloned().collect();
+    if to_drop.is_empty() {
+        return Ok(());
+    }
+    query(&format!(
+        "DROP MATERIALIZED VIEW IF EXISTS {} CASCADE",
+        to_drop.join(",")
+    ))
+    .execute(&db.data)
+    .await
+    .map(|_| ())
+}
+
+// dropping with CASCADE also drops the views of dataviews that read from what
+// was dropped, so they're failed rather than left completed without a view
+async fn fail_dataviews_without_views(db: &Db) -> SQLxResult<()> {
     let extant: HashSet<String> = query_scalar::<_, String>(
         r#"
         SELECT table_name
@@ -116,12 +184,41 @@ async fn drop_unreferenced_dataviews(db: &Db) -> SQLxResult<()> {
     .await?
     .into_iter()
     .collect();
-    let to_drop: Vec<String> = extant.difference(&live).cloned().collect();
-    query(&format!(
-        "DROP VIEW IF EXISTS {} CASCADE",
-        to_drop.join(",")
-    ))
-    .execute(&db.data)
+    let missing: Vec<Uuid> = query_scalar::<_, Uuid>(
+        "SELECT uuid FROM dataviews WHERE status = 'completed'",
+    )
+    .fetch_all(&db.meta)
+    .await?
+    .into_iter()
+    .filter(|uuid| !extant.contains(&dataview_view_name(uuid)))
+    .collect();
+    query("UPDATE dataviews SET status = 'failed' WHERE uuid = ANY($1)")
+        .bind(&missing)
+        .execute(&db.meta)
+        .await
+        .map(|_| ())
+}
+
+// ingest fails its dataset unless it can't reach the meta db, so datasets
+// still queued or running without a job, after longer than the maximum lambda
+// timeout of 15 minutes, were abandoned
+async fn fail_abandoned_datasets(db: &Db) -> SQLxResult<()> {
+    query(
+        r#"
+        UPDATE datasets d
+        SET status = 'failed'
+        WHERE d.status IN ('queued', 'running')
+        AND d.updated_at < NOW() - INTERVAL '20 minutes'
+        AND NOT EXISTS (
+            SELECT 1
+            FROM jobs j
+            WHERE j.kind = 'upload_dataset'
+            AND j.status IN ('queued', 'running')
+            AND j.payload->>'uuid' = CAST(d.uuid AS TEXT)
+        )
+        "#,
+    )
+    .execute(&db.meta)
     .await
     .map(|_| ())
 }
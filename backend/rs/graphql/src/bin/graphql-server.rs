@@ -0,0 +1,131 @@
+use async_graphql::{
+    http::{playground_source, GraphQLPlaygroundConfig},
+    Data, Request as GQLRequest,
+};
+use async_graphql_warp::graphql_subscription_with_data;
+use graphql::{
+    auth::user_from_authorization_header, respond,
+    subscription::ConnectionInit, ContextData, GenericError, MotokoSchema,
+    Mutation, Query, Subscription,
+};
+use std::{convert::Infallible, env, net::SocketAddr};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_compat_02::FutureExt;
+use warp::{Filter, Rejection, Reply};
+
+#[tokio::main]
+async fn main() -> Result<(), GenericError> {
+    let ctx = ContextData::default().compat().await?;
+    let addr: SocketAddr = env::var("MOTOKO_ADDR")
+        .unwrap_or("127.0.0.1:8000".to_owned())
+        .parse()?;
+    let (addr, server) = warp::serve(routes(ctx.clone()))
+        .try_bind_with_graceful_shutdown(addr, shutdown_signal())?;
+    eprintln!("serving graphql at http://{}/graphql", addr);
+    server.await;
+    ctx.db.meta.close().compat().await;
+    ctx.db.data.close().compat().await;
+    Ok(())
+}
+
+fn routes(
+    ctx: ContextData,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let schema = MotokoSchema::build(Query, Mutation, Subscription)
+        .data(ctx.clone())
+        .finish();
+    let subscriptions = warp::path("graphql").and(
+        graphql_subscription_with_data(schema, |payload| {
+            let mut data = Data::default();
+            data.insert(ConnectionInit(payload));
+            Ok(data)
+        }),
+    );
+    let playground = warp::get()
+        .and(warp::path("graphql"))
+        .and(warp::path::end())
+        .map(|| {
+            warp::reply::html(playground_source(
+                GraphQLPlaygroundConfig::new("/graphql")
+                    .subscription_endpoint("/graphql"),
+            ))
+        });
+    let graphql = warp::post()
+        .and(warp::path("graphql"))
+        .and(warp::path::end())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and_then(move |auth_header: Option<String>, req: GQLRequest| {
+            let ctx = ctx.clone();
+            async move {
+                let res = handler(ctx, auth_header, req).compat().await;
+                Ok::<_, Infallible>(res)
+            }
+        });
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_headers(vec!["authorization", "content-type"])
+        .allow_methods(vec!["GET", "POST"]);
+    subscriptions.or(playground).or(graphql).with(cors)
+}
+
+async fn handler(
+    mut ctx: ContextData,
+    auth_header: Option<String>,
+    req: GQLRequest,
+) -> warp::reply::Json {
+    ctx.user = user_from_authorization_header(
+        auth_header.as_deref(),
+        &ctx.auth.jwt_secret,
+        &ctx.db,
+    )
+    .await;
+    let gql_res = respond(req, &ctx).await;
+    warp::reply::json(&gql_res)
+}
+
+async fn shutdown_signal() {
+    let mut sigterm =
+        signal(SignalKind::terminate()).expect("unable to listen for SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = sigterm.recv() => {},
+    }
+    eprintln!("shutting down");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::Response;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn get_playground() -> Result<(), GenericError> {
+        env::set_var("RUN_MODE", "local");
+        let ctx = ContextData::default().compat().await?;
+        let res = warp::test::request()
+            .method("GET")
+            .path("/graphql")
+            .reply(&routes(ctx))
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn me_not_logged_in() -> Result<(), GenericError> {
+        env::set_var("RUN_MODE", "local");
+        let ctx = ContextData::default().compat().await?;
+        let res = warp::test::request()
+            .method("POST")
+            .path("/graphql")
+            .json(&serde_json::json!({"query": "{ me { id } }"}))
+            .reply(&routes(ctx))
+            .await;
+        let gql_res: Response = serde_json::from_slice(res.body())?;
+        assert!(!gql_res.errors.is_empty());
+        Ok(())
+    }
+}
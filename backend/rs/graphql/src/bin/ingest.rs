@@ -0,0 +1,46 @@
+use graphql::{
+    ingest::ingest, types::UploadDatasetPayload, utils::run_mode, Db,
+    GenericError, Secrets,
+};
+use lambda_http::lambda::{self, handler_fn, Context};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use tokio_compat_02::FutureExt;
+
+#[tokio::main]
+async fn main() -> Result<(), GenericError> {
+    lambda::run(handler_fn(lambda_handler)).compat().await?;
+    Ok(())
+}
+
+// invoked by job runners with the payload of an upload dataset job
+async fn lambda_handler(
+    payload: UploadDatasetPayload,
+    _: Context,
+) -> Result<Value, GenericError> {
+    // the dataset can't be failed without the meta db, so the garbage
+    // collector fails those left queued when it or the secrets are
+    // unavailable
+    let secrets = match run_mode().as_str() {
+        "local" => Secrets::docker(),
+        _ => Secrets::aws().await?,
+    };
+    // the data db is only connected to by `ingest`, which fails the dataset
+    // when it can't be
+    let db = Db {
+        meta: PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&secrets.meta_db_url)
+            .compat()
+            .await?,
+        data: PgPoolOptions::new()
+            .max_connections(1)
+            .connect_lazy(&secrets.data_db_url)?,
+    };
+    let res =
+        ingest(&db, &secrets, &payload.uuid, &payload.uri, payload.format)
+            .await;
+    db.meta.close().await;
+    db.data.close().await;
+    Ok(json!({ "nRows": res? }))
+}
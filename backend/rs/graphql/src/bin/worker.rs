@@ -0,0 +1,147 @@
+use futures::future::join_all;
+use graphql::{
+    jobs::{job_executor, JobRunner},
+    models::{Job, Status},
+    utils::run_mode,
+    Db, GenericError, Secrets,
+};
+use rusoto_core::Region;
+use sqlx::postgres::PgPoolOptions;
+use std::{env, process, time::Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+    time::sleep,
+};
+use tokio_compat_02::FutureExt;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// longer than the maximum lambda timeout of 15 minutes
+const STALE_JOB_TIMEOUT_SECONDS: i64 = 20 * 60;
+
+#[tokio::main]
+async fn main() -> Result<(), GenericError> {
+    let (region, secrets) = match run_mode().as_str() {
+        "local" => (
+            Region::Custom {
+                name: "us-west-1".to_owned(),
+                endpoint: "http://127.0.0.1:3001".to_owned(),
+            },
+            Secrets::local(),
+        ),
+        _ => (Region::UsWest1, Secrets::aws().await?),
+    };
+    let n_workers = env_or("MOTOKO_WORKERS", 4);
+    let project_concurrency = env_or("MOTOKO_PROJECT_CONCURRENCY", 2);
+    let db = Db {
+        meta: PgPoolOptions::new()
+            .max_connections(n_workers as u32 + 1)
+            .connect(&secrets.meta_db_url)
+            .compat()
+            .await?,
+        data: PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&secrets.data_db_url)
+            .compat()
+            .await?,
+    };
+    let runner = job_executor(region, &secrets, &db);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    });
+    let workers = (0..n_workers).map(|i| {
+        work(
+            &db,
+            runner.as_ref(),
+            format!("worker-{}-{}", process::id(), i),
+            project_concurrency,
+            shutdown_rx.clone(),
+        )
+    });
+    join_all(workers).compat().await;
+    db.meta.close().compat().await;
+    db.data.close().compat().await;
+    Ok(())
+}
+
+async fn work(
+    db: &Db,
+    runner: &dyn JobRunner,
+    name: String,
+    project_concurrency: i64,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    eprintln!("{} started", name);
+    while !*shutdown.borrow() {
+        match Job::claim(db, &name, project_concurrency).await {
+            Ok(Some(job)) => run(db, runner, &job).await,
+            Ok(None) => {
+                requeue_stale(db).await;
+                tokio::select! {
+                    _ = sleep(POLL_INTERVAL) => {},
+                    _ = shutdown.changed() => {},
+                }
+            }
+            Err(e) => {
+                eprintln!("{} unable to claim job: {}", name, e);
+                sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+    eprintln!("{} stopped", name);
+}
+
+async fn run(db: &Db, runner: &dyn JobRunner, job: &Job) {
+    let res = match job.job_payload() {
+        Ok(payload) => runner.run(payload).await,
+        Err(e) => Err(e),
+    };
+    let updated = match res {
+        Ok(_) => Job::complete(db, &job.uuid).await,
+        Err(e) => {
+            eprintln!("job {} failed: {}", job.uuid, e);
+            Job::fail(db, &job.uuid, &e.to_string()).await
+        }
+    };
+    match updated {
+        Ok(job) if job.status == Status::Failed => fail_target(db, &job).await,
+        Ok(_) => {}
+        Err(e) => eprintln!("unable to update job {}: {}", job.uuid, e),
+    }
+}
+
+async fn requeue_stale(db: &Db) {
+    match Job::requeue_stale(db, STALE_JOB_TIMEOUT_SECONDS).await {
+        Ok(jobs) => {
+            for job in jobs.iter().filter(|j| j.status == Status::Failed) {
+                fail_target(db, job).await;
+            }
+        }
+        Err(e) => eprintln!("unable to requeue stale jobs: {}", e),
+    }
+}
+
+async fn fail_target(db: &Db, job: &Job) {
+    if let Err(e) = job.fail_target(db).await {
+        eprintln!("unable to mark target of job {} failed: {}", job.uuid, e);
+    }
+}
+
+fn env_or(name: &str, default: i64) -> i64 {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+async fn shutdown_signal() {
+    let mut sigterm =
+        signal(SignalKind::terminate()).expect("unable to listen for SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = sigterm.recv() => {},
+    }
+    eprintln!("finishing running jobs before shutting down");
+}
@@ -0,0 +1,271 @@
+use crate::{models::Status, operations::sort::Order, types::Db, Error};
+use async_graphql::{
+    connection::{query, Connection, CursorType, Edge, EmptyFields},
+    Enum, InputObject, OutputValueType, Result as GQLResult,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::PgRow, query as sql_query, FromRow, Result as SQLxResult, Row,
+};
+use uuid::Uuid;
+
+pub const DEFAULT_NODES: usize = 100;
+pub const MAX_NODES: usize = 1000;
+
+pub type Nodes<T> = Connection<NodeCursor, T, EmptyFields, EmptyFields>;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Enum)]
+pub enum NodeOrderField {
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl NodeOrderField {
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::CreatedAt => "created_at",
+            Self::UpdatedAt => "updated_at",
+        }
+    }
+
+    fn data_type(&self) -> &'static str {
+        match self {
+            Self::Name => "TEXT",
+            Self::CreatedAt | Self::UpdatedAt => "TIMESTAMPTZ",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, InputObject)]
+pub struct NodeOrder {
+    pub field: NodeOrderField,
+    pub order: Order,
+}
+
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct NodeFilter {
+    pub status: Option<Status>,
+    // matched case insensitively
+    pub name_contains: Option<String>,
+}
+
+// the order value as postgres text, and the uuid that breaks ties on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeCursor {
+    pub value: String,
+    pub uuid: Uuid,
+}
+
+impl CursorType for NodeCursor {
+    type Error = Error;
+
+    fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+        let invalid =
+            || Error::InvalidArguments(format!("invalid cursor: {}", s));
+        let decoded = base64::decode(s).map_err(|_| invalid())?;
+        serde_json::from_slice(&decoded).map_err(|_| invalid())
+    }
+
+    fn encode_cursor(&self) -> String {
+        base64::encode(serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+// a list query's nodes, selected by `sql` with `binds` as $1, $2, ..., and
+// the optional columns among name and status that its table has
+pub struct NodeQuery<'a> {
+    pub sql: &'a str,
+    pub binds: &'a [Uuid],
+    pub columns: &'a [&'a str],
+}
+
+pub async fn nodes<T>(
+    db: &Db,
+    node_query: NodeQuery<'_>,
+    first: Option<i32>,
+    after: Option<String>,
+    order_by: Option<NodeOrder>,
+    filter: Option<NodeFilter>,
+) -> GQLResult<Nodes<T>>
+where
+    T: for<'r> FromRow<'r, PgRow> + OutputValueType + Send + Sync + Unpin,
+{
+    query(
+        after,
+        None,
+        first,
+        None,
+        |after: Option<NodeCursor>, _, first, _| async move {
+            let first = first.unwrap_or(DEFAULT_NODES).min(MAX_NODES);
+            let order_by = order_by.unwrap_or(NodeOrder {
+                field: NodeOrderField::CreatedAt,
+                order: Order::Ascending,
+            });
+            let filter = filter.unwrap_or_default();
+            let sql = nodes_sql(
+                &node_query,
+                first + 1,
+                after.is_some(),
+                &order_by,
+                &filter,
+            )?;
+            let mut q = sql_query(&sql);
+            for uuid in node_query.binds.iter() {
+                q = q.bind(uuid);
+            }
+            if let Some(status) = filter.status {
+                q = q.bind(status);
+            }
+            if let Some(name) = &filter.name_contains {
+                q = q.bind(escape_like(name));
+            }
+            if let Some(after) = &after {
+                q = q.bind(&after.value).bind(&after.uuid);
+            }
+            let mut rows = q.fetch_all(&db.meta).await?;
+            let has_next = rows.len() > first;
+            rows.truncate(first);
+            let edges = rows
+                .iter()
+                .map(|row| {
+                    let cursor = NodeCursor {
+                        value: row.try_get("__cursor")?,
+                        uuid: row.try_get("uuid")?,
+                    };
+                    Ok(Edge::new(cursor, T::from_row(row)?))
+                })
+                .collect::<SQLxResult<Vec<_>>>()?;
+            let mut connection = Connection::new(after.is_some(), has_next);
+            connection.append(edges);
+            Ok(connection)
+        },
+    )
+    .await
+}
+
+// wraps the list query to filter, order and page by keyset on
+// (order value, uuid), which stays fast and stable as rows are added
+pub fn nodes_sql(
+    node_query: &NodeQuery,
+    limit: usize,
+    after: bool,
+    order_by: &NodeOrder,
+    filter: &NodeFilter,
+) -> Result<String, Error> {
+    let requires =
+        |column: &str, arg: &str| match node_query.columns.contains(&column) {
+            true => Ok(()),
+            false => Err(Error::InvalidArguments(format!(
+                "{} is not supported here",
+                arg
+            ))),
+        };
+    let mut n = node_query.binds.len();
+    let mut conditions = Vec::new();
+    if filter.status.is_some() {
+        requires("status", "status")?;
+        n += 1;
+        conditions.push(format!("n.status = ${}", n));
+    }
+    if filter.name_contains.is_some() {
+        requires("name", "nameContains")?;
+        n += 1;
+        conditions.push(format!("n.name ILIKE '%' || ${} || '%'", n));
+    }
+    let column = order_by.field.column();
+    if order_by.field == NodeOrderField::Name {
+        requires("name", "NAME order")?;
+    }
+    if after {
+        let comparator = match order_by.order {
+            Order::Ascending => ">",
+            Order::Descending => "<",
+        };
+        conditions.push(format!(
+            "(n.{}, n.uuid) {} (CAST(${} AS {}), ${})",
+            column,
+            comparator,
+            n + 1,
+            order_by.field.data_type(),
+            n + 2
+        ));
+    }
+    let condition = match conditions.is_empty() {
+        true => String::new(),
+        false => format!("\nWHERE {}", conditions.join("\nAND ")),
+    };
+    let order = order_by.order.as_sql();
+    Ok(format!(
+        "SELECT n.*, CAST(n.{} AS TEXT) AS \"__cursor\"\n\
+         FROM ({}) n{}\n\
+         ORDER BY n.{} {}, n.uuid {}\n\
+         LIMIT {}",
+        column,
+        node_query.sql.trim(),
+        condition,
+        column,
+        order,
+        order,
+        limit
+    ))
+}
+
+// so that `%`, `_` and `\` in names match literally
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trip() -> Result<(), Error> {
+        let cursor = NodeCursor {
+            value: "2021-02-06 18:00:00.123456+00".to_owned(),
+            uuid: Uuid::new_v4(),
+        };
+        assert_eq!(NodeCursor::decode_cursor(&cursor.encode_cursor())?, cursor);
+        assert!(NodeCursor::decode_cursor("not a cursor").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn nodes_sql_filters_and_pages() -> Result<(), Error> {
+        let node_query = NodeQuery {
+            sql: "SELECT x.* FROM plots x WHERE x.dataview_uuid = $1",
+            binds: &[Uuid::nil()],
+            columns: &["name", "status"],
+        };
+        let order_by = NodeOrder {
+            field: NodeOrderField::Name,
+            order: Order::Descending,
+        };
+        let filter = NodeFilter {
+            status: Some(Status::Completed),
+            name_contains: Some("iris".to_owned()),
+        };
+        assert_eq!(
+            nodes_sql(&node_query, 11, true, &order_by, &filter)?,
+            "SELECT n.*, CAST(n.name AS TEXT) AS \"__cursor\"\n\
+             FROM (SELECT x.* FROM plots x WHERE x.dataview_uuid = $1) n\n\
+             WHERE n.status = $2\n\
+             AND n.name ILIKE '%' || $3 || '%'\n\
+             AND (n.name, n.uuid) < (CAST($4 AS TEXT), $5)\n\
+             ORDER BY n.name DESC, n.uuid DESC\n\
+             LIMIT 11"
+        );
+        let unnamed = NodeQuery {
+            columns: &["status"],
+            ..node_query
+        };
+        let res = nodes_sql(&unnamed, 11, false, &order_by, &filter);
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        assert_eq!(escape_like("a_b%c"), "a\\_b\\%c");
+        Ok(())
+    }
+}
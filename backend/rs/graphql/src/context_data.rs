@@ -1,8 +1,14 @@
-use crate::{models::User, utils::run_mode, Db, GenericError, Secrets};
+use crate::{
+    jobs::{job_runner, JobRunner},
+    models::User,
+    subscription::StatusChanges,
+    utils::run_mode,
+    Db, GenericError, Secrets,
+};
 use rusoto_core::Region;
 use rusoto_credential::AwsCredentials;
-use rusoto_lambda::LambdaClient;
 use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
 use tokio_compat_02::FutureExt;
 
 #[derive(Clone)]
@@ -10,8 +16,9 @@ pub struct ContextData {
     pub user: Option<User>,
     pub db: Db,
     pub auth: Auth,
-    pub lambda: LambdaClient,
+    pub jobs: Arc<dyn JobRunner>,
     pub storage: Storage,
+    pub status_changes: StatusChanges,
 }
 
 #[derive(Debug, Clone)]
@@ -50,7 +57,6 @@ impl ContextData {
             ),
             _ => (Region::UsWest1, Secrets::aws().await?),
         };
-        let lambda = LambdaClient::new(region);
         let meta_db = PgPoolOptions::new()
             .max_connections(5)
             .connect(&secrets.meta_db_url)
@@ -61,6 +67,12 @@ impl ContextData {
             .connect(&secrets.data_db_url)
             .compat()
             .await?;
+        let db = Db {
+            meta: meta_db,
+            data: data_db,
+        };
+        let jobs = job_runner(region, &secrets, &db);
+        let status_changes = StatusChanges::new(&db);
         let client_ids = OAuth2ClientIds {
             google: GoogleOAuthClientIds {
                 android: secrets.google_oauth2_client_id_android,
@@ -69,10 +81,7 @@ impl ContextData {
         };
         Ok(Self {
             user: None,
-            db: Db {
-                meta: meta_db,
-                data: data_db,
-            },
+            db,
             auth: Auth {
                 aws_credentials: AwsCredentials::new(
                     secrets.aws_access_key_id,
@@ -83,11 +92,12 @@ impl ContextData {
                 jwt_secret: secrets.jwt_secret.clone(),
                 client_ids,
             },
-            lambda,
+            jobs,
             storage: Storage {
                 region: Region::UsWest1,
                 bucket: "motoko-data".to_owned(),
             },
+            status_changes,
         })
     }
 }
@@ -5,6 +5,7 @@ use std::fmt;
 #[derive(Debug, thiserror::Error, Serialize, Deserialize)]
 pub enum Error {
     BadRequest,
+    InvalidArguments(String),
     InvalidGraphQLID,
     InvalidIDToken(String),
     InvalidPermissions,
@@ -12,6 +13,7 @@ pub enum Error {
     RequiresEditorPermissions,
     ResultUnavailable(Status),
     Serde,
+    Timeout(String),
     UnsupportedOperation,
 }
 
@@ -19,6 +21,9 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let v = match self {
             Error::BadRequest => "Bad Request".into(),
+            Error::InvalidArguments(msg) => {
+                format!("Invalid Arguments: {}", msg)
+            }
             Error::InvalidGraphQLID => "Invalid GraphQL ID".into(),
             Error::InvalidIDToken(msg) => format!("Invalid ID Token: {}", msg),
             Error::InvalidPermissions => "Invalid Permissions".into(),
@@ -32,6 +37,7 @@ impl fmt::Display for Error {
                 format!("Result unavailable; status: {:?}", status)
             }
             Error::Serde => "Error (de)serializing".into(),
+            Error::Timeout(msg) => format!("Timeout: {}", msg),
             Error::UnsupportedOperation => "Unsupported Operation".into(),
         };
         write!(f, "{}", &v)
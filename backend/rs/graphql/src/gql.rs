@@ -1,16 +1,18 @@
 use crate::{
-    models::User, utils::run_mode, ContextData, Error, ModelKeys, Mutation,
-    Query,
+    loaders::Loaders, models::User, utils::run_mode, ContextData, Error,
+    ModelKeys, Mutation, Query, Subscription,
 };
 use async_graphql::{
-    from_value, Context, EmptySubscription, Error as GQLError,
-    Request as GQLRequest, Response as GQLResponse, Result as GQLResult,
-    Schema, Value as GQLValue, ID,
+    from_value, Context, Error as GQLError, Request as GQLRequest,
+    Response as GQLResponse, Result as GQLResult, Schema, Value as GQLValue,
+    ID,
 };
 use serde::de::DeserializeOwned;
 use std::str;
 use uuid::Uuid;
 
+pub type MotokoSchema = Schema<Query, Mutation, Subscription>;
+
 pub fn current_user<'ctx>(ctx: &'ctx Context<'_>) -> GQLResult<&'ctx User> {
     let d = data(ctx)?;
     match &d.user {
@@ -81,27 +83,165 @@ pub fn model_keys(id: &ID) -> GQLResult<ModelKeys> {
     })
 }
 
+pub fn node_id(model: &str, uuid: &Uuid) -> ID {
+    base64::encode(format!("{}:{}", model, uuid)).into()
+}
+
 pub async fn respond(req: GQLRequest, ctx: &ContextData) -> GQLResponse {
-    Schema::new(Query, Mutation, EmptySubscription)
-        .execute(req.data(ctx.clone()))
-        .await
+    respond_with_loaders(req, ctx, Loaders::new(&ctx.db)).await
+}
+
+pub async fn respond_with_loaders(
+    req: GQLRequest,
+    ctx: &ContextData,
+    loaders: Loaders,
+) -> GQLResponse {
+    schema().execute(req.data(ctx.clone()).data(loaders)).await
+}
+
+pub fn schema() -> MotokoSchema {
+    Schema::new(Query, Mutation, Subscription)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        ingest::format::DatasetFormat,
+        jobs::{JobPayload, RecordingRunner},
         models::{Status, User},
         queries::*,
+        utils::dataset_table_name,
         GenericError,
     };
     use async_graphql::{value as v, Result as GQLResult};
     use rusoto_core::Region;
     use rusoto_lambda::{InvocationRequest, Lambda, LambdaClient};
     use sqlx::{query, Result as SQLxResult};
-    use std::{env, process::Command, thread, time};
+    use std::{
+        env, process::Command, sync::atomic::Ordering, sync::Arc, thread, time,
+    };
     use tokio_compat_02::FutureExt;
 
+    #[tokio::test]
+    async fn create_dataset_runs_upload_job() -> GQLResult<()> {
+        env::set_var("RUN_MODE", "local");
+        _create_dataset_runs_upload_job().compat().await
+    }
+
+    async fn _create_dataset_runs_upload_job() -> GQLResult<()> {
+        let mut ctx = test_ctx().await?;
+        let jobs = Arc::new(RecordingRunner::default());
+        ctx.jobs = jobs.clone();
+        let res =
+            respond(create_project(&v!({"name": "Test Project"})), &ctx).await;
+        let project: ProjectResponse = from_response(res)?;
+        let uri = "https://example.com/iris.csv";
+        let res = respond(
+            create_dataset(&v!({
+                "projectId": &project.id.clone(),
+                "name": "iris",
+                "uri": uri,
+                "format": "CSV",
+            })),
+            &ctx,
+        )
+        .await;
+        let dataset: DatasetResponse = from_response(res)?;
+        assert_eq!(dataset.status, Status::Queued);
+        let payloads = jobs.take();
+        assert_eq!(payloads.len(), 1);
+        match &payloads[0] {
+            JobPayload::UploadDataset(p) => {
+                assert_eq!(p.uri, uri);
+                assert_eq!(p.format, Some(DatasetFormat::Csv));
+                assert_eq!(node_id("Dataset", &p.uuid).to_string(), dataset.id);
+            }
+            _ => return Err(GQLError::new("expected an upload dataset job")),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn references_block_deletion() -> GQLResult<()> {
+        env::set_var("RUN_MODE", "local");
+        _references_block_deletion().compat().await
+    }
+
+    async fn _references_block_deletion() -> GQLResult<()> {
+        let mut ctx = test_ctx().await?;
+        ctx.jobs = Arc::new(RecordingRunner::default());
+        let res =
+            respond(create_project(&v!({"name": "Test Project"})), &ctx).await;
+        let project: ProjectResponse = from_response(res)?;
+        let mut datasets = Vec::new();
+        for name in &["left", "right"] {
+            let res = respond(
+                create_dataset(&v!({
+                    "projectId": &project.id.clone(),
+                    "name": name,
+                    "uri": "https://example.com/data.csv",
+                })),
+                &ctx,
+            )
+            .await;
+            let dataset: DatasetResponse = from_response(res)?;
+            // stands in for the upload job
+            let uuid = graphql_id_to_uuid(&ID::from(&dataset.id))?;
+            query(&format!(
+                "CREATE TABLE {} (id BIGINT)",
+                dataset_table_name(&uuid)
+            ))
+            .execute(&ctx.db.data)
+            .await?;
+            datasets.push(dataset);
+        }
+        // the left dataset's analysis joins the right, and the right's
+        // appends the left
+        let (left, right) = (&datasets[0].id, &datasets[1].id);
+        let mut analyses = Vec::new();
+        for (dataset, args) in vec![
+            (
+                left,
+                v!({"join": {
+                    "with": right,
+                    "how": "INNER",
+                    "on": [{"left": "id", "right": "id"}],
+                }}),
+            ),
+            (right, v!({"union": {"with": [left], "by": "NAME"}})),
+        ] {
+            let res = respond(
+                create_analysis(&v!({"datasetId": dataset, "name": "a"})),
+                &ctx,
+            )
+            .await;
+            let analysis: AnalysisResponse = from_response(res)?;
+            let res = respond(
+                create_dataview(&v!({
+                    "analysisId": &analysis.id.clone(),
+                    "args": args,
+                })),
+                &ctx,
+            )
+            .await;
+            let dv: DataviewResponse = from_response(res)?;
+            assert_eq!(dv.status, Status::Completed);
+            analyses.push(analysis);
+        }
+        let delete = |id: &str| v!({ "id": id });
+        assert!(respond(delete_node(&delete(right)), &ctx).await.is_err());
+        assert!(respond(delete_node(&delete(left)), &ctx).await.is_err());
+        let res = respond(delete_node(&delete(&analyses[0].id)), &ctx).await;
+        assert!(res.is_ok());
+        assert!(respond(delete_node(&delete(left)), &ctx).await.is_err());
+        let res = respond(delete_node(&delete(&analyses[1].id)), &ctx).await;
+        assert!(res.is_ok());
+        assert!(respond(delete_node(&delete(left)), &ctx).await.is_ok());
+        assert!(respond(delete_node(&delete(right)), &ctx).await.is_ok());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn graphql_round_trip() -> GQLResult<()> {
         let mut sam_process = sam_local_start_lambda();
@@ -216,14 +356,16 @@ mod tests {
         res = respond(
             create_dataview(&v!({
                 "analysisId": &analysis.id.clone(),
-                "operation": "SELECT",
-                "args":
-                r#"{"columns": [
-                    "sepal_width",
-                    "sepal_length",
-                    "petal_width",
-                    "species"
-                ]}"#,
+                "args": {
+                    "select": {
+                        "columns": [
+                            "sepal_width",
+                            "sepal_length",
+                            "petal_width",
+                            "species"
+                        ]
+                    }
+                },
             })),
             &ctx,
         )
@@ -235,30 +377,33 @@ mod tests {
         if s.status != Status::Completed {
             return Err(GQLError::new("failed to create dataview - select"));
         }
+        // every species, before the filter keeps only versicolor
+        let selected_id = dv.id.clone();
         eprintln!("create dataview - filter");
         res = respond(
             create_dataview(&v!({
                 "analysisId": &analysis.id.clone(),
-                "operation": "FILTER",
-                "args": r#"{
-                    "filters": [
-                        {
-                            "column": "sepal_length",
-                            "comparator": ">",
-                            "value": "1"
-                        },
-                        {
-                            "column": "petal_width",
-                            "comparator": "<=",
-                            "value": "5"
-                        },
-                        {
-                            "column": "species",
-                            "comparator": "=",
-                            "value": "versicolor"
-                        }
-                    ]
-                }"#,
+                "args": {
+                    "filter": {
+                        "filters": [
+                            {
+                                "column": "sepal_length",
+                                "comparator": "GT",
+                                "value": "1"
+                            },
+                            {
+                                "column": "petal_width",
+                                "comparator": "LE",
+                                "value": "5"
+                            },
+                            {
+                                "column": "species",
+                                "comparator": "EQ",
+                                "value": "versicolor"
+                            }
+                        ]
+                    }
+                },
             })),
             &ctx,
         )
@@ -275,23 +420,24 @@ mod tests {
         res = respond(
             create_dataview(&v!({
                 "analysisId": &analysis.id.clone(),
-                "operation": "SORT",
-                "args": r#"{
-                    "sorts": [
-                        {
-                            "column": "sepal_length",
-                            "order": "ASCENDING"
-                        },
-                        {
-                            "column": "petal_width",
-                            "order": "DESCENDING"
-                        },
-                        {
-                            "column": "species",
-                            "order": "ASCENDING"
-                        }
-                    ]
-                }"#,
+                "args": {
+                    "sort": {
+                        "sorts": [
+                            {
+                                "column": "sepal_length",
+                                "order": "ASCENDING"
+                            },
+                            {
+                                "column": "petal_width",
+                                "order": "DESCENDING"
+                            },
+                            {
+                                "column": "species",
+                                "order": "ASCENDING"
+                            }
+                        ]
+                    }
+                },
             })),
             &ctx,
         )
@@ -304,42 +450,155 @@ mod tests {
             return Err(GQLError::new("failed to create dataview - sort"));
         }
 
-        eprintln!("create dataview - summarize");
+        eprintln!("create dataview - mutate");
         res = respond(
             create_dataview(&v!({
                 "analysisId": &analysis.id.clone(),
-                "operation": "SUMMARIZE",
-                "args": r#"{
-                    "summaries": [
-                        {
-                            "column": "sepal_length",
-                            "summarizer": "MEAN"
-                        },
-                        {
-                            "column": "sepal_length",
-                            "summarizer": "MIN"
-                        },
-                        {
-                            "column": "sepal_length",
-                            "summarizer": "MAX"
-                        },
-                        {
-                            "column": "sepal_length",
-                            "summarizer": "STDDEV"
-                        },
-                        {
-                            "column": "petal_width",
-                            "summarizer": "MEDIAN"
-                        },
+                "args": {
+                    "mutate": {
+                        "mutations": [
+                            {
+                                "column": "sepal_ratio",
+                                "expression": "sepal_length / sepal_width"
+                            },
+                            {
+                                "column": "species",
+                                "expression": "upper(species)"
+                            }
+                        ]
+                    }
+                },
+                "materialize": true,
+            })),
+            &ctx,
+        )
+        .await;
+        dv = from_response::<DataviewResponse>(res)?;
+        res = respond(status(&v!({"id": &dv.id.clone()})), &ctx).await;
+        s = from_response::<StatusResponse>(res)?;
+        if s.status != Status::Completed || !dv.materialized {
+            return Err(GQLError::new("failed to create dataview - mutate"));
+        }
+
+        eprintln!("refresh dataview");
+        res = respond(
+            refresh_dataview(&v!({"dataviewId": &dv.id.clone()})),
+            &ctx,
+        )
+        .await;
+        let refreshed = from_response::<DataviewResponse>(res)?;
+        if refreshed.refreshed_at <= dv.refreshed_at {
+            return Err(GQLError::new("failed to refresh dataview"));
+        }
+
+        eprintln!("page dataview rows");
+        let mut n_rows = 0;
+        let mut after: Option<String> = None;
+        loop {
+            res = respond(
+                dataview_rows(&v!({
+                    "id": &dv.id.clone(),
+                    "first": 100,
+                    "after": after.clone(),
+                    "orderBy": [
                         {
-                            "column": "species",
-                            "summarizer": "MODE"
+                            "column": "sepal_ratio",
+                            "order": "DESCENDING"
                         }
                     ],
-                    "groupBys": [
-                        "sepal_length"
-                    ]
-                }"#,
+                })),
+                &ctx,
+            )
+            .await;
+            let page = from_response::<DataviewRowsResponse>(res)?.rows;
+            n_rows += page.edges.len();
+            if !page.page_info.has_next_page {
+                break;
+            }
+            after = page.page_info.end_cursor;
+        }
+        // only versicolor passes the filter
+        if n_rows != 50 {
+            return Err(GQLError::new("failed to page dataview rows"));
+        }
+
+        eprintln!("sample dataview rows");
+        res = respond(
+            dataview_sample_rows(&v!({
+                "id": &selected_id,
+                "n": 6,
+                "strategy": "STRATIFIED",
+                "seed": 1,
+                "column": "species",
+            })),
+            &ctx,
+        )
+        .await;
+        let sample = from_response::<SampleRowsResponse>(res)?.sample_rows;
+        let mut species: Vec<String> =
+            sample.iter().map(|r| r["species"].to_string()).collect();
+        species.sort();
+        species.dedup();
+        if sample.len() != 6 || species.len() != 3 {
+            return Err(GQLError::new("failed to sample dataview rows"));
+        }
+
+        eprintln!("profile dataview column");
+        res = respond(
+            dataview_column_profile(&v!({
+                "id": &dv.id.clone(),
+                "column": "sepal_length",
+                "bins": 5,
+            })),
+            &ctx,
+        )
+        .await;
+        let p = from_response::<ColumnProfileResponse>(res)?.column_profile;
+        if p.n_rows != 50
+            || p.null_count != 0
+            || p.mean.is_none()
+            || p.histogram.len() != 5
+        {
+            return Err(GQLError::new("failed to profile dataview column"));
+        }
+
+        eprintln!("create dataview - summarize");
+        res = respond(
+            create_dataview(&v!({
+                "analysisId": &analysis.id.clone(),
+                "args": {
+                    "summarize": {
+                        "summaries": [
+                            {
+                                "column": "sepal_length",
+                                "summarizer": "MEAN"
+                            },
+                            {
+                                "column": "sepal_length",
+                                "summarizer": "MIN"
+                            },
+                            {
+                                "column": "sepal_length",
+                                "summarizer": "MAX"
+                            },
+                            {
+                                "column": "sepal_length",
+                                "summarizer": "STDDEV"
+                            },
+                            {
+                                "column": "petal_width",
+                                "summarizer": "MEDIAN"
+                            },
+                            {
+                                "column": "species",
+                                "summarizer": "MODE"
+                            }
+                        ],
+                        "groupBys": [
+                            "sepal_length"
+                        ]
+                    }
+                },
             })),
             &ctx,
         )
@@ -352,12 +611,31 @@ mod tests {
             return Err(GQLError::new("failed to create dataview - summarize"));
         }
 
+        eprintln!("load analysis page");
+        let loaders = Loaders::new(&ctx.db);
+        let queries = loaders.queries.clone();
+        res = respond_with_loaders(
+            analysis_dataviews(&v!({"analysisId": &analysis.id.clone()})),
+            &ctx,
+            loaders,
+        )
+        .await;
+        // one batch each for the dataviews' analysis, its dataset, and the
+        // dataviews' parents, however many dataviews there are
+        if !res.errors.is_empty() || queries.load(Ordering::Relaxed) != 3 {
+            return Err(GQLError::new("failed to batch analysis page loads"));
+        }
+
         eprintln!("create statistic - correlation");
         res = respond(
             create_statistic(&v!({
                 "dataviewId": &analysis.dataview.id.clone(),
-                "type": "CORRELATION",
-                "args": r#"{"x": "sepal_width", "y": "petal_width"}"#,
+                "args": {
+                    "correlation": {
+                        "x": "sepal_width",
+                        "y": "petal_width"
+                    }
+                },
             })),
             &ctx,
         )
@@ -376,8 +654,11 @@ mod tests {
         res = respond(
             create_statistic(&v!({
                 "dataviewId": &analysis.dataview.id.clone(),
-                "type": "SUMMARY",
-                "args": r#"{"x": "sepal_width"}"#,
+                "args": {
+                    "summary": {
+                        "x": "sepal_width"
+                    }
+                },
             })),
             &ctx,
         )
@@ -395,12 +676,13 @@ mod tests {
             create_plot(&v!({
                 "dataviewId": &analysis.dataview.id.clone(),
                 "name": "bar",
-                "type": "BAR",
-                "args": r#"{
-                    "x": "species",
-                    "color": "species",
-                    "title": "Species"
-                }"#,
+                "args": {
+                    "bar": {
+                        "x": "species",
+                        "color": "species",
+                        "title": "Species"
+                    }
+                },
             })),
             &ctx,
         )
@@ -418,8 +700,11 @@ mod tests {
             create_plot(&v!({
                 "dataviewId": &analysis.dataview.id.clone(),
                 "name": "histogram",
-                "type": "HISTOGRAM",
-                "args": r#"{"x": "sepal_width"}"#,
+                "args": {
+                    "histogram": {
+                        "x": "sepal_width"
+                    }
+                },
             })),
             &ctx,
         )
@@ -437,13 +722,14 @@ mod tests {
             create_plot(&v!({
                 "dataviewId": &analysis.dataview.id.clone(),
                 "name": "histogram",
-                "type": "LINE",
-                "args": r#"{
-                    "x": "sepal_width",
-                    "y": "petal_width",
-                    "title": "Sepal vs. Petal Width",
-                    "color": "species"
-                }"#,
+                "args": {
+                    "line": {
+                        "x": "sepal_width",
+                        "y": "petal_width",
+                        "title": "Sepal vs. Petal Width",
+                        "color": "species"
+                    }
+                },
             })),
             &ctx,
         )
@@ -461,14 +747,16 @@ mod tests {
             create_plot(&v!({
                 "dataviewId": &analysis.dataview.id.clone(),
                 "name": "scatter",
-                "type": "SCATTER",
-                "args": r#"{
-                    "x": "sepal_width",
-                    "y": "petal_width",
-                    "title": "Sepal vs. Petal Width",
-                    "color": "species",
-                    "shape": "species"
-                }"#,
+                "args": {
+                    "scatter": {
+                        "x": "sepal_width",
+                        "y": "petal_width",
+                        "title": "Sepal vs. Petal Width",
+                        "color": "species",
+                        "shape": "species",
+                        "facetX": "species"
+                    }
+                },
             })),
             &ctx,
         )
@@ -486,14 +774,15 @@ mod tests {
             create_plot(&v!({
                 "dataviewId": &analysis.dataview.id.clone(),
                 "name": "smooth plot",
-                "type": "SMOOTH",
-                "args": r#"{
-                    "x": "sepal_width",
-                    "y": "petal_width",
-                    "title": "Sepal vs. Petal Width",
-                    "color": "species",
-                    "shape": "species"
-                }"#,
+                "args": {
+                    "smooth": {
+                        "x": "sepal_width",
+                        "y": "petal_width",
+                        "title": "Sepal vs. Petal Width",
+                        "color": "species",
+                        "shape": "species"
+                    }
+                },
             })),
             &ctx,
         )
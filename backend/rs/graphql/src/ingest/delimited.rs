@@ -0,0 +1,300 @@
+use crate::{
+    ingest::{
+        infer::{has_header, infer},
+        Layout, Parser, Source,
+    },
+    GenericError,
+};
+use csv_core::{ReadRecordResult, Reader, ReaderBuilder};
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_8, WINDOWS_1252};
+
+// tried in order, so commas win ties
+pub const DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+// records sniffed for a consistent number of fields
+const SNIFF_RECORDS: usize = 100;
+
+// the layout of a delimited file, sniffing the delimiter unless it's given
+pub fn sniff(
+    sample: &[u8],
+    complete: bool,
+    delimiter: Option<u8>,
+) -> Result<Layout, GenericError> {
+    let encoding = sniff_encoding(sample);
+    let mut text = String::new();
+    let mut decoder = encoding.new_decoder_with_bom_removal();
+    decode(&mut decoder, sample, &mut text, complete);
+    let delimiter = delimiter.unwrap_or_else(|| sniff_delimiter(&text));
+    let mut parser = RecordParser::new(delimiter);
+    let mut records = Vec::new();
+    parser.push(text.as_bytes(), &mut records)?;
+    if complete {
+        parser.finish(&mut records)?;
+    }
+    let n_columns = records.iter().map(|r| r.len()).max().unwrap_or(0);
+    let has_header = has_header(&records);
+    let (header, rows) = match has_header {
+        true => (Some(records[0].as_slice()), &records[1..]),
+        false => (None, records.as_slice()),
+    };
+    let source = Source::Delimited {
+        encoding,
+        delimiter,
+    };
+    Ok(Layout::new(
+        source,
+        has_header,
+        header,
+        infer(rows, n_columns),
+    ))
+}
+
+// the encoding named by a byte order mark, else utf-8 when the sample is
+// valid utf-8, else windows-1252, which decodes any bytes
+pub fn sniff_encoding(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        // the sample may end part way through a character
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+// the delimiter that splits the most records into the most fields, where
+// every record has the same number of fields
+pub fn sniff_delimiter(text: &str) -> u8 {
+    DELIMITERS
+        .iter()
+        .filter_map(|d| {
+            let mut parser = RecordParser::new(*d);
+            let mut records = Vec::new();
+            parser.push(text.as_bytes(), &mut records).ok()?;
+            let counts: Vec<usize> = records
+                .iter()
+                .take(SNIFF_RECORDS)
+                .map(|r| r.len())
+                .collect();
+            match counts.first() {
+                Some(n) if *n > 1 && counts.iter().all(|c| c == n) => {
+                    Some((counts.len(), *n, *d))
+                }
+                _ => None,
+            }
+        })
+        // max_by_key keeps the last maximum
+        .rev()
+        .max_by_key(|(records, fields, _)| (*records, *fields))
+        .map(|(_, _, d)| d)
+        .unwrap_or(b',')
+}
+
+// decodes `src` onto `dst`, holding back partial characters until the next
+// chunk unless `last`
+pub fn decode(decoder: &mut Decoder, src: &[u8], dst: &mut String, last: bool) {
+    let mut src = src;
+    loop {
+        if let Some(n) = decoder.max_utf8_buffer_length(src.len()) {
+            dst.reserve(n);
+        }
+        let (res, read, _) = decoder.decode_to_string(src, dst, last);
+        src = &src[read..];
+        if res == CoderResult::InputEmpty {
+            break;
+        }
+    }
+}
+
+// decodes chunks of a delimited file and parses its records
+pub struct DelimitedParser {
+    decoder: Decoder,
+    records: RecordParser,
+    text: String,
+}
+
+impl DelimitedParser {
+    pub fn new(encoding: &'static Encoding, delimiter: u8) -> Self {
+        Self {
+            decoder: encoding.new_decoder_with_bom_removal(),
+            records: RecordParser::new(delimiter),
+            text: String::new(),
+        }
+    }
+}
+
+impl Parser for DelimitedParser {
+    fn push(
+        &mut self,
+        chunk: &[u8],
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError> {
+        decode(&mut self.decoder, chunk, &mut self.text, false);
+        // a chunk within a character decodes to nothing, which the record
+        // parser would take as the end of the text
+        if !self.text.is_empty() {
+            self.records.push(self.text.as_bytes(), records)?;
+            self.text.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError> {
+        decode(&mut self.decoder, &[], &mut self.text, true);
+        self.records.push(self.text.as_bytes(), records)?;
+        self.text.clear();
+        self.records.finish(records)
+    }
+}
+
+// parses records from text pushed to it in chunks, so files are never held
+// in memory whole
+pub struct RecordParser {
+    reader: Reader,
+    output: Vec<u8>,
+    ends: Vec<usize>,
+    output_len: usize,
+    ends_len: usize,
+}
+
+impl RecordParser {
+    pub fn new(delimiter: u8) -> Self {
+        Self {
+            reader: ReaderBuilder::new().delimiter(delimiter).build(),
+            output: vec![0; 1024],
+            ends: vec![0; 16],
+            output_len: 0,
+            ends_len: 0,
+        }
+    }
+
+    // appends the records completed by `input`; the rest of a partial record
+    // is kept for the next push
+    pub fn push(
+        &mut self,
+        input: &[u8],
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError> {
+        self.read(input, records, false)
+    }
+
+    // appends the last record, which needn't end in a newline
+    pub fn finish(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError> {
+        self.read(&[], records, true)
+    }
+
+    fn read(
+        &mut self,
+        mut input: &[u8],
+        records: &mut Vec<Vec<String>>,
+        last: bool,
+    ) -> Result<(), GenericError> {
+        loop {
+            let (res, read, written, ended) = self.reader.read_record(
+                input,
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            input = &input[read..];
+            self.output_len += written;
+            self.ends_len += ended;
+            match res {
+                ReadRecordResult::InputEmpty if !last => return Ok(()),
+                // an empty input signals the end of the text
+                ReadRecordResult::InputEmpty => {}
+                ReadRecordResult::OutputFull => {
+                    let len = self.output.len();
+                    self.output.resize(len * 2, 0);
+                }
+                ReadRecordResult::OutputEndsFull => {
+                    let len = self.ends.len();
+                    self.ends.resize(len * 2, 0);
+                }
+                ReadRecordResult::Record => records.push(self.record()?),
+                ReadRecordResult::End => return Ok(()),
+            }
+        }
+    }
+
+    fn record(&mut self) -> Result<Vec<String>, GenericError> {
+        let mut start = 0;
+        let fields = self.ends[..self.ends_len]
+            .iter()
+            .map(|end| {
+                let field = std::str::from_utf8(&self.output[start..*end])
+                    .map(|f| f.to_owned());
+                start = *end;
+                field
+            })
+            .collect::<Result<_, _>>()?;
+        self.output_len = 0;
+        self.ends_len = 0;
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&str], delimiter: u8) -> Vec<Vec<String>> {
+        let mut parser = RecordParser::new(delimiter);
+        let mut records = Vec::new();
+        for chunk in chunks {
+            parser.push(chunk.as_bytes(), &mut records).unwrap();
+        }
+        parser.finish(&mut records).unwrap();
+        records
+    }
+
+    #[test]
+    fn parses_records_across_chunks() {
+        let long = "x".repeat(5000);
+        let text = format!("a,b\n\"1,\"\"5\",{}\n\n2,", long);
+        let (head, tail) = text.split_at(7);
+        assert_eq!(
+            parse(&[head, tail], b','),
+            vec![
+                vec!["a".to_owned(), "b".to_owned()],
+                vec!["1,\"5".to_owned(), long],
+                vec!["2".to_owned(), "".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_chunks_split_within_characters() -> Result<(), GenericError> {
+        let mut parser = DelimitedParser::new(UTF_8, b',');
+        let mut records = Vec::new();
+        for byte in "ab,é\n1,€\n".as_bytes().chunks(1) {
+            parser.push(byte, &mut records)?;
+        }
+        parser.finish(&mut records)?;
+        assert_eq!(
+            records,
+            vec![
+                vec!["ab".to_owned(), "é".to_owned()],
+                vec!["1".to_owned(), "€".to_owned()],
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sniffs_delimiters_and_encodings() {
+        assert_eq!(sniff_delimiter("a,b\n1,2\n"), b',');
+        assert_eq!(sniff_delimiter("a\tb\tc,d\n1\t2\t3,4\n"), b'\t');
+        assert_eq!(sniff_delimiter("a;b;c\n1,5;2;3\n"), b';');
+        assert_eq!(sniff_delimiter("a\n1\n"), b',');
+        assert_eq!(sniff_encoding("é".as_bytes()), UTF_8);
+        assert_eq!(sniff_encoding(&"é".as_bytes()[..1]), UTF_8);
+        assert_eq!(sniff_encoding(b"caf\xe9,1"), WINDOWS_1252);
+        assert_eq!(sniff_encoding(b"\xff\xfea\x00"), encoding_rs::UTF_16LE);
+    }
+}
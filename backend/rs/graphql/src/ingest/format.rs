@@ -0,0 +1,233 @@
+use async_graphql::Enum;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DatasetFormat {
+    Csv,
+    Tsv,
+    // newline delimited json objects
+    Ndjson,
+    Parquet,
+    Xlsx,
+}
+
+impl DatasetFormat {
+    // the explicit format, else the format named by a file name's extension
+    // or the content type, else the format the file starts like, else csv
+    pub fn detect(
+        format: Option<Self>,
+        file_names: &[&str],
+        content_type: Option<&str>,
+        sample: &[u8],
+    ) -> Self {
+        format
+            .or_else(|| file_names.iter().find_map(|n| Self::of_file_name(n)))
+            .or_else(|| content_type.and_then(Self::of_content_type))
+            .or_else(|| Self::of_sample(sample))
+            .unwrap_or(Self::Csv)
+    }
+
+    // whether the whole file must be read before any of it, as parquet and
+    // xlsx keep their metadata at the end
+    pub fn is_whole(&self) -> bool {
+        matches!(self, Self::Parquet | Self::Xlsx)
+    }
+
+    fn of_file_name(name: &str) -> Option<Self> {
+        let extension = &name[name.rfind('.')? + 1..];
+        match extension.to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "tsv" | "tab" => Some(Self::Tsv),
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            "parquet" | "pq" => Some(Self::Parquet),
+            "xlsx" => Some(Self::Xlsx),
+            _ => None,
+        }
+    }
+
+    fn of_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next()?.trim().to_lowercase();
+        match mime.as_str() {
+            "text/csv" | "application/csv" => Some(Self::Csv),
+            "text/tab-separated-values" => Some(Self::Tsv),
+            "application/x-ndjson" | "application/jsonl" => Some(Self::Ndjson),
+            "application/vnd.apache.parquet" | "application/x-parquet" => {
+                Some(Self::Parquet)
+            }
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => {
+                Some(Self::Xlsx)
+            }
+            _ => None,
+        }
+    }
+
+    fn of_sample(sample: &[u8]) -> Option<Self> {
+        if sample.starts_with(b"PAR1") {
+            return Some(Self::Parquet);
+        }
+        // xlsx files are zip archives
+        if sample.starts_with(b"PK\x03\x04") {
+            return Some(Self::Xlsx);
+        }
+        match sample.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+// the file name of a Content-Disposition header, which downloads like google
+// drive's name files by
+pub fn content_disposition_file_name(header: &str) -> Option<String> {
+    header.split(';').find_map(|param| {
+        let mut kv = param.trim().splitn(2, '=');
+        let key = kv.next()?.trim().to_lowercase();
+        let value = kv.next()?.trim();
+        match key.as_str() {
+            "filename" => Some(value.trim_matches('"').to_owned()),
+            // filename*=UTF-8''<percent encoded name>
+            "filename*" => value.rsplit("''").next().map(|n| n.to_owned()),
+            _ => None,
+        }
+    })
+}
+
+// google drive and sheets links open pages rather than files, so they are
+// resolved to downloads of the files, with sheets exported as xlsx when that
+// is the format asked for and as csv of the linked sheet otherwise
+pub fn download_uri(uri: &str, format: Option<DatasetFormat>) -> String {
+    let url = match Url::parse(uri) {
+        Ok(url) => url,
+        Err(_) => return uri.to_owned(),
+    };
+    let id = match google_file_id(&url) {
+        Some(id) => id,
+        None => return uri.to_owned(),
+    };
+    let is_sheet =
+        url.path_segments().and_then(|mut s| s.next()) == Some("spreadsheets");
+    match (url.host_str(), is_sheet) {
+        (Some("drive.google.com"), _) => {
+            format!("https://drive.google.com/uc?export=download&id={}", id)
+        }
+        (Some("docs.google.com"), true) => match format {
+            Some(DatasetFormat::Xlsx) => format!(
+                "https://docs.google.com/spreadsheets/d/{}/export?format=xlsx",
+                id
+            ),
+            _ => format!(
+                "https://docs.google.com/spreadsheets/d/{}/export?format=csv{}",
+                id,
+                sheet_gid(&url)
+                    .map(|gid| format!("&gid={}", gid))
+                    .unwrap_or_default()
+            ),
+        },
+        _ => uri.to_owned(),
+    }
+}
+
+// from /file/d/<id>/view, /spreadsheets/d/<id>/edit, /open?id=<id> and
+// /uc?id=<id>
+fn google_file_id(url: &Url) -> Option<String> {
+    match url.host_str() {
+        Some("drive.google.com") | Some("docs.google.com") => {}
+        _ => return None,
+    }
+    let segments: Vec<&str> = url.path_segments()?.collect();
+    segments
+        .windows(2)
+        .find(|w| w[0] == "d" && !w[1].is_empty())
+        .map(|w| w[1].to_owned())
+        .or_else(|| {
+            url.query_pairs()
+                .find(|(k, _)| k == "id")
+                .map(|(_, v)| v.into_owned())
+        })
+}
+
+// the linked sheet, from #gid=<gid> or ?gid=<gid>
+fn sheet_gid(url: &Url) -> Option<String> {
+    url.fragment()
+        .and_then(|f| f.strip_prefix("gid="))
+        .map(|gid| gid.to_owned())
+        .or_else(|| {
+            url.query_pairs()
+                .find(|(k, _)| k == "gid")
+                .map(|(_, v)| v.into_owned())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats() {
+        let detect = |names: &[&str], content_type, sample: &[u8]| {
+            DatasetFormat::detect(None, names, content_type, sample)
+        };
+        assert_eq!(detect(&["iris.TSV"], None, b"a,b"), DatasetFormat::Tsv);
+        assert_eq!(
+            detect(&["iris", "data.jsonl"], Some("text/plain"), b""),
+            DatasetFormat::Ndjson
+        );
+        assert_eq!(
+            detect(&[], Some("text/csv; charset=utf-8"), b"PAR1"),
+            DatasetFormat::Csv
+        );
+        assert_eq!(detect(&["uc"], None, b"PAR1"), DatasetFormat::Parquet);
+        assert_eq!(detect(&[], None, b"PK\x03\x04"), DatasetFormat::Xlsx);
+        assert_eq!(detect(&[], None, b"\n {\"a\": 1}"), DatasetFormat::Ndjson);
+        assert_eq!(detect(&[], None, b"a,b"), DatasetFormat::Csv);
+        assert_eq!(
+            DatasetFormat::detect(
+                Some(DatasetFormat::Parquet),
+                &["x.csv"],
+                None,
+                b""
+            ),
+            DatasetFormat::Parquet
+        );
+        assert_eq!(
+            content_disposition_file_name(
+                "attachment; filename=\"iris.csv\"; filename*=UTF-8''iris.csv"
+            ),
+            Some("iris.csv".to_owned())
+        );
+    }
+
+    #[test]
+    fn resolves_google_links() {
+        let drive = "https://drive.google.com/uc?export=download&id=abc";
+        assert_eq!(
+            download_uri(
+                "https://drive.google.com/file/d/abc/view?usp=sharing",
+                None
+            ),
+            drive
+        );
+        assert_eq!(
+            download_uri("https://drive.google.com/open?id=abc", None),
+            drive
+        );
+        assert_eq!(
+            download_uri(
+                "https://docs.google.com/spreadsheets/d/abc/edit#gid=7",
+                None
+            ),
+            "https://docs.google.com/spreadsheets/d/abc/export?format=csv&gid=7"
+        );
+        assert_eq!(
+            download_uri(
+                "https://docs.google.com/spreadsheets/d/abc/edit",
+                Some(DatasetFormat::Xlsx)
+            ),
+            "https://docs.google.com/spreadsheets/d/abc/export?format=xlsx"
+        );
+        let other = "https://example.com/drive.google/d/abc/iris.csv";
+        assert_eq!(download_uri(other, None), other);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::Json;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+// values read as null, as pandas reads them
+pub const NULLS: [&str; 9] =
+    ["", "NA", "N/A", "n/a", "NaN", "nan", "null", "NULL", "None"];
+
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y"];
+const TIMESTAMP_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%m/%d/%Y %H:%M:%S%.f",
+];
+
+// the postgres types dataset columns are created with; every value fits in
+// TEXT, and JSONB is only inferred from formats with nested values
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ColumnType {
+    Boolean,
+    BigInt,
+    DoublePrecision,
+    Date,
+    Timestamp,
+    TimestampTz,
+    Jsonb,
+    Text,
+}
+
+impl ColumnType {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Boolean => "BOOLEAN",
+            Self::BigInt => "BIGINT",
+            Self::DoublePrecision => "DOUBLE PRECISION",
+            Self::Date => "DATE",
+            Self::Timestamp => "TIMESTAMP",
+            Self::TimestampTz => "TIMESTAMPTZ",
+            Self::Jsonb => "JSONB",
+            Self::Text => "TEXT",
+        }
+    }
+
+    // the narrowest type of a value, or None for nulls
+    pub fn of(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if NULLS.contains(&value) {
+            return None;
+        }
+        let t = if parse_boolean(value).is_some() {
+            Self::Boolean
+        } else if value.parse::<i64>().is_ok() {
+            Self::BigInt
+        } else if value.parse::<f64>().is_ok() {
+            Self::DoublePrecision
+        } else if parse_date(value).is_some() {
+            Self::Date
+        } else if parse_timestamp(value).is_some() {
+            Self::Timestamp
+        } else if DateTime::parse_from_rfc3339(value).is_ok() {
+            Self::TimestampTz
+        } else {
+            Self::Text
+        };
+        Some(t)
+    }
+
+    // the narrowest type both types' values fit in
+    pub fn widen(self, other: Self) -> Self {
+        use ColumnType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (BigInt, DoublePrecision) | (DoublePrecision, BigInt) => {
+                DoublePrecision
+            }
+            (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+            _ => Text,
+        }
+    }
+
+    // the value as postgres reads it for this type, None for nulls, or the
+    // value's own type when it doesn't fit
+    pub fn normalize(&self, value: &str) -> Result<Option<String>, Self> {
+        let t = match Self::of(value) {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        let value = value.trim();
+        match (self, t) {
+            (Self::Text, _) => Ok(Some(value.to_owned())),
+            (Self::Jsonb, _) if serde_json::from_str::<Json>(value).is_ok() => {
+                Ok(Some(value.to_owned()))
+            }
+            (Self::Boolean, Self::Boolean) => {
+                Ok(parse_boolean(value).map(|b| b.to_string()))
+            }
+            (Self::BigInt, Self::BigInt)
+            | (Self::DoublePrecision, Self::BigInt)
+            | (Self::DoublePrecision, Self::DoublePrecision)
+            | (Self::TimestampTz, Self::TimestampTz) => {
+                Ok(Some(value.to_owned()))
+            }
+            (Self::Date, Self::Date) => {
+                Ok(parse_date(value).map(|d| d.to_string()))
+            }
+            (Self::Timestamp, Self::Date) => {
+                Ok(parse_date(value).map(|d| format!("{} 00:00:00", d)))
+            }
+            (Self::Timestamp, Self::Timestamp) => Ok(parse_timestamp(value)
+                .map(|d| d.format("%Y-%m-%d %H:%M:%S%.f").to_string())),
+            _ => Err(t),
+        }
+    }
+}
+
+// the widest type of each column's values, or TEXT for columns of nulls
+pub fn infer(records: &[Vec<String>], n_columns: usize) -> Vec<ColumnType> {
+    let types: Vec<Vec<Option<ColumnType>>> = records
+        .iter()
+        .map(|r| r.iter().map(|v| ColumnType::of(v)).collect())
+        .collect();
+    infer_types(&types, n_columns)
+}
+
+// the widest of each column's types, where formats type their own values
+pub fn infer_types(
+    types: &[Vec<Option<ColumnType>>],
+    n_columns: usize,
+) -> Vec<ColumnType> {
+    (0..n_columns)
+        .map(|i| {
+            types
+                .iter()
+                .filter_map(|r| r.get(i).copied().flatten())
+                .fold(None, |acc: Option<ColumnType>, t| match acc {
+                    Some(acc) => Some(acc.widen(t)),
+                    None => Some(t),
+                })
+                .unwrap_or(ColumnType::Text)
+        })
+        .collect()
+}
+
+// the first record is a header when its values are distinct and present,
+// and, unless every column is text, some value is text in a typed column
+pub fn has_header(records: &[Vec<String>]) -> bool {
+    let (first, rest) = match records.split_first() {
+        Some(split) => split,
+        None => return false,
+    };
+    let mut names: Vec<&str> = first.iter().map(|v| v.trim()).collect();
+    if names.iter().any(|n| ColumnType::of(n).is_none()) {
+        return false;
+    }
+    names.sort_unstable();
+    names.dedup();
+    if names.len() < first.len() {
+        return false;
+    }
+    let types = infer(rest, first.len());
+    types.iter().all(|t| *t == ColumnType::Text)
+        || first
+            .iter()
+            .zip(types.iter())
+            .filter(|(_, t)| **t != ColumnType::Text)
+            .filter_map(|(v, t)| ColumnType::of(v).map(|v| t.widen(v)))
+            .any(|t| t == ColumnType::Text)
+}
+
+fn parse_boolean(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" => Some(true),
+        "false" | "f" | "no" | "n" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(value, f).ok())
+}
+
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    TIMESTAMP_FORMATS
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|r| r.iter().map(|v| v.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn infers_widest_types() {
+        let rows = records(&[
+            &["1", "1", "yes", "2021-02-06", "a", "NA"],
+            &["2", "1.5", "no", "2021-02-06 18:00:00", "1", ""],
+        ]);
+        assert_eq!(
+            infer(&rows, 7),
+            vec![
+                ColumnType::BigInt,
+                ColumnType::DoublePrecision,
+                ColumnType::Boolean,
+                ColumnType::Timestamp,
+                ColumnType::Text,
+                ColumnType::Text,
+                ColumnType::Text,
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_values() {
+        assert_eq!(ColumnType::Boolean.normalize("Y"), Ok(Some("true".into())));
+        assert_eq!(ColumnType::BigInt.normalize(" NaN "), Ok(None));
+        assert_eq!(
+            ColumnType::Date.normalize("02/06/2021"),
+            Ok(Some("2021-02-06".into()))
+        );
+        assert_eq!(
+            ColumnType::Timestamp.normalize("2021-02-06"),
+            Ok(Some("2021-02-06 00:00:00".into()))
+        );
+        assert_eq!(
+            ColumnType::BigInt.normalize("1.5"),
+            Err(ColumnType::DoublePrecision)
+        );
+        assert_eq!(ColumnType::Text.normalize("1.5"), Ok(Some("1.5".into())));
+        assert_eq!(
+            ColumnType::Jsonb.normalize("{\"a\": [1]}"),
+            Ok(Some("{\"a\": [1]}".into()))
+        );
+        assert_eq!(ColumnType::Jsonb.normalize("{"), Err(ColumnType::Text));
+    }
+
+    #[test]
+    fn detects_headers() {
+        let iris = records(&[&["sepal_length", "species"], &["5.1", "setosa"]]);
+        assert!(has_header(&iris));
+        let text = records(&[&["name", "email"], &["a", "a@b.c"]]);
+        assert!(has_header(&text));
+        let numbers = records(&[&["1", "2.5"], &["3", "4"]]);
+        assert!(!has_header(&numbers));
+        let missing = records(&[&["x", ""], &["1", "2"]]);
+        assert!(!has_header(&missing));
+    }
+}
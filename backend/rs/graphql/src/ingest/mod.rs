@@ -0,0 +1,482 @@
+use crate::{
+    ingest::{
+        delimited::DelimitedParser,
+        format::{content_disposition_file_name, download_uri, DatasetFormat},
+        infer::ColumnType,
+        ndjson::NdjsonParser,
+        parquet::RowGroups,
+        table::{
+            copy_row, copy_sql, create_table_sql, field_names, Field, Mismatch,
+        },
+        xlsx::{Sheet, SheetRows},
+    },
+    models::{Dataset, Status},
+    types::Db,
+    utils::dataset_table_name,
+    GenericError, Secrets,
+};
+use bytes::Bytes;
+use encoding_rs::Encoding;
+use futures::{
+    pin_mut,
+    stream::{self, BoxStream},
+    SinkExt, StreamExt,
+};
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use rustls::ClientConfig;
+use std::sync::Arc;
+use tokio_postgres::Client;
+use tokio_postgres_rustls::MakeRustlsConnect;
+use uuid::Uuid;
+
+pub mod delimited;
+pub mod format;
+pub mod infer;
+pub mod ndjson;
+pub mod parquet;
+pub mod table;
+pub mod xlsx;
+
+// the start of the file that the layout and column types are inferred from
+pub const SAMPLE_BYTES: usize = 1 << 20;
+// rows of parquet and xlsx files, which are read whole, that column types
+// are inferred from
+pub const SAMPLE_ROWS: usize = 10_000;
+// parquet and xlsx files are held in memory whole, so larger ones are
+// refused rather than exhausting it
+pub const MAX_WHOLE_BYTES: usize = 256 << 20;
+// COPY data is sent in batches of about this size
+const BATCH_BYTES: usize = 1 << 16;
+// a sample can miss values that don't fit its types, so a mismatch widens
+// that column and starts over, and the last attempt loads every column as
+// text, which always fits
+const MAX_ATTEMPTS: usize = 3;
+
+// how a file is laid out, and the columns it is loaded into
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub source: Source,
+    pub has_header: bool,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Source {
+    Delimited {
+        encoding: &'static Encoding,
+        delimiter: u8,
+    },
+    // the keys of the sample's objects, in the order they're first seen
+    Ndjson {
+        keys: Vec<String>,
+    },
+    // formats read whole keep the file, shared by every attempt to load it
+    Parquet(Arc<Vec<u8>>),
+    Xlsx(Arc<Sheet>),
+}
+
+// turns a file, pushed to it in chunks, into records of values that its
+// layout's column types normalize
+pub trait Parser: Send {
+    fn push(
+        &mut self,
+        chunk: &[u8],
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError>;
+
+    fn finish(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError>;
+}
+
+// reads the records of a file held whole a batch at a time, so they're
+// never all held at once
+pub trait WholeFile: Send {
+    // appends the next batch, or returns false once every record has been
+    fn read(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<bool, GenericError>;
+}
+
+// the records of a file, a batch at a time
+enum Records {
+    // parsed from a download as it arrives
+    Streamed {
+        chunks: BoxStream<'static, reqwest::Result<Bytes>>,
+        parser: Box<dyn Parser>,
+    },
+    Whole(Box<dyn WholeFile>),
+}
+
+// a download, with its start read ahead for sniffing
+struct Download {
+    sample: Vec<u8>,
+    rest: BoxStream<'static, reqwest::Result<Bytes>>,
+    // whether the sample is the whole file
+    complete: bool,
+    // the names the file goes by, from its Content-Disposition and url
+    file_names: Vec<String>,
+    content_type: Option<String>,
+}
+
+impl Layout {
+    // fields named by `names` and typed by `types`, where the first record
+    // is skipped when `has_header`
+    pub fn new(
+        source: Source,
+        has_header: bool,
+        names: Option<&[String]>,
+        types: Vec<ColumnType>,
+    ) -> Self {
+        let fields = field_names(names, types.len())
+            .into_iter()
+            .zip(types)
+            .map(|(name, column_type)| Field { name, column_type })
+            .collect();
+        Self {
+            source,
+            has_header,
+            fields,
+        }
+    }
+}
+
+impl Source {
+    // the file's records, which are read from its download unless the
+    // source holds it whole
+    fn records(
+        &self,
+        download: &mut Download,
+    ) -> Result<Records, GenericError> {
+        let parser: Box<dyn Parser> = match self {
+            Self::Delimited {
+                encoding,
+                delimiter,
+            } => Box::new(DelimitedParser::new(encoding, *delimiter)),
+            Self::Ndjson { keys } => Box::new(NdjsonParser::new(keys.clone())),
+            Self::Parquet(bytes) => {
+                let row_groups = RowGroups::new(Arc::clone(bytes))?;
+                return Ok(Records::Whole(Box::new(row_groups)));
+            }
+            Self::Xlsx(sheet) => {
+                let rows = SheetRows::new(Arc::clone(sheet));
+                return Ok(Records::Whole(Box::new(rows)));
+            }
+        };
+        let sample = Bytes::from(std::mem::take(&mut download.sample));
+        let rest =
+            std::mem::replace(&mut download.rest, stream::empty().boxed());
+        Ok(Records::Streamed {
+            chunks: stream::iter(vec![Ok(sample)]).chain(rest).boxed(),
+            parser,
+        })
+    }
+}
+
+impl Records {
+    // appends the next batch, or returns false once every record has been
+    async fn next(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<bool, GenericError> {
+        match self {
+            Self::Streamed { chunks, parser } => {
+                match chunks.next().await.transpose()? {
+                    Some(chunk) => {
+                        parser.push(&chunk, records)?;
+                        Ok(true)
+                    }
+                    None => {
+                        parser.finish(records)?;
+                        Ok(false)
+                    }
+                }
+            }
+            Self::Whole(file) => file.read(records),
+        }
+    }
+}
+
+impl Download {
+    // takes the whole file, which the download no longer holds
+    async fn read_to_end(&mut self) -> Result<Vec<u8>, GenericError> {
+        let mut bytes = std::mem::take(&mut self.sample);
+        while let Some(chunk) = self.rest.next().await.transpose()? {
+            if bytes.len() + chunk.len() > MAX_WHOLE_BYTES {
+                return Err(format!(
+                    "parquet and xlsx files are read whole, so must be at \
+                     most {} MiB; larger files can be loaded as csv",
+                    MAX_WHOLE_BYTES >> 20
+                )
+                .into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+}
+
+// loads the file at `uri` into the dataset's table, streaming it through
+// COPY, and records the dataset's status and number of rows
+pub async fn ingest(
+    db: &Db,
+    secrets: &Secrets,
+    uuid: &Uuid,
+    uri: &str,
+    format: Option<DatasetFormat>,
+) -> Result<u64, GenericError> {
+    Dataset::set_status(db, uuid, Status::Running).await?;
+    match load(secrets, uuid, uri, format).await {
+        Ok(n_rows) => {
+            Dataset::complete(db, uuid, n_rows as i64).await?;
+            Ok(n_rows)
+        }
+        Err(e) => {
+            if let Err(e) = Dataset::set_status(db, uuid, Status::Failed).await
+            {
+                eprintln!("unable to mark dataset {} failed: {}", uuid, e);
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn load(
+    secrets: &Secrets,
+    uuid: &Uuid,
+    uri: &str,
+    format: Option<DatasetFormat>,
+) -> Result<u64, GenericError> {
+    let (mut client, connection) =
+        tokio_postgres::connect(&secrets.data_db_url, tls(secrets)?).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("data db connection failed: {}", e);
+        }
+    });
+    let table = dataset_table_name(uuid);
+    let uri = download_uri(uri, format);
+    let mut download = fetch(&uri).await?;
+    let file_names: Vec<&str> =
+        download.file_names.iter().map(|n| n.as_str()).collect();
+    let format = DatasetFormat::detect(
+        format,
+        &file_names,
+        download.content_type.as_deref(),
+        &download.sample,
+    );
+    let mut layout = match format.is_whole() {
+        true => sniff_whole(format, download.read_to_end().await?)?,
+        false => sniff(format, &download.sample, download.complete)?,
+    };
+    let mut attempt = 1;
+    loop {
+        let records = layout.source.records(&mut download)?;
+        let e = match copy(&mut client, &table, &layout, records).await {
+            Ok(n_rows) => return Ok(n_rows),
+            Err(e) => e,
+        };
+        let mismatch = match e.downcast_ref::<Mismatch>() {
+            Some(m) => m,
+            None => return Err(e),
+        };
+        attempt += 1;
+        eprintln!("reloading dataset {}: {}", uuid, mismatch);
+        match attempt < MAX_ATTEMPTS {
+            true => {
+                let f = &mut layout.fields[mismatch.column];
+                f.column_type = f.column_type.widen(mismatch.value_type);
+            }
+            false => {
+                for f in layout.fields.iter_mut() {
+                    f.column_type = ColumnType::Text;
+                }
+            }
+        }
+        // downloads are read once, but files held whole are read again
+        if !format.is_whole() {
+            download = fetch(&uri).await?;
+        }
+    }
+}
+
+// verifies the data db's certificate against the public roots and the
+// configured CA, when the url's sslmode, prefer by default, uses TLS
+fn tls(secrets: &Secrets) -> Result<MakeRustlsConnect, GenericError> {
+    let mut config = ClientConfig::new();
+    config
+        .root_store
+        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    if let Some(pem) = &secrets.data_db_ca_cert {
+        match config.root_store.add_pem_file(&mut pem.as_bytes()) {
+            Ok((added, _)) if added > 0 => {}
+            _ => return Err("invalid data db CA certificate".into()),
+        }
+    }
+    Ok(MakeRustlsConnect::new(config))
+}
+
+async fn fetch(uri: &str) -> Result<Download, GenericError> {
+    let res = reqwest::get(uri).await?.error_for_status()?;
+    let header = |name| {
+        res.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_owned())
+    };
+    let content_type = header(CONTENT_TYPE);
+    let file_names = header(CONTENT_DISPOSITION)
+        .and_then(|h| content_disposition_file_name(&h))
+        .into_iter()
+        .chain(
+            res.url()
+                .path_segments()
+                .and_then(|s| s.last())
+                .map(|s| s.to_owned()),
+        )
+        .collect();
+    let mut rest = res.bytes_stream().boxed();
+    let mut sample = Vec::new();
+    let mut complete = false;
+    while !complete && sample.len() < SAMPLE_BYTES {
+        match rest.next().await.transpose()? {
+            Some(chunk) => sample.extend_from_slice(&chunk),
+            None => complete = true,
+        }
+    }
+    Ok(Download {
+        sample,
+        rest,
+        complete,
+        file_names,
+        content_type,
+    })
+}
+
+// the layout of a file in `format` from its sample
+pub fn sniff(
+    format: DatasetFormat,
+    sample: &[u8],
+    complete: bool,
+) -> Result<Layout, GenericError> {
+    let layout = match format {
+        DatasetFormat::Csv => delimited::sniff(sample, complete, None)?,
+        DatasetFormat::Tsv => delimited::sniff(sample, complete, Some(b'\t'))?,
+        DatasetFormat::Ndjson => ndjson::sniff(sample, complete)?,
+        DatasetFormat::Parquet | DatasetFormat::Xlsx => {
+            return Err("parquet and xlsx files are sniffed whole".into())
+        }
+    };
+    has_fields(layout)
+}
+
+// the layout of a whole file in `format`, whose source keeps the file
+pub fn sniff_whole(
+    format: DatasetFormat,
+    bytes: Vec<u8>,
+) -> Result<Layout, GenericError> {
+    let layout = match format {
+        DatasetFormat::Parquet => parquet::sniff(Arc::new(bytes))?,
+        DatasetFormat::Xlsx => xlsx::sniff(&bytes)?,
+        _ => return sniff(format, &bytes, true),
+    };
+    has_fields(layout)
+}
+
+fn has_fields(layout: Layout) -> Result<Layout, GenericError> {
+    match layout.fields.is_empty() {
+        true => Err("no rows found".into()),
+        false => Ok(layout),
+    }
+}
+
+// creates the table and copies every row in one transaction, so a failed
+// attempt leaves nothing behind
+async fn copy(
+    client: &mut Client,
+    table: &str,
+    layout: &Layout,
+    mut records: Records,
+) -> Result<u64, GenericError> {
+    let tx = client.transaction().await?;
+    tx.batch_execute(&create_table_sql(table, &layout.fields))
+        .await?;
+    let sql = copy_sql(table, &layout.fields);
+    let sink = tx.copy_in(sql.as_str()).await?;
+    pin_mut!(sink);
+    let mut parsed = Vec::new();
+    let mut batch = Vec::with_capacity(BATCH_BYTES);
+    let mut row = 0;
+    let mut more = true;
+    while more {
+        more = records.next(&mut parsed).await?;
+        for record in parsed.drain(..) {
+            row += 1;
+            if row == 1 && layout.has_header {
+                continue;
+            }
+            if record.len() > layout.fields.len() {
+                return Err(format!(
+                    "row {} has {} values rather than {}",
+                    row,
+                    record.len(),
+                    layout.fields.len()
+                )
+                .into());
+            }
+            copy_row(&mut batch, &record, &layout.fields)?;
+            if batch.len() >= BATCH_BYTES {
+                sink.send(Bytes::from(std::mem::take(&mut batch))).await?;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        sink.send(Bytes::from(batch)).await?;
+    }
+    let n_rows = sink.finish().await?;
+    tx.commit().await?;
+    Ok(n_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_layouts() -> Result<(), GenericError> {
+        let sample = "\u{feff}id;name;score\n1;a;1,5\n2;b;2\n3;c;";
+        let layout = sniff(DatasetFormat::Csv, sample.as_bytes(), true)?;
+        assert!(matches!(
+            layout.source,
+            Source::Delimited {
+                delimiter: b';',
+                ..
+            }
+        ));
+        assert!(layout.has_header);
+        let fields: Vec<(&str, ColumnType)> = layout
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.column_type))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("id", ColumnType::BigInt),
+                ("name", ColumnType::Text),
+                ("score", ColumnType::Text),
+            ]
+        );
+        let headless = sniff(DatasetFormat::Csv, b"1\t2.5\n2\t3\n", false)?;
+        assert!(!headless.has_header);
+        assert_eq!(headless.fields[1].name, "column_2");
+        assert_eq!(headless.fields[1].column_type, ColumnType::DoublePrecision);
+        // tsv is never sniffed as another delimiter
+        let tsv = sniff(DatasetFormat::Tsv, b"a,b\tc\n1,5\t2\n", true)?;
+        assert_eq!(tsv.fields.len(), 2);
+        assert!(sniff(DatasetFormat::Ndjson, b"\n", true).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    ingest::{
+        delimited::decode,
+        infer::{infer_types, ColumnType},
+        Layout, Parser, Source,
+    },
+    GenericError, Json,
+};
+use encoding_rs::{Decoder, UTF_8};
+use serde_json::Map;
+
+// the layout of newline delimited json, with a column for each key of the
+// sample's objects, in the order they're first seen
+pub fn sniff(sample: &[u8], complete: bool) -> Result<Layout, GenericError> {
+    let objects = NdjsonParser::new(Vec::new()).objects(sample, complete)?;
+    let mut keys: Vec<String> = Vec::new();
+    for key in objects.iter().flat_map(|o| o.keys()) {
+        if !keys.contains(key) {
+            keys.push(key.clone());
+        }
+    }
+    let types: Vec<Vec<Option<ColumnType>>> = objects
+        .iter()
+        .map(|o| keys.iter().map(|k| o.get(k).and_then(type_of)).collect())
+        .collect();
+    let types = infer_types(&types, keys.len());
+    let source = Source::Ndjson { keys: keys.clone() };
+    Ok(Layout::new(source, false, Some(&keys), types))
+}
+
+// renders each object as a record of its values of `keys`, so keys first
+// seen after the sample are dropped
+pub struct NdjsonParser {
+    decoder: Decoder,
+    text: String,
+    line: usize,
+    keys: Vec<String>,
+}
+
+impl NdjsonParser {
+    pub fn new(keys: Vec<String>) -> Self {
+        Self {
+            decoder: UTF_8.new_decoder_with_bom_removal(),
+            text: String::new(),
+            line: 0,
+            keys,
+        }
+    }
+
+    // the objects on the lines `chunk` completes; the rest of a partial line
+    // is kept for the next chunk unless `last`
+    fn objects(
+        &mut self,
+        chunk: &[u8],
+        last: bool,
+    ) -> Result<Vec<Map<String, Json>>, GenericError> {
+        decode(&mut self.decoder, chunk, &mut self.text, last);
+        let end = match (last, self.text.rfind('\n')) {
+            (true, _) => self.text.len(),
+            (false, Some(i)) => i + 1,
+            (false, None) => return Ok(Vec::new()),
+        };
+        let mut objects = Vec::new();
+        for line in self.text[..end].lines() {
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(Json::Object(o)) => objects.push(o),
+                _ => {
+                    return Err(format!(
+                        "line {} isn't a json object",
+                        self.line
+                    )
+                    .into())
+                }
+            }
+        }
+        self.text.drain(..end);
+        Ok(objects)
+    }
+
+    fn read(
+        &mut self,
+        chunk: &[u8],
+        records: &mut Vec<Vec<String>>,
+        last: bool,
+    ) -> Result<(), GenericError> {
+        let objects = self.objects(chunk, last)?;
+        records.extend(
+            objects
+                .iter()
+                .map(|o| self.keys.iter().map(|k| cell(o.get(k))).collect()),
+        );
+        Ok(())
+    }
+}
+
+impl Parser for NdjsonParser {
+    fn push(
+        &mut self,
+        chunk: &[u8],
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError> {
+        self.read(chunk, records, false)
+    }
+
+    fn finish(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<(), GenericError> {
+        self.read(&[], records, true)
+    }
+}
+
+// strings are typed by their text only when it's a date or time, as json
+// has its own numbers and booleans
+fn type_of(value: &Json) -> Option<ColumnType> {
+    match value {
+        Json::Null => None,
+        Json::Bool(_) => Some(ColumnType::Boolean),
+        Json::Number(n) => match n.is_i64() {
+            true => Some(ColumnType::BigInt),
+            false => Some(ColumnType::DoublePrecision),
+        },
+        Json::String(s) => ColumnType::of(s).map(|t| match t {
+            ColumnType::Date
+            | ColumnType::Timestamp
+            | ColumnType::TimestampTz => t,
+            _ => ColumnType::Text,
+        }),
+        Json::Array(_) | Json::Object(_) => Some(ColumnType::Jsonb),
+    }
+}
+
+// missing values and nulls are empty, and nested values are json
+fn cell(value: Option<&Json>) -> String {
+    match value {
+        None | Some(Json::Null) => String::new(),
+        Some(Json::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_objects_as_records() -> Result<(), GenericError> {
+        let sample = "{\"id\": 1, \"at\": \"2021-02-06\", \"tags\": [\"a\"]}\n\
+                      \n\
+                      {\"id\": 2.5, \"ok\": true, \"at\": null}\n\
+                      {\"id\": 3, \"";
+        let layout = sniff(sample.as_bytes(), false)?;
+        let fields: Vec<(&str, ColumnType)> = layout
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.column_type))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("id", ColumnType::DoublePrecision),
+                ("at", ColumnType::Date),
+                ("tags", ColumnType::Jsonb),
+                ("ok", ColumnType::Boolean),
+            ]
+        );
+        let keys = match layout.source {
+            Source::Ndjson { keys } => keys,
+            _ => unreachable!(),
+        };
+        let mut parser = NdjsonParser::new(keys);
+        let mut records = Vec::new();
+        let (head, tail) = sample.split_at(20);
+        parser.push(head.as_bytes(), &mut records)?;
+        assert!(records.is_empty());
+        parser.push(tail.as_bytes(), &mut records)?;
+        parser.push(b"ok\": false}", &mut records)?;
+        parser.finish(&mut records)?;
+        assert_eq!(
+            records,
+            vec![
+                vec!["1", "2021-02-06", "[\"a\"]", ""],
+                vec!["2.5", "", "", "true"],
+                vec!["3", "", "", "false"],
+            ]
+        );
+        let mut parser = NdjsonParser::new(Vec::new());
+        assert!(parser.push(b"{}\n[1]\n", &mut records).is_err());
+        Ok(())
+    }
+}
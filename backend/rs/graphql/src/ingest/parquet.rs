@@ -0,0 +1,140 @@
+use crate::{
+    ingest::{
+        infer::{infer_types, ColumnType},
+        Layout, Source, WholeFile, SAMPLE_ROWS,
+    },
+    GenericError,
+};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use parquet::{
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+    util::cursor::SliceableCursor,
+};
+use std::sync::Arc;
+
+// the layout of a whole parquet file, with a column for each top level field
+// of its schema
+pub fn sniff(bytes: Arc<Vec<u8>>) -> Result<Layout, GenericError> {
+    let reader =
+        SerializedFileReader::new(SliceableCursor::new(Arc::clone(&bytes)))?;
+    let names: Vec<String> = reader
+        .metadata()
+        .file_metadata()
+        .schema()
+        .get_fields()
+        .iter()
+        .map(|f| f.name().to_owned())
+        .collect();
+    let types: Vec<Vec<Option<ColumnType>>> = reader
+        .get_row_iter(None)?
+        .take(SAMPLE_ROWS)
+        .map(|row| row.get_column_iter().map(|(_, f)| type_of(f)).collect())
+        .collect();
+    let types = infer_types(&types, names.len());
+    Ok(Layout::new(
+        Source::Parquet(bytes),
+        false,
+        Some(&names),
+        types,
+    ))
+}
+
+// reads a parquet file a row group at a time
+pub struct RowGroups {
+    reader: SerializedFileReader<SliceableCursor>,
+    next: usize,
+}
+
+impl RowGroups {
+    pub fn new(bytes: Arc<Vec<u8>>) -> Result<Self, GenericError> {
+        Ok(Self {
+            reader: SerializedFileReader::new(SliceableCursor::new(bytes))?,
+            next: 0,
+        })
+    }
+}
+
+impl WholeFile for RowGroups {
+    fn read(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<bool, GenericError> {
+        if self.next == self.reader.num_row_groups() {
+            return Ok(false);
+        }
+        let row_group = self.reader.get_row_group(self.next)?;
+        records.extend(
+            row_group.get_row_iter(None)?.map(|row| {
+                row.get_column_iter().map(|(_, f)| cell(f)).collect()
+            }),
+        );
+        self.next += 1;
+        Ok(true)
+    }
+}
+
+// decimals, bytes and nested fields are loaded as their text
+fn type_of(field: &Field) -> Option<ColumnType> {
+    let t = match field {
+        Field::Null => return None,
+        Field::Bool(_) => ColumnType::Boolean,
+        Field::Byte(_)
+        | Field::Short(_)
+        | Field::Int(_)
+        | Field::Long(_)
+        | Field::UByte(_)
+        | Field::UShort(_)
+        | Field::UInt(_) => ColumnType::BigInt,
+        Field::Float(_) | Field::Double(_) => ColumnType::DoublePrecision,
+        Field::Date(_) => ColumnType::Date,
+        Field::TimestampMillis(_) | Field::TimestampMicros(_) => {
+            ColumnType::TimestampTz
+        }
+        _ => ColumnType::Text,
+    };
+    Some(t)
+}
+
+// fields as their column types read them, where parquet's own formatting
+// quotes strings and offsets dates
+fn cell(field: &Field) -> String {
+    match field {
+        Field::Null => String::new(),
+        Field::Str(s) => s.clone(),
+        Field::Float(f) => f.to_string(),
+        Field::Double(f) => f.to_string(),
+        Field::Date(days) => (NaiveDate::from_ymd(1970, 1, 1)
+            + Duration::days(*days as i64))
+        .to_string(),
+        Field::TimestampMillis(ms) => {
+            Utc.timestamp_millis(*ms as i64).to_rfc3339()
+        }
+        Field::TimestampMicros(us) => {
+            let us = *us as i64;
+            Utc.timestamp(
+                us.div_euclid(1_000_000),
+                (us.rem_euclid(1_000_000) * 1000) as u32,
+            )
+            .to_rfc3339()
+        }
+        f => f.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_fields() {
+        assert_eq!(cell(&Field::Str("a \"b\"".into())), "a \"b\"");
+        assert_eq!(cell(&Field::Date(18664)), "2021-02-06");
+        assert_eq!(
+            cell(&Field::TimestampMillis(1612634400000)),
+            "2021-02-06T18:00:00+00:00"
+        );
+        assert_eq!(type_of(&Field::Long(1)), Some(ColumnType::BigInt));
+        assert_eq!(type_of(&Field::Null), None);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    ingest::infer::ColumnType,
+    operations::{quote_identifier, truncate_identifier, MAX_COLUMN_LENGTH},
+};
+use std::{collections::HashSet, error, fmt};
+
+// a column of the dataset table
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub column_type: ColumnType,
+}
+
+// a value that doesn't fit the type inferred for its column, which is only
+// inferred from a sample of the file
+#[derive(Debug)]
+pub struct Mismatch {
+    pub column: usize,
+    pub value_type: ColumnType,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "column {} has a {:?} value",
+            self.column, self.value_type
+        )
+    }
+}
+
+impl error::Error for Mismatch {}
+
+// header names, trimmed, truncated to what postgres keeps and made unique,
+// or column_1, column_2, ... where there are none
+pub fn field_names(header: Option<&[String]>, n_columns: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    (0..n_columns)
+        .map(|i| {
+            let name = header
+                .and_then(|h| h.get(i))
+                .map(|n| truncate_identifier(n.trim(), MAX_COLUMN_LENGTH))
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| format!("column_{}", i + 1));
+            let mut unique = name.clone();
+            let mut k = 2;
+            while !seen.insert(unique.clone()) {
+                let suffix = format!("_{}", k);
+                unique = format!(
+                    "{}{}",
+                    truncate_identifier(
+                        &name,
+                        MAX_COLUMN_LENGTH - suffix.len()
+                    ),
+                    suffix
+                );
+                k += 1;
+            }
+            unique
+        })
+        .collect()
+}
+
+// drops any table left by an earlier attempt
+pub fn create_table_sql(table: &str, fields: &[Field]) -> String {
+    let columns: Vec<String> = fields
+        .iter()
+        .map(|f| {
+            format!("{} {}", quote_identifier(&f.name), f.column_type.as_sql())
+        })
+        .collect();
+    format!(
+        "DROP TABLE IF EXISTS {};\nCREATE TABLE {} ({})",
+        quote_identifier(table),
+        quote_identifier(table),
+        columns.join(", ")
+    )
+}
+
+pub fn copy_sql(table: &str, fields: &[Field]) -> String {
+    let columns: Vec<String> =
+        fields.iter().map(|f| quote_identifier(&f.name)).collect();
+    format!(
+        "COPY {} ({}) FROM STDIN WITH (FORMAT csv)",
+        quote_identifier(table),
+        columns.join(", ")
+    )
+}
+
+// appends the record as a line of COPY csv, where nulls are unquoted and
+// empty and missing trailing values are null
+pub fn copy_row(
+    out: &mut Vec<u8>,
+    record: &[String],
+    fields: &[Field],
+) -> Result<(), Mismatch> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(b',');
+        }
+        let value = record.get(i).map(|v| v.as_str()).unwrap_or("");
+        let value =
+            field.column_type.normalize(value).map_err(|t| Mismatch {
+                column: i,
+                value_type: t,
+            })?;
+        if let Some(value) = value {
+            out.push(b'"');
+            out.extend_from_slice(value.replace('"', "\"\"").as_bytes());
+            out.push(b'"');
+        }
+    }
+    out.push(b'\n');
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_fields_uniquely() {
+        let header: Vec<String> =
+            vec!["x".into(), " x ".into(), "".into(), "é".repeat(40)];
+        assert_eq!(
+            field_names(Some(&header), 5),
+            vec![
+                "x".to_owned(),
+                "x_2".to_owned(),
+                "column_3".to_owned(),
+                "é".repeat(31),
+                "column_5".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_copy_rows() {
+        let fields = vec![
+            Field {
+                name: "n".to_owned(),
+                column_type: ColumnType::BigInt,
+            },
+            Field {
+                name: "s".to_owned(),
+                column_type: ColumnType::Text,
+            },
+            Field {
+                name: "b".to_owned(),
+                column_type: ColumnType::Boolean,
+            },
+        ];
+        let mut out = Vec::new();
+        copy_row(&mut out, &["1".into(), "a \"b\"".into()], &fields).unwrap();
+        copy_row(&mut out, &["NA".into(), "".into(), "no".into()], &fields)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\"1\",\"a \"\"b\"\"\",\n,,\"false\"\n"
+        );
+        let res = copy_row(&mut Vec::new(), &["x".into()], &fields);
+        assert!(matches!(res, Err(Mismatch { column: 0, .. })));
+        assert_eq!(
+            copy_sql("dataset_x", &fields[..1]),
+            "COPY \"dataset_x\" (\"n\") FROM STDIN WITH (FORMAT csv)"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    ingest::{
+        infer::{has_header, infer_types, ColumnType},
+        Layout, Source, WholeFile, SAMPLE_ROWS,
+    },
+    GenericError,
+};
+use calamine::{DataType, Range, Reader, Xlsx};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::{io::Cursor, sync::Arc};
+
+// rows are copied in batches of this many
+const BATCH_ROWS: usize = 10_000;
+
+// the cells of a sheet, which calamine reads whole
+pub type Sheet = Range<DataType>;
+
+// the layout of the first sheet of a whole xlsx workbook, whose source keeps
+// the sheet rather than the workbook
+pub fn sniff(bytes: &[u8]) -> Result<Layout, GenericError> {
+    let range = first_sheet(bytes)?;
+    let rows: Vec<&[DataType]> = range.rows().take(SAMPLE_ROWS).collect();
+    let records: Vec<Vec<String>> =
+        rows.iter().map(|r| r.iter().map(cell).collect()).collect();
+    let has_header = has_header(&records);
+    let (header, rows) = match has_header {
+        true => (Some(records[0].as_slice()), &rows[1..]),
+        false => (None, rows.as_slice()),
+    };
+    let types: Vec<Vec<Option<ColumnType>>> = rows
+        .iter()
+        .map(|r| r.iter().map(type_of).collect())
+        .collect();
+    let types = infer_types(&types, range.width());
+    let source = Source::Xlsx(Arc::new(range));
+    Ok(Layout::new(source, has_header, header, types))
+}
+
+// reads the rows of a sheet a batch at a time
+pub struct SheetRows {
+    sheet: Arc<Sheet>,
+    next: usize,
+}
+
+impl SheetRows {
+    pub fn new(sheet: Arc<Sheet>) -> Self {
+        Self { sheet, next: 0 }
+    }
+}
+
+impl WholeFile for SheetRows {
+    fn read(
+        &mut self,
+        records: &mut Vec<Vec<String>>,
+    ) -> Result<bool, GenericError> {
+        let end = (self.next + BATCH_ROWS).min(self.sheet.height());
+        if self.next == end {
+            return Ok(false);
+        }
+        let sheet = &self.sheet;
+        records.extend(
+            (self.next..end).map(|i| sheet[i].iter().map(cell).collect()),
+        );
+        self.next = end;
+        Ok(true)
+    }
+}
+
+fn first_sheet(bytes: &[u8]) -> Result<Sheet, GenericError> {
+    let mut workbook = Xlsx::new(Cursor::new(bytes))?;
+    match workbook.worksheet_range_at(0) {
+        Some(range) => Ok(range?),
+        None => Err("workbook has no sheets".into()),
+    }
+}
+
+// whole floats are integers, as excel stores every number as a float, and
+// error cells are null
+fn type_of(value: &DataType) -> Option<ColumnType> {
+    let t = match value {
+        DataType::Int(_) => ColumnType::BigInt,
+        DataType::Float(f) if f.fract() == 0.0 => ColumnType::BigInt,
+        DataType::Float(_) => ColumnType::DoublePrecision,
+        DataType::Bool(_) => ColumnType::Boolean,
+        DataType::DateTime(d) if d.fract() == 0.0 => ColumnType::Date,
+        DataType::DateTime(_) => ColumnType::Timestamp,
+        DataType::String(s) => {
+            return ColumnType::of(s).map(|_| ColumnType::Text)
+        }
+        _ => return None,
+    };
+    Some(t)
+}
+
+fn cell(value: &DataType) -> String {
+    match value {
+        DataType::Int(i) => i.to_string(),
+        DataType::Float(f) if f.fract() == 0.0 => format!("{:.0}", f),
+        DataType::Float(f) => f.to_string(),
+        DataType::Bool(b) => b.to_string(),
+        DataType::DateTime(d) if d.fract() == 0.0 => {
+            excel_date(*d).date().to_string()
+        }
+        DataType::DateTime(d) => excel_date(*d).to_string(),
+        DataType::String(s) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+// excel dates are days since the end of 1899, with times as fractions of a
+// day
+fn excel_date(serial: f64) -> NaiveDateTime {
+    let ms = (serial * 86_400_000.0).round() as i64;
+    NaiveDate::from_ymd(1899, 12, 30).and_hms(0, 0, 0)
+        + Duration::milliseconds(ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_cells() {
+        assert_eq!(cell(&DataType::Float(3.0)), "3");
+        assert_eq!(type_of(&DataType::Float(3.0)), Some(ColumnType::BigInt));
+        assert_eq!(cell(&DataType::DateTime(44233.0)), "2021-02-06");
+        assert_eq!(cell(&DataType::DateTime(44233.75)), "2021-02-06 18:00:00");
+        assert_eq!(
+            type_of(&DataType::String("1".into())),
+            Some(ColumnType::Text)
+        );
+        assert_eq!(type_of(&DataType::Empty), None);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{
+    gql::get_invocation_type,
+    ingest::ingest,
+    models::{Job, JobKind},
+    types::*,
+    utils::as_bytes,
+    GenericError, Secrets,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use rusoto_core::Region;
+use rusoto_lambda::{InvocationRequest, Lambda, LambdaClient};
+use serde::{Deserialize, Serialize};
+use std::{env, path::PathBuf, process::Stdio, sync::Arc};
+use tokio::{io::AsyncWriteExt, process::Command};
+use tokio_compat_02::FutureExt;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+pub enum JobPayload {
+    UploadDataset(UploadDatasetPayload),
+    CreateStatistic(CreateStatisticPayload),
+    CreatePlot(CreatePlotPayload),
+    CreateModel(CreateModelPayload),
+}
+
+impl JobPayload {
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            Self::UploadDataset(_) => "motoko-ingest",
+            Self::CreateStatistic(_) => "motoko-statistic",
+            Self::CreatePlot(_) => "motoko-plot",
+            Self::CreateModel(_) => "motoko-model",
+        }
+    }
+
+    pub fn as_bytes(&self) -> Result<Bytes, GenericError> {
+        match self {
+            Self::UploadDataset(p) => as_bytes(p),
+            Self::CreateStatistic(p) => as_bytes(p),
+            Self::CreatePlot(p) => as_bytes(p),
+            Self::CreateModel(p) => as_bytes(p),
+        }
+    }
+
+    pub fn from_parts(
+        kind: JobKind,
+        payload: &Json,
+    ) -> Result<Self, GenericError> {
+        serde_json::from_value(serde_json::json!({
+            "kind": kind,
+            "payload": payload,
+        }))
+        .map_err(|e| e.into())
+    }
+
+    pub fn kind(&self) -> JobKind {
+        match self {
+            Self::UploadDataset(_) => JobKind::UploadDataset,
+            Self::CreateStatistic(_) => JobKind::CreateStatistic,
+            Self::CreatePlot(_) => JobKind::CreatePlot,
+            Self::CreateModel(_) => JobKind::CreateModel,
+        }
+    }
+
+    pub fn payload(&self) -> Result<Json, GenericError> {
+        let v = match self {
+            Self::UploadDataset(p) => serde_json::to_value(p),
+            Self::CreateStatistic(p) => serde_json::to_value(p),
+            Self::CreatePlot(p) => serde_json::to_value(p),
+            Self::CreateModel(p) => serde_json::to_value(p),
+        };
+        v.map_err(|e| e.into())
+    }
+
+    // interactive jobs are dequeued before long running ones
+    pub fn priority(&self) -> i32 {
+        match self {
+            Self::CreateStatistic(_) | Self::CreatePlot(_) => 2,
+            Self::UploadDataset(_) => 1,
+            Self::CreateModel(_) => 0,
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::UploadDataset(_) => "datasets",
+            Self::CreateStatistic(_) => "statistics",
+            Self::CreatePlot(_) => "plots",
+            Self::CreateModel(_) => "models",
+        }
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        match self {
+            Self::UploadDataset(p) => p.uuid,
+            Self::CreateStatistic(p) => p.uuid,
+            Self::CreatePlot(p) => p.uuid,
+            Self::CreateModel(p) => p.uuid,
+        }
+    }
+}
+
+#[async_trait]
+pub trait JobRunner: Send + Sync {
+    async fn run(&self, payload: JobPayload) -> Result<(), GenericError>;
+}
+
+// runners for the API; jobs are queued for workers when JOB_RUNNER=queue
+pub fn job_runner(
+    region: Region,
+    secrets: &Secrets,
+    db: &Db,
+) -> Arc<dyn JobRunner> {
+    match env::var("JOB_RUNNER").as_deref() {
+        Ok("local") => Arc::new(LocalRunner::new(secrets, db)),
+        Ok("queue") => Arc::new(QueueRunner::new(db)),
+        _ => Arc::new(LambdaRunner::new(region)),
+    }
+}
+
+// runners for workers, which must wait for each job to finish
+pub fn job_executor(
+    region: Region,
+    secrets: &Secrets,
+    db: &Db,
+) -> Arc<dyn JobRunner> {
+    match env::var("JOB_RUNNER").as_deref() {
+        Ok("local") => Arc::new(LocalRunner::new(secrets, db)),
+        _ => Arc::new(LambdaRunner::synchronous(region)),
+    }
+}
+
+pub struct LambdaRunner {
+    client: LambdaClient,
+    invocation_type: Option<String>,
+}
+
+impl LambdaRunner {
+    pub fn new(region: Region) -> Self {
+        Self {
+            client: LambdaClient::new(region),
+            invocation_type: get_invocation_type(),
+        }
+    }
+
+    pub fn synchronous(region: Region) -> Self {
+        Self {
+            client: LambdaClient::new(region),
+            // None defaults to RequestResponse
+            invocation_type: None,
+        }
+    }
+}
+
+#[async_trait]
+impl JobRunner for LambdaRunner {
+    async fn run(&self, payload: JobPayload) -> Result<(), GenericError> {
+        let req = InvocationRequest {
+            function_name: payload.function_name().to_owned(),
+            invocation_type: self.invocation_type.clone(),
+            payload: Some(payload.as_bytes()?),
+            ..Default::default()
+        };
+        let res = self.client.invoke(req).compat().await?;
+        match res.function_error {
+            Some(e) => {
+                Err(format!("{} failed: {}", payload.function_name(), e).into())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+// runs the python lambda handlers directly, which requires their
+// dependencies to be installed, i.e. pip install -r <function>/requirements.txt
+pub struct LocalRunner {
+    py_dir: PathBuf,
+    db: Db,
+    secrets: Secrets,
+}
+
+impl LocalRunner {
+    pub fn new(secrets: &Secrets, db: &Db) -> Self {
+        Self {
+            py_dir: env::var("MOTOKO_PY_DIR")
+                .unwrap_or("../../py".to_owned())
+                .into(),
+            db: db.clone(),
+            secrets: secrets.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl JobRunner for LocalRunner {
+    async fn run(&self, payload: JobPayload) -> Result<(), GenericError> {
+        // datasets are ingested in rust, in process
+        if let JobPayload::UploadDataset(p) = &payload {
+            return ingest(&self.db, &self.secrets, &p.uuid, &p.uri, p.format)
+                .await
+                .map(|_| ());
+        }
+        let function_name = payload.function_name();
+        let mut child = Command::new("python3")
+            .args(&[
+                "-c",
+                "import app, json, sys; \
+                 app.lambda_handler(json.load(sys.stdin), None)",
+            ])
+            .current_dir(
+                self.py_dir
+                    .join(function_name.trim_start_matches("motoko-")),
+            )
+            .env("PYTHONPATH", &self.py_dir)
+            .env("DATA_DB_URL", &self.secrets.data_db_url)
+            .env("META_DB_URL", &self.secrets.meta_db_url)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().ok_or("unable to open stdin")?;
+        stdin.write_all(&payload.as_bytes()?).await?;
+        drop(stdin);
+        let status = child.wait().await?;
+        if !status.success() {
+            return Err(format!("{} failed: {}", function_name, status).into());
+        }
+        Ok(())
+    }
+}
+
+pub struct QueueRunner {
+    db: Db,
+}
+
+impl QueueRunner {
+    pub fn new(db: &Db) -> Self {
+        Self { db: db.clone() }
+    }
+}
+
+#[async_trait]
+impl JobRunner for QueueRunner {
+    async fn run(&self, payload: JobPayload) -> Result<(), GenericError> {
+        Job::enqueue(&self.db, &payload).await?;
+        Ok(())
+    }
+}
+
+// records jobs rather than running them, so mutations can be tested without
+// lambdas or python
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingRunner {
+    payloads: std::sync::Mutex<Vec<JobPayload>>,
+}
+
+#[cfg(test)]
+impl RecordingRunner {
+    // the jobs run since the last call
+    pub fn take(&self) -> Vec<JobPayload> {
+        match self.payloads.lock() {
+            Ok(mut payloads) => std::mem::take(&mut *payloads),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl JobRunner for RecordingRunner {
+    async fn run(&self, payload: JobPayload) -> Result<(), GenericError> {
+        self.payloads
+            .lock()
+            .map_err(|_| "recorded jobs poisoned")?
+            .push(payload);
+        Ok(())
+    }
+}
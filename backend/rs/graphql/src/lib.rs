@@ -1,22 +1,33 @@
 pub mod auth;
+pub mod connections;
 pub mod context_data;
 pub mod error;
 pub mod gql;
+pub mod ingest;
+pub mod jobs;
+pub mod loaders;
+pub mod materialization;
 pub mod models;
 pub mod mutation;
 pub mod node;
+pub mod operations;
+pub mod profile;
 pub mod queries;
 pub mod query;
+pub mod rows;
+pub mod schema;
 pub mod secrets;
+pub mod subscription;
 pub mod types;
 pub mod utils;
 
 pub use auth::user_from_authorization_header;
 pub use context_data::{Auth, ContextData};
 pub use error::Error;
-pub use gql::respond;
+pub use gql::{respond, schema, MotokoSchema};
 pub use mutation::Mutation;
 pub use node::{id_to_node, Node};
 pub use query::Query;
 pub use secrets::Secrets;
+pub use subscription::Subscription;
 pub use types::*;
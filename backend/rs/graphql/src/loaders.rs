@@ -0,0 +1,140 @@
+use crate::{
+    models::{
+        Analysis, Dataset, Dataview, Job, Model, Plot, Project, Statistic, User,
+    },
+    types::Db,
+};
+use async_graphql::{
+    dataloader::{DataLoader, Loader},
+    Context, Result as GQLResult,
+};
+use async_trait::async_trait;
+use sqlx::{postgres::PgRow, query_as, Error as SQLxError, FromRow};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use uuid::Uuid;
+
+// a model stored in the meta database with a uuid primary key
+pub trait Keyed:
+    for<'r> FromRow<'r, PgRow> + Clone + Send + Sync + Unpin + 'static
+{
+    const TABLE: &'static str;
+
+    fn uuid(&self) -> Uuid;
+
+    fn loader(loaders: &Loaders) -> &DataLoader<UuidLoader<Self>>;
+}
+
+// batches the lookups of one request into a single query per table
+pub struct UuidLoader<T> {
+    db: Db,
+    queries: Arc<AtomicUsize>,
+    model: PhantomData<T>,
+}
+
+#[async_trait]
+impl<T: Keyed> Loader<Uuid> for UuidLoader<T> {
+    type Value = T;
+    // sqlx errors are not Clone
+    type Error = Arc<SQLxError>;
+
+    async fn load(
+        &self,
+        keys: &[Uuid],
+    ) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        let rows: Vec<T> = query_as(&format!(
+            "SELECT * FROM {} WHERE uuid = ANY($1)",
+            T::TABLE
+        ))
+        .bind(keys)
+        .fetch_all(&self.db.meta)
+        .await
+        .map_err(Arc::new)?;
+        Ok(rows.into_iter().map(|r| (r.uuid(), r)).collect())
+    }
+}
+
+// loaders cache what they load, so each request gets its own
+pub struct Loaders {
+    // the number of batched queries run, for tests
+    pub queries: Arc<AtomicUsize>,
+    analyses: DataLoader<UuidLoader<Analysis>>,
+    datasets: DataLoader<UuidLoader<Dataset>>,
+    dataviews: DataLoader<UuidLoader<Dataview>>,
+    jobs: DataLoader<UuidLoader<Job>>,
+    models: DataLoader<UuidLoader<Model>>,
+    plots: DataLoader<UuidLoader<Plot>>,
+    projects: DataLoader<UuidLoader<Project>>,
+    statistics: DataLoader<UuidLoader<Statistic>>,
+    users: DataLoader<UuidLoader<User>>,
+}
+
+impl Loaders {
+    pub fn new(db: &Db) -> Self {
+        let queries = Arc::new(AtomicUsize::new(0));
+        Self {
+            analyses: loader(db, &queries),
+            datasets: loader(db, &queries),
+            dataviews: loader(db, &queries),
+            jobs: loader(db, &queries),
+            models: loader(db, &queries),
+            plots: loader(db, &queries),
+            projects: loader(db, &queries),
+            statistics: loader(db, &queries),
+            users: loader(db, &queries),
+            queries,
+        }
+    }
+}
+
+fn loader<T: Keyed>(
+    db: &Db,
+    queries: &Arc<AtomicUsize>,
+) -> DataLoader<UuidLoader<T>> {
+    DataLoader::new(UuidLoader {
+        db: db.clone(),
+        queries: queries.clone(),
+        model: PhantomData,
+    })
+}
+
+pub async fn load<T: Keyed>(ctx: &Context<'_>, uuid: &Uuid) -> GQLResult<T> {
+    let loaders = ctx.data::<Loaders>()?;
+    T::loader(loaders)
+        .load_one(*uuid)
+        .await?
+        .ok_or_else(|| SQLxError::RowNotFound.into())
+}
+
+macro_rules! keyed {
+    ($model:ty, $table:literal, $field:ident) => {
+        impl Keyed for $model {
+            const TABLE: &'static str = $table;
+
+            fn uuid(&self) -> Uuid {
+                self.uuid
+            }
+
+            fn loader(loaders: &Loaders) -> &DataLoader<UuidLoader<Self>> {
+                &loaders.$field
+            }
+        }
+    };
+}
+
+keyed!(Analysis, "analyses", analyses);
+keyed!(Dataset, "datasets", datasets);
+keyed!(Dataview, "dataviews", dataviews);
+keyed!(Job, "jobs", jobs);
+keyed!(Model, "models", models);
+keyed!(Plot, "plots", plots);
+keyed!(Project, "projects", projects);
+keyed!(Statistic, "statistics", statistics);
+keyed!(User, "users", users);
@@ -0,0 +1,146 @@
+use crate::{
+    operations::{quote_identifier, quote_literal},
+    types::Db,
+    utils::{dataview_view_name, materialized_view_name},
+    Json,
+};
+use sqlx::{query, query_scalar, Result as SQLxResult};
+use uuid::Uuid;
+
+// dataviews are materialized automatically when reading them would evaluate
+// this many views, counting back to the nearest materialized ancestor
+pub const MAX_VIEW_DEPTH: i64 = 8;
+// or when postgres estimates reading them costs more than this
+pub const MAX_VIEW_COST: f64 = 1_000_000.0;
+
+// a materialized dataview keeps its view, which selects from a materialized
+// copy of the original query, so dependent views and readers are unaffected;
+// the copy numbers rows in the query's order, which the view sorts by, so
+// sorted dataviews keep their order, and keeps the query in its comment
+pub async fn materialize(db: &Db, uuid: &Uuid) -> SQLxResult<()> {
+    let view = dataview_view_name(uuid);
+    let matview = materialized_view_name(uuid);
+    let mut tx = db.data.begin().await?;
+    let definition: String =
+        query_scalar("SELECT pg_get_viewdef(CAST($1 AS REGCLASS))")
+            .bind(&view)
+            .fetch_one(&mut tx)
+            .await?;
+    let definition = definition.trim_end_matches(';');
+    let columns: Vec<String> = query_scalar(
+        r#"
+        SELECT column_name
+        FROM information_schema.columns
+        WHERE table_schema = 'public'
+        AND table_name = $1
+        ORDER BY ordinal_position
+        "#,
+    )
+    .bind(&view)
+    .fetch_all(&mut tx)
+    .await?;
+    let columns: Vec<String> =
+        columns.iter().map(|c| quote_identifier(c)).collect();
+    // ROW_NUMBER() without a window reads the subquery in its order
+    query(&format!(
+        "CREATE MATERIALIZED VIEW {} AS \
+         SELECT v.*, ROW_NUMBER() OVER () AS \"__order\" FROM ({}) v",
+        matview, definition
+    ))
+    .execute(&mut tx)
+    .await?;
+    query(&format!("CREATE INDEX ON {} (\"__order\")", matview))
+        .execute(&mut tx)
+        .await?;
+    query(&format!(
+        "COMMENT ON MATERIALIZED VIEW {} IS {}",
+        matview,
+        quote_literal(definition)
+    ))
+    .execute(&mut tx)
+    .await?;
+    query(&format!(
+        "CREATE OR REPLACE VIEW {} AS SELECT {} FROM {} ORDER BY \"__order\"",
+        view,
+        columns.join(", "),
+        matview
+    ))
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await
+}
+
+// restores the original query and drops the materialized copy
+pub async fn dematerialize(db: &Db, uuid: &Uuid) -> SQLxResult<()> {
+    let view = dataview_view_name(uuid);
+    let matview = materialized_view_name(uuid);
+    let mut tx = db.data.begin().await?;
+    // copies made before they kept the query in their comment are the query
+    let definition: String = query_scalar(
+        r#"
+        SELECT COALESCE(
+            obj_description(CAST($1 AS REGCLASS), 'pg_class'),
+            pg_get_viewdef(CAST($1 AS REGCLASS))
+        )
+        "#,
+    )
+    .bind(&matview)
+    .fetch_one(&mut tx)
+    .await?;
+    query(&format!(
+        "CREATE OR REPLACE VIEW {} AS {}",
+        view,
+        definition.trim_end_matches(';')
+    ))
+    .execute(&mut tx)
+    .await?;
+    query(&format!("DROP MATERIALIZED VIEW {}", matview))
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await
+}
+
+// drops a dataview's view and any materialized copy of it
+pub async fn drop_views(db: &Db, uuid: &Uuid) -> SQLxResult<()> {
+    let mut tx = db.data.begin().await?;
+    query(&format!("DROP VIEW IF EXISTS {}", dataview_view_name(uuid)))
+        .execute(&mut tx)
+        .await?;
+    query(&format!(
+        "DROP MATERIALIZED VIEW IF EXISTS {}",
+        materialized_view_name(uuid)
+    ))
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await
+}
+
+pub async fn refresh(db: &Db, uuid: &Uuid) -> SQLxResult<()> {
+    query(&format!(
+        "REFRESH MATERIALIZED VIEW {}",
+        materialized_view_name(uuid)
+    ))
+    .execute(&db.data)
+    .await
+    .map(|_| ())
+}
+
+// whether a dataview `depth` views deep is worth materializing
+pub async fn is_expensive(
+    db: &Db,
+    uuid: &Uuid,
+    depth: i64,
+) -> SQLxResult<bool> {
+    if depth >= MAX_VIEW_DEPTH {
+        return Ok(true);
+    }
+    let plan: Json = query_scalar(&format!(
+        "EXPLAIN (FORMAT JSON) SELECT * FROM {}",
+        dataview_view_name(uuid)
+    ))
+    .fetch_one(&db.data)
+    .await?;
+    Ok(plan[0]["Plan"]["Total Cost"]
+        .as_f64()
+        .map_or(false, |cost| cost > MAX_VIEW_COST))
+}
@@ -1,5 +1,5 @@
 use crate::{
-    gql::data,
+    loaders::load,
     models::{Dataset, Dataview, Role},
     types::Db,
     utils::{dataset_table_name, dataview_view_name},
@@ -142,17 +142,11 @@ impl Analysis {
     }
 
     pub async fn dataset(&self, ctx: &Context<'_>) -> GQLResult<Dataset> {
-        let d = data(ctx)?;
-        Dataset::get(&d.db, &self.dataset_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.dataset_uuid).await
     }
 
     pub async fn dataview(&self, ctx: &Context<'_>) -> GQLResult<Dataview> {
-        let d = data(ctx)?;
-        Dataview::get(&d.db, &self.dataview_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.dataview_uuid).await
     }
 
     pub async fn name(&self) -> &String {
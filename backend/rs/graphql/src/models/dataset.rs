@@ -1,7 +1,12 @@
 use crate::{
     gql::data,
+    loaders::load,
     models::{Project, Role, Status},
-    types::{ColumnDataType, Db, Json},
+    operations::{sort::Sort, Relation},
+    profile::{profile, ColumnProfile},
+    rows::{rows, sample_rows, Rows, SampleStrategy},
+    schema::schema,
+    types::{Column, Db, Json},
     utils::dataset_table_name,
 };
 use async_graphql::{Context, Json as GQLJson, Result as GQLResult, ID};
@@ -21,6 +26,8 @@ pub struct Dataset {
     pub name: String,
     pub uri: String,
     pub status: Status,
+    // recorded when the dataset is loaded
+    pub n_rows: Option<i64>,
 }
 
 impl Dataset {
@@ -50,6 +57,37 @@ impl Dataset {
             .await
     }
 
+    pub async fn set_status(
+        db: &Db,
+        uuid: &Uuid,
+        status: Status,
+    ) -> SQLxResult<Self> {
+        query_as("UPDATE datasets SET status = $2 WHERE uuid = $1 RETURNING *")
+            .bind(uuid)
+            .bind(status)
+            .fetch_one(&db.meta)
+            .await
+    }
+
+    pub async fn complete(
+        db: &Db,
+        uuid: &Uuid,
+        n_rows: i64,
+    ) -> SQLxResult<Self> {
+        query_as(
+            r#"
+            UPDATE datasets
+            SET status = 'completed', n_rows = $2
+            WHERE uuid = $1
+            RETURNING *
+            "#,
+        )
+        .bind(uuid)
+        .bind(n_rows)
+        .fetch_one(&db.meta)
+        .await
+    }
+
     pub async fn rename(db: &Db, uuid: &Uuid, name: &str) -> SQLxResult<Self> {
         query_as(
             r#"
@@ -108,10 +146,7 @@ impl Dataset {
     }
 
     pub async fn project(&self, ctx: &Context<'_>) -> GQLResult<Project> {
-        let d = data(ctx)?;
-        Project::get(&d.db, &self.project_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.project_uuid).await
     }
 
     pub async fn name(&self) -> &String {
@@ -122,26 +157,16 @@ impl Dataset {
         &self.status
     }
 
-    pub async fn schema(
-        &self,
-        ctx: &Context<'_>,
-    ) -> Option<Vec<ColumnDataType>> {
+    pub async fn schema(&self, ctx: &Context<'_>) -> Option<Vec<Column>> {
         let d = data(ctx).ok()?;
-        let table_name = dataset_table_name(&self.uuid);
-        query_as(
-            r#"
-            SELECT column_name, data_type
-            FROM information_schema.columns
-            WHERE table_name = $1
-            "#,
-        )
-        .bind(&table_name)
-        .fetch_all(&d.db.data)
-        .await
-        .ok()
+        schema(&d.db, &dataset_table_name(&self.uuid)).await.ok()
     }
 
+    // counted for datasets loaded before row counts were recorded
     pub async fn n_rows(&self, ctx: &Context<'_>) -> Option<i64> {
+        if self.n_rows.is_some() {
+            return self.n_rows;
+        }
         let d = data(ctx).ok()?;
         let table = dataset_table_name(&self.uuid);
         query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {}", &table))
@@ -150,22 +175,60 @@ impl Dataset {
             .ok()
     }
 
+    // up to `n` rows, the first rows by default
     pub async fn sample_rows(
         &self,
         ctx: &Context<'_>,
-    ) -> Option<GQLJson<Json>> {
-        let d = data(ctx).ok()?;
-        let table_name = dataset_table_name(&self.uuid);
-        query_scalar::<_, Json>(&format!(
-            r#"
-            SELECT JSON_AGG(t)
-            FROM (SELECT * FROM {} TABLESAMPLE SYSTEM_ROWS(100)) t
-            "#,
-            &table_name
-        ))
-        .fetch_one(&d.db.data)
-        .await
-        .map(|v| GQLJson(v))
-        .ok()
+        n: Option<i32>,
+        strategy: Option<SampleStrategy>,
+        seed: Option<i32>,
+        column: Option<String>,
+    ) -> GQLResult<Option<GQLJson<Json>>> {
+        let d = data(ctx)?;
+        let relation = Relation::dataset(&d.db, &self.uuid).await?;
+        sample_rows(&d.db, &relation, n, strategy, seed, column).await
+    }
+
+    // null and distinct counts, range, top values and histogram of a column
+    pub async fn column_profile(
+        &self,
+        ctx: &Context<'_>,
+        column: String,
+        top_values: Option<i32>,
+        bins: Option<i32>,
+    ) -> GQLResult<ColumnProfile> {
+        let d = data(ctx)?;
+        let relation = Relation::dataset(&d.db, &self.uuid).await?;
+        let mut profiles =
+            profile(&d.db, &relation, Some(vec![column]), top_values, bins)
+                .await?;
+        Ok(profiles.remove(0))
+    }
+
+    // column profiles of `columns`, or of every column
+    pub async fn profile(
+        &self,
+        ctx: &Context<'_>,
+        columns: Option<Vec<String>>,
+        top_values: Option<i32>,
+        bins: Option<i32>,
+    ) -> GQLResult<Vec<ColumnProfile>> {
+        let d = data(ctx)?;
+        let relation = Relation::dataset(&d.db, &self.uuid).await?;
+        profile(&d.db, &relation, columns, top_values, bins).await
+    }
+
+    // pages through rows in `orderBy` order, optionally projecting `columns`
+    pub async fn rows(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        order_by: Option<Vec<Sort>>,
+        columns: Option<Vec<String>>,
+    ) -> GQLResult<Rows> {
+        let d = data(ctx)?;
+        let relation = Relation::dataset(&d.db, &self.uuid).await?;
+        rows(&d.db, &relation, first, after, order_by, columns).await
     }
 }
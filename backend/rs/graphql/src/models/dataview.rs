@@ -1,7 +1,12 @@
 use crate::{
     gql::data,
+    loaders::load,
     models::{Analysis, Role, Status},
-    types::{ColumnDataType, Db},
+    operations::{sort::Sort, Relation},
+    profile::{profile, ColumnProfile},
+    rows::{rows, sample_rows, Rows, SampleStrategy},
+    schema::schema,
+    types::{Column, Db},
     utils::dataview_view_name,
 };
 use async_graphql::{Context, Enum, Json as GQLJson, Result as GQLResult, ID};
@@ -21,12 +26,33 @@ use uuid::Uuid;
 #[sqlx(rename_all = "snake_case")]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Operation {
+    Bin,
+    Cast,
     Create,
+    Distinct,
+    DropNulls,
     Filter,
+    Impute,
+    Join,
+    Limit,
     Mutate,
+    Pivot,
+    Sample,
     Select,
     Sort,
     Summarize,
+    Transform,
+    Union,
+    Unpivot,
+    Window,
+}
+
+// a dataset or dataview that a dataview's view reads from besides its parent,
+// which can't be deleted while the dataview exists
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Reference {
+    Dataset(Uuid),
+    Dataview(Uuid),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, FromRow)]
@@ -39,34 +65,72 @@ pub struct Dataview {
     pub operation: Operation,
     pub args: Option<Json>,
     pub status: Status,
+    pub materialized: bool,
+    pub refreshed_at: Option<DateTime<Utc>>,
 }
 
 impl Dataview {
     pub async fn create(
         db: &Db,
+        uuid: &Uuid,
         dataview_uuid: &Uuid,
         operation: &Operation,
         args: &Json,
+        status: &Status,
+        materialized: bool,
+        references: &[Reference],
     ) -> SQLxResult<Self> {
-        query_as(
+        let mut tx = db.meta.begin().await?;
+        let dataview: Self = query_as(
             r#"
             INSERT INTO dataviews (
+                uuid,
                 analysis_uuid,
                 parent_uuid,
                 operation,
-                args
+                args,
+                status,
+                materialized,
+                refreshed_at
             )
-            SELECT analysis_uuid, uuid, $2, $3
+            SELECT $1, analysis_uuid, uuid, $3, $4, $5, $6,
+                CASE WHEN $6 THEN NOW() END
             FROM dataviews
-            WHERE uuid = $1
+            WHERE uuid = $2
             RETURNING *
             "#,
         )
+        .bind(uuid)
         .bind(dataview_uuid)
         .bind(operation)
         .bind(args)
-        .fetch_one(&db.meta)
-        .await
+        .bind(status)
+        .bind(materialized)
+        .fetch_one(&mut tx)
+        .await?;
+        for reference in references {
+            let (dataset_uuid, referenced_uuid) = match reference {
+                Reference::Dataset(uuid) => (Some(uuid), None),
+                Reference::Dataview(uuid) => (None, Some(uuid)),
+            };
+            query(
+                r#"
+                INSERT INTO dataview_references (
+                    dataview_uuid,
+                    dataset_uuid,
+                    referenced_dataview_uuid
+                )
+                VALUES ($1, $2, $3)
+                "#,
+            )
+            .bind(uuid)
+            .bind(dataset_uuid)
+            .bind(referenced_uuid)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(dataview)
     }
 
     pub async fn get(db: &Db, uuid: &Uuid) -> SQLxResult<Self> {
@@ -76,6 +140,116 @@ impl Dataview {
             .await
     }
 
+    // the dataview and its ancestors, starting from the root
+    pub async fn ancestors(db: &Db, uuid: &Uuid) -> SQLxResult<Vec<Self>> {
+        query_as(
+            r#"
+            WITH RECURSIVE chain AS (
+                SELECT dv.*, 0 AS depth
+                FROM dataviews dv
+                WHERE dv.uuid = $1
+                UNION ALL
+                SELECT dv.*, c.depth + 1
+                FROM chain c
+                JOIN dataviews dv
+                ON c.parent_uuid = dv.uuid
+                AND c.uuid != c.parent_uuid
+            )
+            SELECT *
+            FROM chain
+            ORDER BY depth DESC
+            "#,
+        )
+        .bind(uuid)
+        .fetch_all(&db.meta)
+        .await
+    }
+
+    // the number of views evaluated to read the dataview, up to and
+    // including the nearest materialized ancestor
+    pub async fn view_depth(db: &Db, uuid: &Uuid) -> SQLxResult<i64> {
+        let ancestors = Self::ancestors(db, uuid).await?;
+        let depth = ancestors
+            .iter()
+            .rev()
+            .position(|dv| dv.materialized)
+            .map_or(ancestors.len(), |i| i + 1);
+        Ok(depth as i64)
+    }
+
+    pub async fn set_materialized(
+        db: &Db,
+        uuid: &Uuid,
+        materialized: bool,
+    ) -> SQLxResult<Self> {
+        query_as(
+            r#"
+            UPDATE dataviews
+            SET materialized = $2,
+                refreshed_at = CASE WHEN $2 THEN NOW() END
+            WHERE uuid = $1
+            RETURNING *
+            "#,
+        )
+        .bind(uuid)
+        .bind(materialized)
+        .fetch_one(&db.meta)
+        .await
+    }
+
+    // marks a dataview whose view has been created completed
+    pub async fn complete(
+        db: &Db,
+        uuid: &Uuid,
+        materialized: bool,
+    ) -> SQLxResult<Self> {
+        query_as(
+            r#"
+            UPDATE dataviews
+            SET status = 'completed',
+                materialized = $2,
+                refreshed_at = CASE WHEN $2 THEN NOW() END
+            WHERE uuid = $1
+            RETURNING *
+            "#,
+        )
+        .bind(uuid)
+        .bind(materialized)
+        .fetch_one(&db.meta)
+        .await
+    }
+
+    pub async fn refreshed(db: &Db, uuid: &Uuid) -> SQLxResult<Self> {
+        query_as(
+            r#"
+            UPDATE dataviews
+            SET refreshed_at = NOW()
+            WHERE uuid = $1
+            RETURNING *
+            "#,
+        )
+        .bind(uuid)
+        .fetch_one(&db.meta)
+        .await
+    }
+
+    pub async fn project_uuid(db: &Db, uuid: &Uuid) -> SQLxResult<Uuid> {
+        query_scalar(
+            r#"
+            SELECT d.project_uuid
+            FROM dataviews dv
+            JOIN analyses a
+            ON dv.analysis_uuid = a.uuid
+            JOIN datasets d
+            ON a.dataset_uuid = d.uuid
+            WHERE dv.uuid = $1
+            "#,
+        )
+        .bind(uuid)
+        .fetch_one(&db.meta)
+        .await
+    }
+
     pub async fn role(
         db: &Db,
         uuid: &Uuid,
@@ -124,17 +298,11 @@ impl Dataview {
     }
 
     pub async fn analysis(&self, ctx: &Context<'_>) -> GQLResult<Analysis> {
-        let d = data(ctx)?;
-        Analysis::get(&d.db, &self.analysis_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.analysis_uuid).await
     }
 
     pub async fn parent(&self, ctx: &Context<'_>) -> GQLResult<Self> {
-        let d = data(ctx)?;
-        Self::get(&d.db, &self.parent_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.parent_uuid).await
     }
 
     pub async fn operation(&self) -> &Operation {
@@ -149,23 +317,19 @@ impl Dataview {
         &self.status
     }
 
-    pub async fn schema(
-        &self,
-        ctx: &Context<'_>,
-    ) -> Option<Vec<ColumnDataType>> {
+    // whether reads come from a materialized copy, which is as current as
+    // `refreshed_at`
+    pub async fn materialized(&self) -> bool {
+        self.materialized
+    }
+
+    pub async fn refreshed_at(&self) -> Option<DateTime<Utc>> {
+        self.refreshed_at
+    }
+
+    pub async fn schema(&self, ctx: &Context<'_>) -> Option<Vec<Column>> {
         let d = data(ctx).ok()?;
-        let view = dataview_view_name(&self.uuid);
-        query_as(
-            r#"
-            SELECT column_name, data_type
-            FROM information_schema.columns
-            WHERE table_name = $1
-            "#,
-        )
-        .bind(&view)
-        .fetch_all(&d.db.data)
-        .await
-        .ok()
+        schema(&d.db, &dataview_view_name(&self.uuid)).await.ok()
     }
 
     pub async fn n_rows(&self, ctx: &Context<'_>) -> Option<i64> {
@@ -177,22 +341,60 @@ impl Dataview {
             .ok()
     }
 
+    // up to `n` rows, the first rows by default
     pub async fn sample_rows(
         &self,
         ctx: &Context<'_>,
-    ) -> Option<GQLJson<Json>> {
-        let d = data(ctx).ok()?;
-        let view = dataview_view_name(&self.uuid);
-        query_scalar::<_, Json>(&format!(
-            r#"
-            SELECT JSON_AGG(t)
-            FROM (SELECT * FROM {} LIMIT 100) t
-            "#,
-            &view
-        ))
-        .fetch_one(&d.db.data)
-        .await
-        .map(|v| GQLJson(v))
-        .ok()
+        n: Option<i32>,
+        strategy: Option<SampleStrategy>,
+        seed: Option<i32>,
+        column: Option<String>,
+    ) -> GQLResult<Option<GQLJson<Json>>> {
+        let d = data(ctx)?;
+        let relation = Relation::dataview(&d.db, &self.uuid).await?;
+        sample_rows(&d.db, &relation, n, strategy, seed, column).await
+    }
+
+    // null and distinct counts, range, top values and histogram of a column
+    pub async fn column_profile(
+        &self,
+        ctx: &Context<'_>,
+        column: String,
+        top_values: Option<i32>,
+        bins: Option<i32>,
+    ) -> GQLResult<ColumnProfile> {
+        let d = data(ctx)?;
+        let relation = Relation::dataview(&d.db, &self.uuid).await?;
+        let mut profiles =
+            profile(&d.db, &relation, Some(vec![column]), top_values, bins)
+                .await?;
+        Ok(profiles.remove(0))
+    }
+
+    // column profiles of `columns`, or of every column
+    pub async fn profile(
+        &self,
+        ctx: &Context<'_>,
+        columns: Option<Vec<String>>,
+        top_values: Option<i32>,
+        bins: Option<i32>,
+    ) -> GQLResult<Vec<ColumnProfile>> {
+        let d = data(ctx)?;
+        let relation = Relation::dataview(&d.db, &self.uuid).await?;
+        profile(&d.db, &relation, columns, top_values, bins).await
+    }
+
+    // pages through rows in `orderBy` order, optionally projecting `columns`
+    pub async fn rows(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        order_by: Option<Vec<Sort>>,
+        columns: Option<Vec<String>>,
+    ) -> GQLResult<Rows> {
+        let d = data(ctx)?;
+        let relation = Relation::dataview(&d.db, &self.uuid).await?;
+        rows(&d.db, &relation, first, after, order_by, columns).await
     }
 }
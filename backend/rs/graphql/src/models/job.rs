@@ -0,0 +1,338 @@
+use crate::{jobs::JobPayload, models::Status, types::Db, GenericError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use sqlx::{self, query, query_as, FromRow, Result as SQLxResult};
+use uuid::Uuid;
+
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, sqlx::Type,
+)]
+#[sqlx(rename = "JOB_KIND")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    UploadDataset,
+    CreateStatistic,
+    CreatePlot,
+    CreateModel,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct Job {
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub uuid: Uuid,
+    pub project_uuid: Uuid,
+    pub kind: Kind,
+    pub payload: Json,
+    pub status: Status,
+    pub priority: i32,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_after: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub locked_by: Option<String>,
+    pub last_error: Option<String>,
+}
+
+impl Job {
+    pub async fn enqueue(
+        db: &Db,
+        payload: &JobPayload,
+    ) -> Result<Self, GenericError> {
+        // resolve the project through the node the job will update
+        let project = match payload.table() {
+            "datasets" => r#"
+                SELECT project_uuid
+                FROM datasets
+                WHERE uuid = $1
+            "#
+            .to_owned(),
+            "dataviews" => r#"
+                SELECT ds.project_uuid
+                FROM dataviews dv
+                JOIN analyses a
+                ON dv.analysis_uuid = a.uuid
+                JOIN datasets ds
+                ON a.dataset_uuid = ds.uuid
+                WHERE dv.uuid = $1
+            "#
+            .to_owned(),
+            table => format!(
+                r#"
+                SELECT ds.project_uuid
+                FROM {} x
+                JOIN dataviews dv
+                ON x.dataview_uuid = dv.uuid
+                JOIN analyses a
+                ON dv.analysis_uuid = a.uuid
+                JOIN datasets ds
+                ON a.dataset_uuid = ds.uuid
+                WHERE x.uuid = $1
+                "#,
+                table
+            ),
+        };
+        query_as(&format!(
+            r#"
+            INSERT INTO jobs (project_uuid, kind, payload, priority)
+            SELECT project_uuid, $2, $3, $4
+            FROM ({}) p
+            RETURNING *
+            "#,
+            project
+        ))
+        .bind(payload.uuid())
+        .bind(payload.kind())
+        .bind(payload.payload()?)
+        .bind(payload.priority())
+        .fetch_one(&db.meta)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    pub async fn get(db: &Db, uuid: &Uuid) -> SQLxResult<Self> {
+        query_as("SELECT * FROM jobs WHERE uuid = $1")
+            .bind(uuid)
+            .fetch_one(&db.meta)
+            .await
+    }
+
+    // claims the next queued job whose project has fewer than
+    // `project_concurrency` running jobs
+    pub async fn claim(
+        db: &Db,
+        worker: &str,
+        project_concurrency: i64,
+    ) -> SQLxResult<Option<Self>> {
+        let mut tx = db.meta.begin().await?;
+        let candidate: Option<(Uuid, Uuid)> = query_as(
+            r#"
+            SELECT j.uuid, j.project_uuid
+            FROM jobs j
+            WHERE j.status = 'queued'
+            AND j.run_after <= NOW()
+            AND (
+                SELECT COUNT(*)
+                FROM jobs r
+                WHERE r.project_uuid = j.project_uuid
+                AND r.status = 'running'
+            ) < $1
+            ORDER BY j.priority DESC, j.created_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(project_concurrency)
+        .fetch_optional(&mut tx)
+        .await?;
+        let (uuid, project_uuid) = match candidate {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        // claims are serialized per project, so the running jobs are counted
+        // again once any concurrent claim for the project has committed
+        query("SELECT pg_advisory_xact_lock(hashtext($1::TEXT))")
+            .bind(project_uuid)
+            .execute(&mut tx)
+            .await?;
+        let job = query_as(
+            r#"
+            UPDATE jobs
+            SET status = 'running',
+                attempts = attempts + 1,
+                locked_at = NOW(),
+                locked_by = $2
+            WHERE uuid = $1
+            AND (
+                SELECT COUNT(*)
+                FROM jobs r
+                WHERE r.project_uuid = $3
+                AND r.status = 'running'
+            ) < $4
+            RETURNING *
+            "#,
+        )
+        .bind(uuid)
+        .bind(worker)
+        .bind(project_uuid)
+        .bind(project_concurrency)
+        .fetch_optional(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(job)
+    }
+
+    pub async fn complete(db: &Db, uuid: &Uuid) -> SQLxResult<Self> {
+        query_as(
+            r#"
+            UPDATE jobs
+            SET status = 'completed',
+                locked_at = NULL,
+                locked_by = NULL
+            WHERE uuid = $1
+            RETURNING *
+            "#,
+        )
+        .bind(uuid)
+        .fetch_one(&db.meta)
+        .await
+    }
+
+    // requeues the job with quadratic backoff until it runs out of attempts
+    pub async fn fail(db: &Db, uuid: &Uuid, error: &str) -> SQLxResult<Self> {
+        query_as(
+            r#"
+            UPDATE jobs
+            SET status = (
+                    CASE WHEN attempts < max_attempts
+                    THEN 'queued'
+                    ELSE 'failed'
+                    END
+                )::STATUS,
+                run_after = NOW() + attempts * attempts * INTERVAL '10 seconds',
+                locked_at = NULL,
+                locked_by = NULL,
+                last_error = $2
+            WHERE uuid = $1
+            RETURNING *
+            "#,
+        )
+        .bind(uuid)
+        .bind(error)
+        .fetch_one(&db.meta)
+        .await
+    }
+
+    // returns jobs left running by workers that died, e.g. out of memory
+    pub async fn requeue_stale(
+        db: &Db,
+        timeout_seconds: i64,
+    ) -> SQLxResult<Vec<Self>> {
+        query_as(
+            r#"
+            UPDATE jobs
+            SET status = (
+                    CASE WHEN attempts < max_attempts
+                    THEN 'queued'
+                    ELSE 'failed'
+                    END
+                )::STATUS,
+                locked_at = NULL,
+                locked_by = NULL,
+                last_error = 'timed out'
+            WHERE status = 'running'
+            AND locked_at < NOW() - $1::FLOAT8 * INTERVAL '1 second'
+            RETURNING *
+            "#,
+        )
+        .bind(timeout_seconds)
+        .fetch_all(&db.meta)
+        .await
+    }
+
+    pub fn job_payload(&self) -> Result<JobPayload, GenericError> {
+        JobPayload::from_parts(self.kind, &self.payload)
+    }
+
+    // marks the dataset, dataview, statistic, plot, or model as failed, since
+    // the lambda may have died before it could
+    pub async fn fail_target(&self, db: &Db) -> Result<(), GenericError> {
+        let payload = self.job_payload()?;
+        query(&format!(
+            r#"
+            UPDATE {}
+            SET status = 'failed'
+            WHERE uuid = $1
+            AND status != 'completed'
+            "#,
+            payload.table()
+        ))
+        .bind(payload.uuid())
+        .execute(&db.meta)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{Dataset, Project, User},
+        ContextData, UploadDatasetPayload,
+    };
+    use std::env;
+    use tokio_compat_02::FutureExt;
+
+    async fn enqueue_upload(
+        db: &Db,
+        project: &Project,
+    ) -> Result<Job, GenericError> {
+        let ds = Dataset::create(db, &project.uuid, "iris", "iris.csv").await?;
+        let payload = JobPayload::UploadDataset(UploadDatasetPayload {
+            uri: ds.uri.clone(),
+            uuid: ds.uuid,
+            format: None,
+        });
+        Job::enqueue(db, &payload).await
+    }
+
+    #[tokio::test]
+    async fn claims_fails_and_requeues_jobs() -> Result<(), GenericError> {
+        env::set_var("RUN_MODE", "local");
+        _claims_fails_and_requeues_jobs().compat().await
+    }
+
+    async fn _claims_fails_and_requeues_jobs() -> Result<(), GenericError> {
+        let db = ContextData::default().await?.db;
+        query("DELETE FROM jobs").execute(&db.meta).await?;
+        let name = format!("queue-{}", Uuid::new_v4());
+        let email = format!("{}@motoko.ai", name);
+        let user = User::create(&db, "Queue", &name, &email).await?;
+        let project = Project::create(&db, "queue", &user.uuid).await?;
+        let first = enqueue_upload(&db, &project).await?;
+        let second = enqueue_upload(&db, &project).await?;
+
+        // concurrent workers never exceed the project's concurrency
+        let (a, b) = futures::join!(
+            Job::claim(&db, "worker-a", 1),
+            Job::claim(&db, "worker-b", 1)
+        );
+        let claimed: Vec<Job> = vec![a?, b?].into_iter().flatten().collect();
+        assert_eq!(claimed.len(), 1);
+        let running = &claimed[0];
+        assert_eq!(running.uuid, first.uuid);
+        assert_eq!(running.status, Status::Running);
+        assert_eq!(running.attempts, 1);
+        assert!(Job::claim(&db, "worker-a", 1).await?.is_none());
+
+        // failures are retried after a backoff
+        let failed = Job::fail(&db, &running.uuid, "boom").await?;
+        assert_eq!(failed.status, Status::Queued);
+        assert_eq!(failed.last_error.as_deref(), Some("boom"));
+        assert_eq!(failed.locked_by, None);
+        assert!(failed.run_after > failed.updated_at);
+        let next = Job::claim(&db, "worker-a", 1).await?;
+        assert_eq!(next.map(|j| j.uuid), Some(second.uuid));
+
+        // jobs left running by dead workers are requeued
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let stale = Job::requeue_stale(&db, 0).await?;
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].uuid, second.uuid);
+        assert_eq!(stale[0].status, Status::Queued);
+        assert_eq!(stale[0].last_error.as_deref(), Some("timed out"));
+
+        // and fail for good once out of attempts
+        query("UPDATE jobs SET max_attempts = 1 WHERE uuid = $1")
+            .bind(&first.uuid)
+            .execute(&db.meta)
+            .await?;
+        let failed = Job::fail(&db, &first.uuid, "boom").await?;
+        assert_eq!(failed.status, Status::Failed);
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 mod analysis;
 mod dataset;
 mod dataview;
+mod job;
 mod model;
 mod plot;
 mod project;
@@ -12,12 +13,19 @@ mod user_refresh_token;
 
 pub use analysis::Analysis;
 pub use dataset::Dataset;
-pub use dataview::{Dataview, Operation};
+pub use dataview::{Dataview, Operation, Reference as DataviewReference};
+pub use job::{Job, Kind as JobKind};
 pub use model::Model;
-pub use plot::{Plot, Type as PlotType};
+pub use plot::{
+    BarArgs, HistogramArgs, LineArgs, Plot, PlotArgs, ScatterArgs, SmoothArgs,
+    Type as PlotType,
+};
 pub use project::Project;
 pub use project_user_role::{ProjectUserRole, Role};
-pub use statistic::{Statistic, Type as StatisticType};
+pub use statistic::{
+    CorrelationArgs, Statistic, StatisticArgs, SummaryArgs,
+    Type as StatisticType,
+};
 pub use status::Status;
 pub use user::User;
 pub use user_refresh_token::UserRefreshToken;
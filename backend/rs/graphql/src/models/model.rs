@@ -1,5 +1,5 @@
 use crate::{
-    gql::data,
+    loaders::load,
     models::{Dataview, Role, Status},
     types::Db,
 };
@@ -122,10 +122,7 @@ impl Model {
     }
 
     pub async fn dataview(&self, ctx: &Context<'_>) -> GQLResult<Dataview> {
-        let d = data(ctx)?;
-        Dataview::get(&d.db, &self.dataview_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.dataview_uuid).await
     }
 
     pub async fn name(&self) -> &String {
@@ -1,10 +1,13 @@
 use crate::{
     gql::data,
+    loaders::load,
     models::{Dataview, Role, Status},
-    utils::get_presigned_url,
+    utils::{get_presigned_url, json_string, one_of},
     Db, Error,
 };
-use async_graphql::{Context, Enum, Json as GQLJson, Result as GQLResult, ID};
+use async_graphql::{
+    Context, Enum, InputObject, Json as GQLJson, Result as GQLResult, ID,
+};
 use chrono::{DateTime, Utc};
 use node_derive::node;
 use serde::{Deserialize, Serialize};
@@ -27,6 +30,143 @@ pub enum Type {
     Smooth,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct BarArgs {
+    pub x: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_y: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct HistogramArgs {
+    pub x: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_y: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct LineArgs {
+    pub x: String,
+    pub y: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_y: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct ScatterArgs {
+    pub x: String,
+    pub y: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shape: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_y: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct SmoothArgs {
+    pub x: String,
+    pub y: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shape: Option<String>,
+    // whether to show the standard error band
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub se: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_y: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+// set exactly one field, which determines the plot type
+#[derive(Debug, Clone, InputObject)]
+pub struct PlotArgs {
+    pub bar: Option<BarArgs>,
+    pub histogram: Option<HistogramArgs>,
+    pub line: Option<LineArgs>,
+    pub scatter: Option<ScatterArgs>,
+    pub smooth: Option<SmoothArgs>,
+}
+
+impl PlotArgs {
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        if let Some(a) = &self.bar {
+            columns.push(&a.x);
+            columns.extend(&a.color);
+        }
+        if let Some(a) = &self.histogram {
+            columns.push(&a.x);
+        }
+        if let Some(a) = &self.line {
+            columns.extend(vec![&a.x, &a.y]);
+            columns.extend(&a.color);
+        }
+        if let Some(a) = &self.scatter {
+            columns.extend(vec![&a.x, &a.y]);
+            columns.extend(&a.color);
+            columns.extend(&a.shape);
+        }
+        if let Some(a) = &self.smooth {
+            columns.extend(vec![&a.x, &a.y]);
+            columns.extend(&a.color);
+            columns.extend(&a.shape);
+        }
+        // every plot can be faceted into a grid of subplots
+        let facets = vec![
+            self.bar.as_ref().map(|a| (&a.facet_x, &a.facet_y)),
+            self.histogram.as_ref().map(|a| (&a.facet_x, &a.facet_y)),
+            self.line.as_ref().map(|a| (&a.facet_x, &a.facet_y)),
+            self.scatter.as_ref().map(|a| (&a.facet_x, &a.facet_y)),
+            self.smooth.as_ref().map(|a| (&a.facet_x, &a.facet_y)),
+        ];
+        for (facet_x, facet_y) in facets.into_iter().flatten() {
+            columns.extend(facet_x);
+            columns.extend(facet_y);
+        }
+        columns.into_iter().map(|c| c.as_str()).collect()
+    }
+
+    pub fn into_parts(self) -> Result<(Type, Json), Error> {
+        let (type_, args) = one_of(
+            "PlotArgs",
+            vec![
+                self.bar.map(|a| (Type::Bar, json_string(&a))),
+                self.histogram.map(|a| (Type::Histogram, json_string(&a))),
+                self.line.map(|a| (Type::Line, json_string(&a))),
+                self.scatter.map(|a| (Type::Scatter, json_string(&a))),
+                self.smooth.map(|a| (Type::Smooth, json_string(&a))),
+            ],
+        )?;
+        Ok((type_, args?))
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct Plot {
     pub created_at: DateTime<Utc>,
@@ -134,10 +274,7 @@ impl Plot {
     }
 
     pub async fn dataview(&self, ctx: &Context<'_>) -> GQLResult<Dataview> {
-        let d = data(ctx)?;
-        Dataview::get(&d.db, &self.dataview_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.dataview_uuid).await
     }
 
     pub async fn name(&self) -> &String {
@@ -1,5 +1,5 @@
 use crate::{
-    gql::data,
+    loaders::load,
     models::{Project, User},
     types::Db,
 };
@@ -152,17 +152,11 @@ impl ProjectUserRole {
     }
 
     pub async fn project(&self, ctx: &Context<'_>) -> GQLResult<Project> {
-        let d = data(ctx)?;
-        Project::get(&d.db, &self.project_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.project_uuid).await
     }
 
     pub async fn user(&self, ctx: &Context<'_>) -> GQLResult<User> {
-        let d = data(ctx)?;
-        User::get(&d.db, &self.user_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.user_uuid).await
     }
 
     pub async fn role(&self) -> &Role {
@@ -1,9 +1,13 @@
 use crate::{
-    gql::data,
+    loaders::load,
     models::{Dataview, Role, Status},
     types::Db,
+    utils::{json_string, one_of},
+    Error,
+};
+use async_graphql::{
+    Context, Enum, InputObject, Json as GQLJson, Result as GQLResult, ID,
 };
-use async_graphql::{Context, Enum, Json as GQLJson, Result as GQLResult, ID};
 use chrono::{DateTime, Utc};
 use node_derive::node;
 use serde::{Deserialize, Serialize};
@@ -23,6 +27,49 @@ pub enum Type {
     Summary,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct CorrelationArgs {
+    pub x: String,
+    pub y: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct SummaryArgs {
+    pub x: String,
+}
+
+// set exactly one field, which determines the statistic type
+#[derive(Debug, Clone, InputObject)]
+pub struct StatisticArgs {
+    pub correlation: Option<CorrelationArgs>,
+    pub summary: Option<SummaryArgs>,
+}
+
+impl StatisticArgs {
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        if let Some(a) = &self.correlation {
+            columns.extend(vec![a.x.as_str(), a.y.as_str()]);
+        }
+        if let Some(a) = &self.summary {
+            columns.push(a.x.as_str());
+        }
+        columns
+    }
+
+    pub fn into_parts(self) -> Result<(Type, Json), Error> {
+        let (type_, args) = one_of(
+            "StatisticArgs",
+            vec![
+                self.correlation
+                    .map(|a| (Type::Correlation, json_string(&a))),
+                self.summary.map(|a| (Type::Summary, json_string(&a))),
+            ],
+        )?;
+        Ok((type_, args?))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct Statistic {
     pub created_at: DateTime<Utc>,
@@ -113,10 +160,7 @@ impl Statistic {
     }
 
     pub async fn dataview(&self, ctx: &Context<'_>) -> GQLResult<Dataview> {
-        let d = data(ctx)?;
-        Dataview::get(&d.db, &self.dataview_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.dataview_uuid).await
     }
 
     #[graphql(name = "type")]
@@ -1,4 +1,4 @@
-use crate::{gql::data, models::User, types::Db};
+use crate::{loaders::load, models::User, types::Db};
 use async_graphql::{Context, Result as GQLResult, ID};
 use chrono::{DateTime, Utc};
 use node_derive::node;
@@ -53,10 +53,7 @@ impl UserRefreshToken {
 #[async_graphql::Object]
 impl UserRefreshToken {
     pub async fn user(&self, ctx: &Context<'_>) -> GQLResult<User> {
-        let d = data(ctx)?;
-        User::get(&d.db, &self.user_uuid)
-            .await
-            .map_err(|e| e.into())
+        load(ctx, &self.user_uuid).await
     }
 
     pub async fn value(&self) -> &String {
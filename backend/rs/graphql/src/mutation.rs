@@ -3,24 +3,26 @@ use crate::{
         credentials_for_user, validate_google_id_token, Credentials, Provider,
     },
     gql::{
-        current_user, data, get_invocation_type, graphql_id_to_uuid,
-        is_current_user, model_keys,
+        current_user, data, graphql_id_to_uuid, is_current_user, model_keys,
     },
+    ingest::format::DatasetFormat,
+    jobs::JobPayload,
+    materialization,
     models::{
-        Analysis, Dataset, Dataview, Model, Operation, Plot, PlotType, Project,
-        ProjectUserRole, Role, Statistic, StatisticType, User,
+        Analysis, Dataset, Dataview, Model, Plot, PlotArgs, Project,
+        ProjectUserRole, Role, Statistic, StatisticArgs, Status, User,
         UserRefreshToken,
     },
+    operations::{self, OperationArgs, Relation},
     types::*,
-    utils::{as_bytes, dataview_view_name, user_name_from_email},
+    utils::{dataview_view_name, user_name_from_email},
     Error,
 };
 use async_graphql::{
     Context, Error as GQLError, Json as GQLJson, Result as GQLResult, ID,
 };
-use rusoto_lambda::{InvocationRequest, Lambda};
 use serde_json::Value as Json;
-use tokio_compat_02::FutureExt;
+use sqlx::{query, Error as SQLxError};
 use uuid::Uuid;
 
 pub struct Mutation;
@@ -222,6 +224,7 @@ impl Mutation {
         project_id: ID,
         name: String,
         uri: String,
+        format: Option<DatasetFormat>,
     ) -> GQLResult<Dataset> {
         let d = data(ctx)?;
         let user = current_user(ctx)?;
@@ -238,16 +241,9 @@ impl Mutation {
         let payload = UploadDatasetPayload {
             uri: uri.clone(),
             uuid: ds.uuid.clone(),
+            format,
         };
-        let req = InvocationRequest {
-            client_context: None,
-            function_name: "motoko-uri-to-sql-db".to_owned(),
-            invocation_type: get_invocation_type(),
-            log_type: None,
-            payload: Some(as_bytes(&payload)?),
-            qualifier: None,
-        };
-        d.lambda.invoke(req).compat().await?;
+        d.jobs.run(JobPayload::UploadDataset(payload)).await?;
         Ok(ds)
     }
 
@@ -288,16 +284,18 @@ impl Mutation {
         Dataset::delete(&d.db, &dataset_uuid)
             .await
             .map(|_| dataset_id)
-            .map_err(|e| e.into())
+            .map_err(|e| referenced(e, "dataset"))
     }
 
+    // materialize defaults to materializing long or expensive chains
     pub async fn create_dataview(
         &self,
         ctx: &Context<'_>,
         analysis_id: ID,
-        operation: Operation,
-        args: GQLJson<Json>,
+        args: OperationArgs,
+        materialize: Option<bool>,
     ) -> GQLResult<Dataview> {
+        let (operation, args) = args.into_parts()?;
         let d = data(ctx)?;
         let user = current_user(ctx)?;
         let analysis_uuid = graphql_id_to_uuid(&analysis_id)?;
@@ -310,29 +308,141 @@ impl Mutation {
         let a = Analysis::get(&d.db, &analysis_uuid)
             .await
             .map_err(|e| -> GQLError { e.into() })?;
-        let dv = Dataview::create(&d.db, &a.dataview_uuid, &operation, &args)
+        let parent = Relation::dataview(&d.db, &a.dataview_uuid)
             .await
             .map_err(|e| -> GQLError { e.into() })?;
+        let project_uuid = Dataset::get(&d.db, &a.dataset_uuid)
+            .await
+            .map_err(|e| -> GQLError { e.into() })?
+            .project_uuid;
+        let (mut references, mut relations) = (Vec::new(), Vec::new());
+        for id in operations::references(&operation, &args)? {
+            let (reference, relation) =
+                operations::resolve(&d.db, &id, &user.uuid, &project_uuid)
+                    .await?;
+            references.push(reference);
+            relations.push(relation);
+        }
+        let args =
+            operations::prepare(&d.db, &operation, args, &parent).await?;
+        // compiling up front rejects invalid args before anything is created
+        let uuid = Uuid::new_v4();
+        let sql = operations::compile(
+            &operation,
+            &args,
+            &parent,
+            &relations,
+            &dataview_view_name(&uuid),
+        )?;
+        // the dataview is recorded before its view is created, so the
+        // garbage collector never finds the view without it
+        Dataview::create(
+            &d.db,
+            &uuid,
+            &a.dataview_uuid,
+            &operation,
+            &args,
+            &Status::Queued,
+            false,
+            &references,
+        )
+        .await
+        .map_err(|e| -> GQLError { e.into() })?;
+        let dv = match create_views(
+            &d.db,
+            &uuid,
+            &a.dataview_uuid,
+            &sql,
+            materialize,
+        )
+        .await
+        {
+            Ok(dv) => dv,
+            Err(e) => {
+                // nothing would ever drop a view without its dataview
+                if let Err(e) = materialization::drop_views(&d.db, &uuid).await
+                {
+                    eprintln!(
+                        "unable to drop view of dataview {}: {}",
+                        uuid, e
+                    );
+                }
+                if let Err(e) = Dataview::delete(&d.db, &uuid).await {
+                    eprintln!("unable to delete dataview {}: {}", uuid, e);
+                }
+                return Err(e);
+            }
+        };
         Analysis::point_to(&d.db, &analysis_uuid, &dv.uuid)
             .await
             .map_err(|e| -> GQLError { e.into() })?;
-        let payload = CreateDataviewPayload {
-            parent_view: dataview_view_name(&dv.parent_uuid),
-            view: dataview_view_name(&dv.uuid),
-            uuid: dv.uuid.clone(),
-            operation,
-            args: (*args).clone(),
-        };
-        let req = InvocationRequest {
-            function_name: "motoko-dataview".to_owned(),
-            invocation_type: get_invocation_type(),
-            payload: Some(as_bytes(&payload)?),
-            ..Default::default()
-        };
-        d.lambda.invoke(req).compat().await?;
         Ok(dv)
     }
 
+    pub async fn materialize_dataview(
+        &self,
+        ctx: &Context<'_>,
+        dataview_id: ID,
+        materialized: bool,
+    ) -> GQLResult<Dataview> {
+        let d = data(ctx)?;
+        let user = current_user(ctx)?;
+        let dataview_uuid = graphql_id_to_uuid(&dataview_id)?;
+        let role = Dataview::role(&d.db, &dataview_uuid, &user.uuid)
+            .await
+            .map_err(|_| -> GQLError { Error::InvalidPermissions.into() })?;
+        if role == Role::Viewer {
+            return Err(Error::RequiresEditorPermissions.into());
+        }
+        let dv = Dataview::get(&d.db, &dataview_uuid)
+            .await
+            .map_err(|e| -> GQLError { e.into() })?;
+        if dv.materialized == materialized {
+            return Ok(dv);
+        }
+        let res = match materialized {
+            true => materialization::materialize(&d.db, &dataview_uuid).await,
+            false => {
+                materialization::dematerialize(&d.db, &dataview_uuid).await
+            }
+        };
+        res.map_err(|e| -> GQLError { e.into() })?;
+        Dataview::set_materialized(&d.db, &dataview_uuid, materialized)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    // refreshes materialized ancestors first, so the dataview reflects them
+    pub async fn refresh_dataview(
+        &self,
+        ctx: &Context<'_>,
+        dataview_id: ID,
+    ) -> GQLResult<Dataview> {
+        let d = data(ctx)?;
+        let user = current_user(ctx)?;
+        let dataview_uuid = graphql_id_to_uuid(&dataview_id)?;
+        let role = Dataview::role(&d.db, &dataview_uuid, &user.uuid)
+            .await
+            .map_err(|_| -> GQLError { Error::InvalidPermissions.into() })?;
+        if role == Role::Viewer {
+            return Err(Error::RequiresEditorPermissions.into());
+        }
+        let ancestors = Dataview::ancestors(&d.db, &dataview_uuid)
+            .await
+            .map_err(|e| -> GQLError { e.into() })?;
+        for dv in ancestors.iter().filter(|dv| dv.materialized) {
+            materialization::refresh(&d.db, &dv.uuid)
+                .await
+                .map_err(|e| -> GQLError { e.into() })?;
+            Dataview::refreshed(&d.db, &dv.uuid)
+                .await
+                .map_err(|e| -> GQLError { e.into() })?;
+        }
+        Dataview::get(&d.db, &dataview_uuid)
+            .await
+            .map_err(|e| e.into())
+    }
+
     pub async fn delete_dataview(
         &self,
         ctx: &Context<'_>,
@@ -359,7 +469,7 @@ impl Mutation {
         Dataview::delete(&d.db, &dataview_uuid)
             .await
             .map(|_| dataview_id)
-            .map_err(|e| e.into())
+            .map_err(|e| referenced(e, "dataview"))
     }
 
     pub async fn create_analysis(
@@ -440,7 +550,7 @@ impl Mutation {
         Analysis::delete(&d.db, &analysis_uuid)
             .await
             .map(|_| analysis_id)
-            .map_err(|e| e.into())
+            .map_err(|e| referenced(e, "analysis"))
     }
 
     pub async fn create_role(
@@ -552,8 +662,7 @@ impl Mutation {
         &self,
         ctx: &Context<'_>,
         dataview_id: ID,
-        type_: StatisticType,
-        args: GQLJson<Json>,
+        args: StatisticArgs,
     ) -> GQLResult<Statistic> {
         let d = data(ctx)?;
         let user = current_user(ctx)?;
@@ -564,6 +673,13 @@ impl Mutation {
         if role == Role::Viewer {
             return Err(Error::RequiresEditorPermissions.into());
         }
+        let view = Relation::dataview(&d.db, &dataview_uuid)
+            .await
+            .map_err(|e| -> GQLError { e.into() })?;
+        for column in args.columns() {
+            view.column(column)?;
+        }
+        let (type_, args) = args.into_parts()?;
         let s = Statistic::create(&d.db, &dataview_uuid, &type_, &args)
             .await
             .map_err(|e| -> GQLError { e.into() })?;
@@ -571,15 +687,9 @@ impl Mutation {
             view: dataview_view_name(&dataview_uuid),
             uuid: s.uuid.clone(),
             type_,
-            args: (*args).clone(),
+            args,
         };
-        let req = InvocationRequest {
-            function_name: "motoko-statistic".to_owned(),
-            invocation_type: get_invocation_type(),
-            payload: Some(as_bytes(&payload)?),
-            ..Default::default()
-        };
-        d.lambda.invoke(req).compat().await?;
+        d.jobs.run(JobPayload::CreateStatistic(payload)).await?;
         Ok(s)
     }
 
@@ -608,8 +718,7 @@ impl Mutation {
         ctx: &Context<'_>,
         dataview_id: ID,
         name: String,
-        type_: PlotType,
-        args: GQLJson<Json>,
+        args: PlotArgs,
     ) -> GQLResult<Plot> {
         let d = data(ctx)?;
         let user = current_user(ctx)?;
@@ -620,6 +729,13 @@ impl Mutation {
         if role == Role::Viewer {
             return Err(Error::RequiresEditorPermissions.into());
         }
+        let view = Relation::dataview(&d.db, &dataview_uuid)
+            .await
+            .map_err(|e| -> GQLError { e.into() })?;
+        for column in args.columns() {
+            view.column(column)?;
+        }
+        let (type_, args) = args.into_parts()?;
         let p = Plot::create(&d.db, &dataview_uuid, &name, &type_, &args)
             .await
             .map_err(|e| -> GQLError { e.into() })?;
@@ -627,15 +743,9 @@ impl Mutation {
             view: dataview_view_name(&dataview_uuid),
             uuid: p.uuid.clone(),
             type_,
-            args: (*args).clone(),
-        };
-        let req = InvocationRequest {
-            function_name: "motoko-plot".to_owned(),
-            invocation_type: get_invocation_type(),
-            payload: Some(as_bytes(&payload)?),
-            ..Default::default()
+            args,
         };
-        d.lambda.invoke(req).compat().await?;
+        d.jobs.run(JobPayload::CreatePlot(payload)).await?;
         Ok(p)
     }
 
@@ -715,13 +825,7 @@ impl Mutation {
             features: features,
             args: argz,
         };
-        let req = InvocationRequest {
-            function_name: "motoko-model".to_owned(),
-            invocation_type: get_invocation_type(),
-            payload: Some(as_bytes(&payload)?),
-            ..Default::default()
-        };
-        d.lambda.invoke(req).compat().await?;
+        d.jobs.run(JobPayload::CreateModel(payload)).await?;
         Ok(m)
     }
 
@@ -765,3 +869,53 @@ impl Mutation {
             .map_err(|e| e.into())
     }
 }
+
+// creates the new dataview's view, materializes it if asked to, or if it's
+// long or expensive to read, and completes the dataview
+async fn create_views(
+    db: &Db,
+    uuid: &Uuid,
+    parent_uuid: &Uuid,
+    sql: &str,
+    materialize: Option<bool>,
+) -> GQLResult<Dataview> {
+    query(sql)
+        .execute(&db.data)
+        .await
+        .map_err(|e| -> GQLError { e.into() })?;
+    let materialized = match materialize {
+        Some(m) => m,
+        None => {
+            let depth = Dataview::view_depth(db, parent_uuid)
+                .await
+                .map_err(|e| -> GQLError { e.into() })?
+                + 1;
+            materialization::is_expensive(db, uuid, depth)
+                .await
+                .map_err(|e| -> GQLError { e.into() })?
+        }
+    };
+    if materialized {
+        materialization::materialize(db, uuid)
+            .await
+            .map_err(|e| -> GQLError { e.into() })?;
+    }
+    Dataview::complete(db, uuid, materialized)
+        .await
+        .map_err(|e| e.into())
+}
+
+// deleting a dataset or dataview that other dataviews read from, or one
+// whose dataviews are read from, violates their references
+fn referenced(e: SQLxError, what: &str) -> GQLError {
+    match &e {
+        SQLxError::Database(db) if db.code().as_deref() == Some("23503") => {
+            Error::InvalidArguments(format!(
+                "{} is read from by other dataviews",
+                what
+            ))
+            .into()
+        }
+        _ => e.into(),
+    }
+}
@@ -0,0 +1,253 @@
+use super::{
+    create_view, quote_identifier, quote_literal, DataType, Relation,
+    MAX_COLUMN_LENGTH,
+};
+use crate::{types::Db, Error};
+use async_graphql::{Enum, InputObject, Result as GQLResult};
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, query_scalar};
+
+pub const MAX_BINS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "BinArgs")]
+pub struct Args {
+    pub column: String,
+    pub method: Method,
+    // the number of bins for EQUAL_WIDTH and QUANTILE
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bins: Option<i32>,
+    // ascending bin boundaries, which EQUAL_WIDTH and QUANTILE compute from
+    // the parent when the dataview is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edges: Option<Vec<f64>>,
+    // one per bin, defaulting to the bin's interval, e.g. [0, 1.5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    // defaults to <column>_bin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "BinMethod")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Method {
+    EqualWidth,
+    Quantile,
+    Edges,
+}
+
+// the bin boundaries of the parent's values
+pub async fn edges(
+    db: &Db,
+    args: &Args,
+    parent: &Relation,
+) -> GQLResult<Vec<f64>> {
+    if parent.data_type(&args.column)? != DataType::Numeric {
+        return Err(Error::InvalidArguments(format!(
+            "bins require a numeric column: {}",
+            args.column
+        ))
+        .into());
+    }
+    let bins = match args.bins {
+        Some(n) if n > 0 && n as usize <= MAX_BINS => n,
+        _ => {
+            return Err(Error::InvalidArguments(format!(
+                "bins must be 1 to {}",
+                MAX_BINS
+            ))
+            .into())
+        }
+    };
+    let column = format!(
+        "CAST({} AS DOUBLE PRECISION)",
+        quote_identifier(&args.column)
+    );
+    let table = quote_identifier(&parent.name);
+    let mut edges = match args.method {
+        Method::EqualWidth => {
+            let (min, max): (Option<f64>, Option<f64>) = query_as(&format!(
+                "SELECT MIN({}), MAX({}) FROM {}",
+                column, column, table
+            ))
+            .fetch_one(&db.data)
+            .await?;
+            match (min, max) {
+                (Some(min), Some(max)) => {
+                    let width = (max - min) / bins as f64;
+                    let mut edges: Vec<f64> =
+                        (0..bins).map(|i| min + i as f64 * width).collect();
+                    edges.push(max);
+                    edges
+                }
+                _ => vec![],
+            }
+        }
+        Method::Quantile => {
+            let quantiles: Vec<String> = (0..=bins)
+                .map(|i| (i as f64 / bins as f64).to_string())
+                .collect();
+            let edges: Option<Vec<f64>> = query_scalar(&format!(
+                "SELECT PERCENTILE_CONT(ARRAY[{}]) \
+                 WITHIN GROUP (ORDER BY {}) FROM {}",
+                quantiles.join(", "),
+                column,
+                table
+            ))
+            .fetch_one(&db.data)
+            .await?;
+            edges.unwrap_or_default()
+        }
+        Method::Edges => {
+            return Err(Error::InvalidArguments(
+                "EDGES requires edges".to_owned(),
+            )
+            .into())
+        }
+    };
+    // ties between quantiles, or a column with one value, give fewer bins
+    edges.dedup();
+    Ok(edges)
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    if parent.data_type(&args.column)? != DataType::Numeric {
+        return Err(Error::InvalidArguments(format!(
+            "bins require a numeric column: {}",
+            args.column
+        )));
+    }
+    let edges = args.edges.as_ref().ok_or_else(|| {
+        Error::InvalidArguments("bin edges are required".to_owned())
+    })?;
+    if edges.len() < 2 || edges.len() > MAX_BINS + 1 {
+        return Err(Error::InvalidArguments(format!(
+            "bins must have 2 to {} edges, not {}",
+            MAX_BINS + 1,
+            edges.len()
+        )));
+    }
+    if edges.iter().any(|e| !e.is_finite())
+        || edges.windows(2).any(|w| w[0] >= w[1])
+    {
+        return Err(Error::InvalidArguments(
+            "bin edges must be finite and strictly increasing".to_owned(),
+        ));
+    }
+    let n = edges.len() - 1;
+    let labels: Vec<String> = match &args.labels {
+        Some(labels) if labels.len() == n => labels.clone(),
+        Some(labels) => {
+            return Err(Error::InvalidArguments(format!(
+                "{} bins require {} labels, not {}",
+                n,
+                n,
+                labels.len()
+            )))
+        }
+        // the last bin includes its upper edge, so the maximum is binned
+        None => (0..n)
+            .map(|i| {
+                let close = if i + 1 == n { "]" } else { ")" };
+                format!("[{}, {}{}", edges[i], edges[i + 1], close)
+            })
+            .collect(),
+    };
+    let default_name = format!("{}_bin", args.column);
+    let name = args.name.as_ref().unwrap_or(&default_name);
+    if name.is_empty() || name.len() > MAX_COLUMN_LENGTH {
+        return Err(Error::InvalidArguments(format!(
+            "column names must be 1 to {} bytes: {}",
+            MAX_COLUMN_LENGTH, name
+        )));
+    }
+    if parent.column(name).is_ok() {
+        return Err(Error::InvalidArguments(format!(
+            "duplicate output column: {}",
+            name
+        )));
+    }
+    let column = quote_identifier(&args.column);
+    let cases: Vec<String> = labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let upper = if i + 1 == n { "<=" } else { "<" };
+            format!(
+                "WHEN {} >= {} AND {} {} {} THEN {}",
+                column,
+                edges[i],
+                column,
+                upper,
+                edges[i + 1],
+                quote_literal(label)
+            )
+        })
+        .collect();
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT *, CASE {} END AS {}\nFROM {}",
+            cases.join(" "),
+            quote_identifier(name),
+            quote_identifier(&parent.name)
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn bin() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Bin,
+            json!({
+                "column": "petal_length",
+                "method": "EDGES",
+                "edges": [0, 1.5, 7],
+            }),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT *, CASE \
+             WHEN \"petal_length\" >= 0 AND \"petal_length\" < 1.5 \
+             THEN '[0, 1.5)' \
+             WHEN \"petal_length\" >= 1.5 AND \"petal_length\" <= 7 \
+             THEN '[1.5, 7]' \
+             END AS \"petal_length_bin\"\n\
+             FROM \"dataview_parent\""
+        );
+        let invalid = [
+            json!({"column": "species", "method": "EDGES", "edges": [0, 1]}),
+            json!({"column": "petal_length", "method": "EDGES", "edges": [1, 1]}),
+            json!({"column": "petal_length", "method": "QUANTILE", "bins": 4}),
+            json!({
+                "column": "petal_length",
+                "method": "EDGES",
+                "edges": [0, 1],
+                "labels": ["a", "b"],
+            }),
+            json!({
+                "column": "petal_length",
+                "method": "EDGES",
+                "edges": [0, 1],
+                "name": "species",
+            }),
+        ];
+        for args in invalid.iter() {
+            let res = compile_json(Operation::Bin, args.clone());
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+        Ok(())
+    }
+}
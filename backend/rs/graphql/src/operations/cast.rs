@@ -0,0 +1,178 @@
+use super::{create_view, non_empty, quote_identifier, Relation};
+use crate::{types::Db, Error};
+use async_graphql::{Enum, InputObject, Result as GQLResult};
+use serde::{Deserialize, Serialize};
+use sqlx::query_scalar;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "CastArgs")]
+pub struct Args {
+    pub casts: Vec<ColumnCast>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnCast {
+    pub column: String,
+    pub to: CastType,
+    // defaults to FAIL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<OnError>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CastType {
+    Integer,
+    Float,
+    Date,
+    Timestamp,
+    Boolean,
+}
+
+impl CastType {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Integer => "BIGINT",
+            Self::Float => "DOUBLE PRECISION",
+            Self::Date => "DATE",
+            Self::Timestamp => "TIMESTAMP",
+            Self::Boolean => "BOOLEAN",
+        }
+    }
+
+    // the function from the try_cast data migration
+    fn try_cast(&self) -> &'static str {
+        match self {
+            Self::Integer => "try_cast_bigint",
+            Self::Float => "try_cast_double_precision",
+            Self::Date => "try_cast_date",
+            Self::Timestamp => "try_cast_timestamp",
+            Self::Boolean => "try_cast_boolean",
+        }
+    }
+}
+
+// values that cannot be cast either become NULL or fail the dataview
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "CastOnError")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OnError {
+    Null,
+    Fail,
+}
+
+// views are only evaluated when read, so the values of columns that fail on
+// errors are checked up front
+pub async fn check(db: &Db, args: &Args, parent: &Relation) -> GQLResult<()> {
+    for c in args.casts.iter() {
+        if c.on_error.unwrap_or(OnError::Fail) != OnError::Fail {
+            continue;
+        }
+        parent.column(&c.column)?;
+        let column = quote_identifier(&c.column);
+        let invalid: i64 = query_scalar(&format!(
+            r#"
+            SELECT COUNT(*)
+            FROM {}
+            WHERE {} IS NOT NULL
+            AND {}({}) IS NULL
+            "#,
+            quote_identifier(&parent.name),
+            column,
+            c.to.try_cast(),
+            column
+        ))
+        .fetch_one(&db.data)
+        .await?;
+        if invalid > 0 {
+            return Err(Error::InvalidArguments(format!(
+                "{} values of {} cannot be cast to {}",
+                invalid,
+                c.column,
+                c.to.as_sql()
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.casts, "casts")?;
+    let mut cast: HashMap<&str, String> = HashMap::new();
+    for c in args.casts.iter() {
+        parent.column(&c.column)?;
+        let column = quote_identifier(&c.column);
+        let sql = match c.on_error.unwrap_or(OnError::Fail) {
+            OnError::Null => {
+                format!("{}({}) AS {}", c.to.try_cast(), column, column)
+            }
+            OnError::Fail => {
+                format!("CAST({} AS {}) AS {}", column, c.to.as_sql(), column)
+            }
+        };
+        if cast.insert(&c.column, sql).is_some() {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate column: {}",
+                c.column
+            )));
+        }
+    }
+    let columns: Vec<String> = parent
+        .columns
+        .iter()
+        .map(|c| match cast.get(c.column_name.as_str()) {
+            Some(sql) => sql.clone(),
+            None => quote_identifier(&c.column_name),
+        })
+        .collect();
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\nFROM {}",
+            columns.join(", "),
+            quote_identifier(&parent.name)
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn cast() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Cast,
+            json!({"casts": [
+                {"column": "species", "to": "BOOLEAN", "onError": "NULL"},
+                {"column": "sepal_width", "to": "INTEGER"},
+            ]}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT \"sepal_length\", \
+             CAST(\"sepal_width\" AS BIGINT) AS \"sepal_width\", \
+             \"petal_length\", \"petal_width\", \
+             try_cast_boolean(\"species\") AS \"species\"\n\
+             FROM \"dataview_parent\""
+        );
+        let res = compile_json(
+            Operation::Cast,
+            json!({"casts": [
+                {"column": "species", "to": "DATE"},
+                {"column": "species", "to": "INTEGER"},
+            ]}),
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+}
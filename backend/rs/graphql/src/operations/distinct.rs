@@ -0,0 +1,123 @@
+use super::{create_view, non_empty, quote_identifier, Relation};
+use crate::Error;
+use async_graphql::{Enum, InputObject};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "DistinctArgs")]
+pub struct Args {
+    // the columns rows are compared on, defaulting to all of them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+    // which of the rows sharing `columns` is kept, defaulting to FIRST
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep: Option<Keep>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "DistinctKeep")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Keep {
+    First,
+    Last,
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    let columns = match &args.columns {
+        Some(columns) => columns,
+        None => {
+            return Ok(create_view(
+                view,
+                &format!(
+                    "SELECT DISTINCT *\nFROM {}",
+                    quote_identifier(&parent.name)
+                ),
+            ))
+        }
+    };
+    non_empty(columns, "columns")?;
+    let mut seen = HashSet::new();
+    for column in columns.iter() {
+        parent.column(column)?;
+        if !seen.insert(column) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate column: {}",
+                column
+            )));
+        }
+    }
+    let partition: Vec<String> =
+        columns.iter().map(|c| quote_identifier(c)).collect();
+    let output: Vec<String> = parent
+        .columns
+        .iter()
+        .map(|c| format!("d.{}", quote_identifier(&c.column_name)))
+        .collect();
+    let order = match args.keep.unwrap_or(Keep::First) {
+        Keep::First => "ASC",
+        Keep::Last => "DESC",
+    };
+    // first and last follow the parent's order, which the outer row numbers
+    // capture and the result keeps
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\n\
+             FROM (\n\
+             SELECT *, ROW_NUMBER() OVER \
+             (PARTITION BY {} ORDER BY \"__row\" {}) AS \"__rank\"\n\
+             FROM (SELECT *, ROW_NUMBER() OVER () AS \"__row\" FROM {}) p\n\
+             ) d\n\
+             WHERE \"__rank\" = 1\n\
+             ORDER BY \"__row\"",
+            output.join(", "),
+            partition.join(", "),
+            order,
+            quote_identifier(&parent.name)
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn distinct() -> Result<(), Error> {
+        let sql = compile_json(Operation::Distinct, json!({}))?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT DISTINCT *\n\
+             FROM \"dataview_parent\""
+        );
+        let sql = compile_json(
+            Operation::Distinct,
+            json!({"columns": ["species"], "keep": "LAST"}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT d.\"sepal_length\", d.\"sepal_width\", \
+             d.\"petal_length\", d.\"petal_width\", d.\"species\"\n\
+             FROM (\n\
+             SELECT *, ROW_NUMBER() OVER \
+             (PARTITION BY \"species\" ORDER BY \"__row\" DESC) \
+             AS \"__rank\"\n\
+             FROM (SELECT *, ROW_NUMBER() OVER () AS \"__row\" \
+             FROM \"dataview_parent\") p\n\
+             ) d\n\
+             WHERE \"__rank\" = 1\n\
+             ORDER BY \"__row\""
+        );
+        let res = compile_json(Operation::Distinct, json!({"columns": []}));
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+}
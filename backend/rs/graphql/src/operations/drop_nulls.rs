@@ -0,0 +1,94 @@
+use super::{create_view, non_empty, quote_identifier, Relation};
+use crate::Error;
+use async_graphql::{Enum, InputObject};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "DropNullsArgs")]
+pub struct Args {
+    // the columns checked for nulls, defaulting to all of them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+    // defaults to ANY
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub how: Option<How>,
+}
+
+// drops rows with a null in any of the columns, or only in all of them
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "DropNullsHow")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum How {
+    Any,
+    All,
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    let columns: Vec<&str> = match &args.columns {
+        Some(columns) => {
+            non_empty(columns, "columns")?;
+            let mut seen = HashSet::new();
+            for column in columns.iter() {
+                parent.column(column)?;
+                if !seen.insert(column) {
+                    return Err(Error::InvalidArguments(format!(
+                        "duplicate column: {}",
+                        column
+                    )));
+                }
+            }
+            columns.iter().map(|c| c.as_str()).collect()
+        }
+        None => parent
+            .columns
+            .iter()
+            .map(|c| c.column_name.as_str())
+            .collect(),
+    };
+    let conditions: Vec<String> = columns
+        .iter()
+        .map(|c| format!("{} IS NOT NULL", quote_identifier(c)))
+        .collect();
+    let op = match args.how.unwrap_or(How::Any) {
+        How::Any => "\nAND ",
+        How::All => "\nOR ",
+    };
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT *\nFROM {}\nWHERE {}",
+            quote_identifier(&parent.name),
+            conditions.join(op)
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn drop_nulls() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::DropNulls,
+            json!({"columns": ["sepal_length", "species"], "how": "ALL"}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT *\n\
+             FROM \"dataview_parent\"\n\
+             WHERE \"sepal_length\" IS NOT NULL\n\
+             OR \"species\" IS NOT NULL"
+        );
+        let res = compile_json(Operation::DropNulls, json!({"columns": []}));
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+}
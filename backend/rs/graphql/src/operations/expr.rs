@@ -0,0 +1,1114 @@
+// a small expression language for derived columns, e.g.
+//
+//   case when sepal_length > 5 then 'long' else 'short' end
+//   round(petal_length / petal_width, 2)
+//   year(measured_at)::text
+//
+// expressions are parsed, type checked against the parent view, and emitted
+// as SQL built only from quoted identifiers, escaped literals, and a fixed set
+// of operators and functions
+use super::{quote_identifier, quote_literal, DataType, Relation};
+use crate::Error;
+
+const MAX_LENGTH: usize = 10_000;
+const MAX_DEPTH: usize = 64;
+// chains of operators nest the tree deeper than the parser recurses, and
+// checking and emitting it recurses over the whole tree
+const MAX_HEIGHT: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(String),
+    Number(String),
+    Text(String),
+    Boolean(bool),
+    Null,
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    IsNull(Box<Expr>, bool),
+    Case(Vec<(Expr, Expr)>, Option<Box<Expr>>),
+    Cast(Box<Expr>, CastType),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CastType {
+    Boolean,
+    Date,
+    Float,
+    Integer,
+    Numeric,
+    Text,
+    Timestamp,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Function {
+    Abs,
+    Ceil,
+    Coalesce,
+    Concat,
+    DateTrunc,
+    Day,
+    Exp,
+    Floor,
+    Greatest,
+    Least,
+    Length,
+    Ln,
+    Lower,
+    Month,
+    Power,
+    Replace,
+    Round,
+    Sqrt,
+    Substr,
+    Trim,
+    Upper,
+    Weekday,
+    Year,
+}
+
+// the type of an expression; None is an untyped NULL
+pub type Type = Option<DataType>;
+
+// parses, type checks, and emits the expression as SQL
+pub fn compile(
+    src: &str,
+    relation: &Relation,
+) -> Result<(String, Type), Error> {
+    let expr = parse(src)?;
+    let t = expr.check(relation)?;
+    Ok((expr.to_sql(relation), t))
+}
+
+pub fn parse(src: &str) -> Result<Expr, Error> {
+    if src.len() > MAX_LENGTH {
+        return Err(invalid(format!(
+            "expressions are limited to {} characters",
+            MAX_LENGTH
+        )));
+    }
+    let mut parser = Parser {
+        tokens: lex(src)?,
+        pos: 0,
+        depth: 0,
+    };
+    let expr = parser.expr(0)?;
+    if let Some(t) = parser.peek() {
+        return Err(invalid(format!("unexpected {}", t)));
+    }
+    match expr.height() > MAX_HEIGHT {
+        true => Err(too_deep()),
+        false => Ok(expr),
+    }
+}
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidArguments(msg)
+}
+
+fn too_deep() -> Error {
+    invalid("expression is nested too deeply".to_owned())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(String),
+    Text(String),
+    Ident(String),
+    QuotedIdent(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+    DoubleColon,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Text(s) => write!(f, "string '{}'", s),
+            Token::Ident(s) => write!(f, "'{}'", s),
+            Token::QuotedIdent(s) => write!(f, "column \"{}\"", s),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+            Token::DoubleColon => write!(f, "'::'"),
+        }
+    }
+}
+
+const OPS: [&str; 13] = [
+    "==", "!=", "<>", "<=", ">=", "+", "-", "*", "/", "%", "=", "<", ">",
+];
+
+fn lex(src: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.')
+            {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+                if i < chars.len() && (chars[i] == '+' || chars[i] == '-') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let n: String = chars[start..i].iter().collect();
+            if n.parse::<f64>().is_err() {
+                return Err(invalid(format!("invalid number: {}", n)));
+            }
+            tokens.push(Token::Number(n));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_')
+            {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '\'' || c == '"' {
+            // quotes are escaped by doubling them, as in SQL
+            let mut s = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(invalid("unterminated quote".to_owned()))
+                    }
+                    Some(&q) if q == c => {
+                        if chars.get(i + 1) == Some(&c) {
+                            s.push(c);
+                            i += 2;
+                        } else {
+                            i += 1;
+                            break;
+                        }
+                    }
+                    Some(&ch) => {
+                        s.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(if c == '\'' {
+                Token::Text(s)
+            } else {
+                Token::QuotedIdent(s)
+            });
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else if c == ':' && chars.get(i + 1) == Some(&':') {
+            tokens.push(Token::DoubleColon);
+            i += 2;
+        } else {
+            let rest: String =
+                chars[i..chars.len().min(i + 2)].iter().collect();
+            match OPS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => {
+                    tokens.push(Token::Op(op));
+                    i += op.len();
+                }
+                None => {
+                    return Err(invalid(format!("unexpected character: {}", c)))
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+// binding powers, from loosest to tightest
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARE: u8 = 4;
+const IS: u8 = 5;
+const ADD: u8 = 6;
+const MUL: u8 = 7;
+const NEG: u8 = 8;
+const CAST: u8 = 9;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, Error> {
+        let t = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+            invalid("unexpected end of expression".to_owned())
+        })?;
+        self.pos += 1;
+        Ok(t)
+    }
+
+    fn keyword(&self, kw: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(s)) => s.eq_ignore_ascii_case(kw),
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, kw: &str) -> Result<(), Error> {
+        if !self.keyword(kw) {
+            return Err(self.expected(kw));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), Error> {
+        if self.peek() != Some(&token) {
+            return Err(self.expected(&token.to_string()));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expected(&self, what: &str) -> Error {
+        match self.peek() {
+            Some(t) => invalid(format!("expected {} but found {}", what, t)),
+            None => invalid(format!("expected {} at end of expression", what)),
+        }
+    }
+
+    fn expr(&mut self, min_bp: u8) -> Result<Expr, Error> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(too_deep());
+        }
+        let mut lhs = self.prefix()?;
+        let mut chained = 0;
+        loop {
+            let (op, bp) = match self.peek() {
+                Some(Token::DoubleColon) => (None, CAST),
+                Some(Token::Op(op)) => {
+                    let op = match *op {
+                        "+" => BinaryOp::Add,
+                        "-" => BinaryOp::Sub,
+                        "*" => BinaryOp::Mul,
+                        "/" => BinaryOp::Div,
+                        "%" => BinaryOp::Mod,
+                        "=" | "==" => BinaryOp::Eq,
+                        "!=" | "<>" => BinaryOp::Ne,
+                        "<" => BinaryOp::Lt,
+                        "<=" => BinaryOp::Le,
+                        ">" => BinaryOp::Gt,
+                        _ => BinaryOp::Ge,
+                    };
+                    (Some(op), op.binding_power())
+                }
+                Some(Token::Ident(s)) if s.eq_ignore_ascii_case("and") => {
+                    (Some(BinaryOp::And), AND)
+                }
+                Some(Token::Ident(s)) if s.eq_ignore_ascii_case("or") => {
+                    (Some(BinaryOp::Or), OR)
+                }
+                Some(Token::Ident(s)) if s.eq_ignore_ascii_case("is") => {
+                    (None, IS)
+                }
+                _ => break,
+            };
+            if bp <= min_bp {
+                break;
+            }
+            chained += 1;
+            if chained > MAX_HEIGHT {
+                return Err(too_deep());
+            }
+            let token = self.next()?;
+            lhs = match op {
+                Some(op) => {
+                    let rhs = self.expr(bp)?;
+                    Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+                }
+                None if token == Token::DoubleColon => {
+                    Expr::Cast(Box::new(lhs), self.cast_type()?)
+                }
+                None => {
+                    let negated = self.keyword("not");
+                    if negated {
+                        self.pos += 1;
+                    }
+                    self.expect_keyword("null")?;
+                    Expr::IsNull(Box::new(lhs), negated)
+                }
+            };
+        }
+        self.depth -= 1;
+        Ok(lhs)
+    }
+
+    fn prefix(&mut self) -> Result<Expr, Error> {
+        match self.next()? {
+            Token::Number(n) => Ok(Expr::Number(n)),
+            Token::Text(s) => Ok(Expr::Text(s)),
+            Token::QuotedIdent(s) => Ok(Expr::Column(s)),
+            Token::Op("-") => {
+                Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.expr(NEG)?)))
+            }
+            Token::LParen => {
+                let e = self.expr(0)?;
+                self.expect(Token::RParen)?;
+                Ok(e)
+            }
+            Token::Ident(s) => match s.to_lowercase().as_str() {
+                "true" => Ok(Expr::Boolean(true)),
+                "false" => Ok(Expr::Boolean(false)),
+                "null" => Ok(Expr::Null),
+                "not" => {
+                    Ok(Expr::Unary(UnaryOp::Not, Box::new(self.expr(NOT)?)))
+                }
+                "case" => self.case(),
+                _ if self.peek() == Some(&Token::LParen) => self.call(&s),
+                "and" | "or" | "is" | "when" | "then" | "else" | "end" => {
+                    Err(invalid(format!("unexpected '{}'", s)))
+                }
+                _ => Ok(Expr::Column(s)),
+            },
+            t => Err(invalid(format!("unexpected {}", t))),
+        }
+    }
+
+    fn case(&mut self) -> Result<Expr, Error> {
+        let mut branches = Vec::new();
+        while self.keyword("when") {
+            self.pos += 1;
+            let condition = self.expr(0)?;
+            self.expect_keyword("then")?;
+            branches.push((condition, self.expr(0)?));
+        }
+        if branches.is_empty() {
+            return Err(self.expected("when"));
+        }
+        let default = if self.keyword("else") {
+            self.pos += 1;
+            Some(Box::new(self.expr(0)?))
+        } else {
+            None
+        };
+        self.expect_keyword("end")?;
+        Ok(Expr::Case(branches, default))
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, Error> {
+        let function = Function::from_name(name)?;
+        self.expect(Token::LParen)?;
+        let mut args = Vec::new();
+        if self.peek() != Some(&Token::RParen) {
+            loop {
+                args.push(self.expr(0)?);
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        self.expect(Token::RParen)?;
+        Ok(Expr::Call(function, args))
+    }
+
+    fn cast_type(&mut self) -> Result<CastType, Error> {
+        match self.next()? {
+            Token::Ident(s) => CastType::from_name(&s),
+            t => Err(invalid(format!("expected a type but found {}", t))),
+        }
+    }
+}
+
+impl BinaryOp {
+    fn binding_power(&self) -> u8 {
+        match self {
+            Self::Or => OR,
+            Self::And => AND,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => {
+                COMPARE
+            }
+            Self::Add | Self::Sub => ADD,
+            Self::Mul | Self::Div | Self::Mod => MUL,
+        }
+    }
+
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Eq => "=",
+            Self::Ne => "<>",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "AND",
+            Self::Or => "OR",
+        }
+    }
+}
+
+impl CastType {
+    fn from_name(name: &str) -> Result<Self, Error> {
+        match name.to_lowercase().as_str() {
+            "boolean" | "bool" => Ok(Self::Boolean),
+            "date" => Ok(Self::Date),
+            "float" | "double" => Ok(Self::Float),
+            "integer" | "int" => Ok(Self::Integer),
+            "numeric" | "number" => Ok(Self::Numeric),
+            "text" | "string" => Ok(Self::Text),
+            "timestamp" | "datetime" => Ok(Self::Timestamp),
+            _ => Err(invalid(format!("unknown type: {}", name))),
+        }
+    }
+
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Boolean => "BOOLEAN",
+            Self::Date => "DATE",
+            Self::Float => "DOUBLE PRECISION",
+            Self::Integer => "BIGINT",
+            Self::Numeric => "NUMERIC",
+            Self::Text => "TEXT",
+            Self::Timestamp => "TIMESTAMP",
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::Boolean => DataType::Boolean,
+            Self::Date => DataType::Date,
+            Self::Float | Self::Integer | Self::Numeric => DataType::Numeric,
+            Self::Text => DataType::Text,
+            Self::Timestamp => DataType::Timestamp,
+        }
+    }
+}
+
+const DATE_TRUNC_UNITS: [&str; 7] =
+    ["year", "quarter", "month", "week", "day", "hour", "minute"];
+
+impl Function {
+    fn from_name(name: &str) -> Result<Self, Error> {
+        match name.to_lowercase().as_str() {
+            "abs" => Ok(Self::Abs),
+            "ceil" => Ok(Self::Ceil),
+            "coalesce" => Ok(Self::Coalesce),
+            "concat" => Ok(Self::Concat),
+            "date_trunc" => Ok(Self::DateTrunc),
+            "day" => Ok(Self::Day),
+            "exp" => Ok(Self::Exp),
+            "floor" => Ok(Self::Floor),
+            "greatest" => Ok(Self::Greatest),
+            "least" => Ok(Self::Least),
+            "length" => Ok(Self::Length),
+            "ln" => Ok(Self::Ln),
+            "lower" => Ok(Self::Lower),
+            "month" => Ok(Self::Month),
+            "power" => Ok(Self::Power),
+            "replace" => Ok(Self::Replace),
+            "round" => Ok(Self::Round),
+            "sqrt" => Ok(Self::Sqrt),
+            "substr" => Ok(Self::Substr),
+            "trim" => Ok(Self::Trim),
+            "upper" => Ok(Self::Upper),
+            "weekday" => Ok(Self::Weekday),
+            "year" => Ok(Self::Year),
+            _ => Err(invalid(format!("unknown function: {}", name))),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Abs => "abs",
+            Self::Ceil => "ceil",
+            Self::Coalesce => "coalesce",
+            Self::Concat => "concat",
+            Self::DateTrunc => "date_trunc",
+            Self::Day => "day",
+            Self::Exp => "exp",
+            Self::Floor => "floor",
+            Self::Greatest => "greatest",
+            Self::Least => "least",
+            Self::Length => "length",
+            Self::Ln => "ln",
+            Self::Lower => "lower",
+            Self::Month => "month",
+            Self::Power => "power",
+            Self::Replace => "replace",
+            Self::Round => "round",
+            Self::Sqrt => "sqrt",
+            Self::Substr => "substr",
+            Self::Trim => "trim",
+            Self::Upper => "upper",
+            Self::Weekday => "weekday",
+            Self::Year => "year",
+        }
+    }
+
+    // checks the arguments and returns the result type
+    fn check(&self, args: &[Expr], types: &[Type]) -> Result<Type, Error> {
+        let arity = |min: usize, max: usize| {
+            if args.len() < min || args.len() > max {
+                let expected = if min == max {
+                    min.to_string()
+                } else if max == usize::MAX {
+                    format!("at least {}", min)
+                } else {
+                    format!("{} to {}", min, max)
+                };
+                return Err(invalid(format!(
+                    "{} takes {} argument(s) but received {}",
+                    self.name(),
+                    expected,
+                    args.len()
+                )));
+            }
+            Ok(())
+        };
+        let expect = |i: usize, allowed: &[DataType]| {
+            expect_type(
+                &format!("{} argument {}", self.name(), i + 1),
+                types[i],
+                allowed,
+            )
+        };
+        use DataType::*;
+        match self {
+            Self::Abs
+            | Self::Ceil
+            | Self::Exp
+            | Self::Floor
+            | Self::Ln
+            | Self::Sqrt => {
+                arity(1, 1)?;
+                expect(0, &[Numeric])?;
+                Ok(Some(Numeric))
+            }
+            Self::Power => {
+                arity(2, 2)?;
+                expect(0, &[Numeric])?;
+                expect(1, &[Numeric])?;
+                Ok(Some(Numeric))
+            }
+            Self::Round => {
+                arity(1, 2)?;
+                for i in 0..args.len() {
+                    expect(i, &[Numeric])?;
+                }
+                Ok(Some(Numeric))
+            }
+            Self::Coalesce | Self::Greatest | Self::Least => {
+                arity(1, usize::MAX)?;
+                types.iter().try_fold(None, |acc, t| unify(acc, *t))
+            }
+            Self::Concat => {
+                arity(1, usize::MAX)?;
+                Ok(Some(Text))
+            }
+            Self::Length => {
+                arity(1, 1)?;
+                expect(0, &[Text])?;
+                Ok(Some(Numeric))
+            }
+            Self::Lower | Self::Trim | Self::Upper => {
+                arity(1, 1)?;
+                expect(0, &[Text])?;
+                Ok(Some(Text))
+            }
+            Self::Replace => {
+                arity(3, 3)?;
+                for i in 0..args.len() {
+                    expect(i, &[Text])?;
+                }
+                Ok(Some(Text))
+            }
+            Self::Substr => {
+                arity(2, 3)?;
+                expect(0, &[Text])?;
+                for i in 1..args.len() {
+                    expect(i, &[Numeric])?;
+                }
+                Ok(Some(Text))
+            }
+            Self::Day | Self::Month | Self::Weekday | Self::Year => {
+                arity(1, 1)?;
+                expect(0, &[Date, Timestamp])?;
+                Ok(Some(Numeric))
+            }
+            Self::DateTrunc => {
+                arity(2, 2)?;
+                match &args[0] {
+                    Expr::Text(unit)
+                        if DATE_TRUNC_UNITS
+                            .contains(&unit.to_lowercase().as_str()) => {}
+                    _ => {
+                        return Err(invalid(format!(
+                            "date_trunc unit must be one of: {}",
+                            DATE_TRUNC_UNITS.join(", ")
+                        )))
+                    }
+                }
+                expect(1, &[Date, Timestamp])?;
+                Ok(Some(Timestamp))
+            }
+        }
+    }
+
+    fn call_sql(&self, args: &[String]) -> String {
+        let call = |name: &str| format!("{}({})", name, args.join(", "));
+        match self {
+            Self::Abs => call("ABS"),
+            Self::Ceil => call("CEIL"),
+            Self::Coalesce => call("COALESCE"),
+            Self::Concat => call("CONCAT"),
+            Self::DateTrunc => call("DATE_TRUNC"),
+            Self::Day => format!("EXTRACT(DAY FROM {})", args[0]),
+            Self::Exp => call("EXP"),
+            Self::Floor => call("FLOOR"),
+            Self::Greatest => call("GREATEST"),
+            Self::Least => call("LEAST"),
+            Self::Length => call("LENGTH"),
+            Self::Ln => call("LN"),
+            Self::Lower => call("LOWER"),
+            Self::Month => format!("EXTRACT(MONTH FROM {})", args[0]),
+            Self::Power => call("POWER"),
+            Self::Replace => call("REPLACE"),
+            // ROUND with digits and SUBSTR only accept integers, and
+            // ROUND(DOUBLE PRECISION, INTEGER) does not exist
+            Self::Round if args.len() == 2 => format!(
+                "ROUND(CAST({} AS NUMERIC), CAST({} AS INTEGER))",
+                args[0], args[1]
+            ),
+            Self::Round => call("ROUND"),
+            Self::Sqrt => call("SQRT"),
+            Self::Substr => {
+                let mut sql_args = vec![args[0].clone()];
+                sql_args.extend(
+                    args[1..].iter().map(|a| format!("CAST({} AS INTEGER)", a)),
+                );
+                format!("SUBSTR({})", sql_args.join(", "))
+            }
+            Self::Trim => call("BTRIM"),
+            Self::Upper => call("UPPER"),
+            Self::Weekday => format!("EXTRACT(DOW FROM {})", args[0]),
+            Self::Year => format!("EXTRACT(YEAR FROM {})", args[0]),
+        }
+    }
+}
+
+fn type_name(t: Type) -> String {
+    match t {
+        Some(t) => format!("{:?}", t).to_lowercase(),
+        None => "null".to_owned(),
+    }
+}
+
+fn expect_type(what: &str, t: Type, allowed: &[DataType]) -> Result<(), Error> {
+    match t {
+        Some(t) if !allowed.contains(&t) => {
+            let allowed: Vec<String> =
+                allowed.iter().map(|a| type_name(Some(*a))).collect();
+            Err(invalid(format!(
+                "{} must be {} but is {}",
+                what,
+                allowed.join(" or "),
+                type_name(Some(t))
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+// the common type of two expressions, where NULL is compatible with any type
+pub fn unify(a: Type, b: Type) -> Result<Type, Error> {
+    match (a, b) {
+        (None, t) | (t, None) => Ok(t),
+        (Some(a), Some(b)) if a == b => Ok(Some(a)),
+        _ => Err(invalid(format!(
+            "mismatched types: {} and {}",
+            type_name(a),
+            type_name(b)
+        ))),
+    }
+}
+
+impl Expr {
+    pub fn check(&self, relation: &Relation) -> Result<Type, Error> {
+        use DataType::*;
+        match self {
+            Expr::Column(c) => relation.data_type(c).map(Some),
+            Expr::Number(_) => Ok(Some(Numeric)),
+            Expr::Text(_) => Ok(Some(Text)),
+            Expr::Boolean(_) => Ok(Some(Boolean)),
+            Expr::Null => Ok(None),
+            Expr::Unary(UnaryOp::Neg, e) => {
+                expect_type("negated value", e.check(relation)?, &[Numeric])?;
+                Ok(Some(Numeric))
+            }
+            Expr::Unary(UnaryOp::Not, e) => {
+                expect_type("not", e.check(relation)?, &[Boolean])?;
+                Ok(Some(Boolean))
+            }
+            Expr::Binary(op, l, r) => {
+                let (lt, rt) = (l.check(relation)?, r.check(relation)?);
+                let what = format!("operand of {}", op.as_sql());
+                match op {
+                    BinaryOp::And | BinaryOp::Or => {
+                        expect_type(&what, lt, &[Boolean])?;
+                        expect_type(&what, rt, &[Boolean])?;
+                        Ok(Some(Boolean))
+                    }
+                    BinaryOp::Eq
+                    | BinaryOp::Ne
+                    | BinaryOp::Lt
+                    | BinaryOp::Le
+                    | BinaryOp::Gt
+                    | BinaryOp::Ge => {
+                        unify(lt, rt)?;
+                        Ok(Some(Boolean))
+                    }
+                    // whole days can be added to or subtracted from dates,
+                    // and subtracting dates gives the days between them
+                    BinaryOp::Add | BinaryOp::Sub
+                        if lt == Some(Date) && rt != Some(Date) =>
+                    {
+                        expect_type(&what, rt, &[Numeric])?;
+                        if !r.is_integer(relation) {
+                            return Err(invalid(format!(
+                                "{} must be a whole number of days",
+                                what
+                            )));
+                        }
+                        Ok(Some(Date))
+                    }
+                    BinaryOp::Sub if lt == Some(Date) => Ok(Some(Numeric)),
+                    _ => {
+                        expect_type(&what, lt, &[Numeric])?;
+                        expect_type(&what, rt, &[Numeric])?;
+                        Ok(Some(Numeric))
+                    }
+                }
+            }
+            Expr::IsNull(e, _) => {
+                e.check(relation)?;
+                Ok(Some(Boolean))
+            }
+            Expr::Case(branches, default) => {
+                let mut t = match default {
+                    Some(e) => e.check(relation)?,
+                    None => None,
+                };
+                for (condition, result) in branches.iter() {
+                    expect_type(
+                        "case condition",
+                        condition.check(relation)?,
+                        &[Boolean],
+                    )?;
+                    t = unify(t, result.check(relation)?)?;
+                }
+                Ok(t)
+            }
+            Expr::Cast(e, t) => {
+                e.check(relation)?;
+                Ok(Some(t.data_type()))
+            }
+            Expr::Call(f, args) => {
+                let types = args
+                    .iter()
+                    .map(|a| a.check(relation))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                f.check(args, &types)
+            }
+        }
+    }
+
+    // whether the expression's values are whole numbers, which postgres can
+    // add to dates
+    fn is_integer(&self, relation: &Relation) -> bool {
+        match self {
+            Expr::Number(n) => n.chars().all(|c| c.is_ascii_digit()),
+            Expr::Null => true,
+            Expr::Column(c) => relation.column(c).map_or(false, |c| {
+                matches!(
+                    c.data_type.as_str(),
+                    "smallint" | "integer" | "bigint"
+                )
+            }),
+            Expr::Cast(_, CastType::Integer) => true,
+            Expr::Unary(UnaryOp::Neg, e) => e.is_integer(relation),
+            Expr::Binary(BinaryOp::Add, l, r)
+            | Expr::Binary(BinaryOp::Sub, l, r)
+            | Expr::Binary(BinaryOp::Mul, l, r) => {
+                l.is_integer(relation) && r.is_integer(relation)
+            }
+            _ => false,
+        }
+    }
+
+    // the height of the tree, found without recursing so deep trees are
+    // rejected before anything recurses over them
+    fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack = vec![(self, 1)];
+        while let Some((e, h)) = stack.pop() {
+            height = height.max(h);
+            let children: Vec<&Expr> = match e {
+                Expr::Unary(_, e) | Expr::IsNull(e, _) | Expr::Cast(e, _) => {
+                    vec![e.as_ref()]
+                }
+                Expr::Binary(_, l, r) => vec![l.as_ref(), r.as_ref()],
+                Expr::Case(branches, default) => branches
+                    .iter()
+                    .flat_map(|(c, r)| vec![c, r])
+                    .chain(default.iter().map(|e| e.as_ref()))
+                    .collect(),
+                Expr::Call(_, args) => args.iter().collect(),
+                _ => vec![],
+            };
+            stack.extend(children.into_iter().map(|c| (c, h + 1)));
+        }
+        height
+    }
+
+    // compound expressions are parenthesized so the emitted SQL never relies
+    // on postgres' operator precedence
+    pub fn to_sql(&self, relation: &Relation) -> String {
+        match self {
+            Expr::Column(c) => quote_identifier(c),
+            Expr::Number(n) => n.clone(),
+            Expr::Text(s) => quote_literal(s),
+            Expr::Boolean(true) => "TRUE".to_owned(),
+            Expr::Boolean(false) => "FALSE".to_owned(),
+            Expr::Null => "NULL".to_owned(),
+            Expr::Unary(UnaryOp::Neg, e) => {
+                format!("(-{})", e.to_sql(relation))
+            }
+            Expr::Unary(UnaryOp::Not, e) => {
+                format!("(NOT {})", e.to_sql(relation))
+            }
+            // division by zero is NULL rather than an error, and integer
+            // columns are not truncated
+            Expr::Binary(BinaryOp::Div, l, r) => format!(
+                "(CAST({} AS DOUBLE PRECISION) / NULLIF({}, 0))",
+                l.to_sql(relation),
+                r.to_sql(relation)
+            ),
+            Expr::Binary(BinaryOp::Mod, l, r) => format!(
+                "MOD(CAST({} AS NUMERIC), NULLIF(CAST({} AS NUMERIC), 0))",
+                l.to_sql(relation),
+                r.to_sql(relation)
+            ),
+            // postgres only adds integers, not bigints, to dates
+            Expr::Binary(op, l, r)
+                if matches!(op, BinaryOp::Add | BinaryOp::Sub)
+                    && matches!(
+                        l.check(relation),
+                        Ok(Some(DataType::Date))
+                    )
+                    && !matches!(
+                        r.check(relation),
+                        Ok(Some(DataType::Date))
+                    ) =>
+            {
+                format!(
+                    "({} {} CAST({} AS INTEGER))",
+                    l.to_sql(relation),
+                    op.as_sql(),
+                    r.to_sql(relation)
+                )
+            }
+            Expr::Binary(op, l, r) => format!(
+                "({} {} {})",
+                l.to_sql(relation),
+                op.as_sql(),
+                r.to_sql(relation)
+            ),
+            Expr::IsNull(e, false) => {
+                format!("({} IS NULL)", e.to_sql(relation))
+            }
+            Expr::IsNull(e, true) => {
+                format!("({} IS NOT NULL)", e.to_sql(relation))
+            }
+            Expr::Case(branches, default) => {
+                let mut sql = "CASE".to_owned();
+                for (condition, result) in branches.iter() {
+                    sql.push_str(&format!(
+                        " WHEN {} THEN {}",
+                        condition.to_sql(relation),
+                        result.to_sql(relation)
+                    ));
+                }
+                if let Some(e) = default {
+                    sql.push_str(&format!(" ELSE {}", e.to_sql(relation)));
+                }
+                sql.push_str(" END");
+                sql
+            }
+            Expr::Cast(e, t) => {
+                format!("CAST({} AS {})", e.to_sql(relation), t.as_sql())
+            }
+            Expr::Call(f, args) => {
+                let args: Vec<String> =
+                    args.iter().map(|a| a.to_sql(relation)).collect();
+                f.call_sql(&args)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::fixtures;
+
+    fn relation() -> Relation {
+        fixtures::relation(
+            "RGF0YXZpZXc6cGFyZW50",
+            "dataview_parent",
+            &[
+                ("x", "double precision"),
+                ("n", "integer"),
+                ("name", "text"),
+                ("flag", "boolean"),
+                ("day", "date"),
+            ],
+        )
+    }
+
+    fn sql(src: &str) -> Result<String, Error> {
+        compile(src, &relation()).map(|(sql, _)| sql)
+    }
+
+    #[test]
+    fn precedence() -> Result<(), Error> {
+        assert_eq!(sql("x + n * 2 - 1")?, r#"(("x" + ("n" * 2)) - 1)"#);
+        assert_eq!(
+            sql("not x > 1 and flag or n = 2")?,
+            r#"(((NOT ("x" > 1)) AND "flag") OR ("n" = 2))"#
+        );
+        assert_eq!(sql("-(x + 1)::int")?, r#"(-CAST(("x" + 1) AS BIGINT))"#);
+        Ok(())
+    }
+
+    #[test]
+    fn division() -> Result<(), Error> {
+        assert_eq!(
+            sql("n / 2")?,
+            r#"(CAST("n" AS DOUBLE PRECISION) / NULLIF(2, 0))"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn case_and_functions() -> Result<(), Error> {
+        assert_eq!(
+            sql("case when x is not null then upper(name) else 'it''s' end")?,
+            r#"CASE WHEN ("x" IS NOT NULL) THEN UPPER("name") ELSE 'it''s' END"#
+        );
+        assert_eq!(
+            sql("round(x, 2)")?,
+            r#"ROUND(CAST("x" AS NUMERIC), CAST(2 AS INTEGER))"#
+        );
+        assert_eq!(sql("year(day)")?, r#"EXTRACT(YEAR FROM "day")"#);
+        assert!(sql(r#""name" || 1"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn types() -> Result<(), Error> {
+        let rel = relation();
+        assert_eq!(compile("day + 1", &rel)?.1, Some(DataType::Date));
+        assert_eq!(
+            compile("day - n * 7", &rel)?.0,
+            r#"("day" - CAST(("n" * 7) AS INTEGER))"#
+        );
+        assert!(compile("day + 1.5", &rel).is_err());
+        assert!(compile("day + x", &rel).is_err());
+        assert_eq!(compile("day - day", &rel)?.1, Some(DataType::Numeric));
+        assert_eq!(
+            compile("coalesce(null, name)", &rel)?.1,
+            Some(DataType::Text)
+        );
+        assert!(compile("name + 1", &rel).is_err());
+        assert!(compile("case when x then 1 end", &rel).is_err());
+        assert!(compile("case when flag then 1 else 'a' end", &rel).is_err());
+        assert!(compile("lower(x)", &rel).is_err());
+        assert!(compile("substr(name)", &rel).is_err());
+        assert!(compile("date_trunc('century', day)", &rel).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_injection() {
+        assert!(parse("x; DROP TABLE users").is_err());
+        assert!(parse("pg_sleep(10)").is_err());
+        assert!(sql("x -- comment").is_err());
+        assert!(sql("unknown_column + 1").is_err());
+        assert!(parse("'unterminated").is_err());
+        assert!(parse(&"(".repeat(100)).is_err());
+    }
+
+    #[test]
+    fn rejects_deep_trees() {
+        let chain = |n: usize| vec!["x"; n].join(" + ");
+        assert!(sql(&chain(100)).is_ok());
+        assert!(parse(&chain(MAX_HEIGHT + 2)).is_err());
+        assert!(parse(&chain(5000)).is_err());
+        // chains in nested parentheses
+        let nested = (0..40)
+            .fold("x".to_owned(), |e, _| format!("({} + {})", e, chain(10)));
+        assert!(parse(&nested).is_err());
+    }
+}
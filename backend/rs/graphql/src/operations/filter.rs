@@ -0,0 +1,358 @@
+use super::{
+    create_view, deserialize_optional_scalar, deserialize_scalar,
+    is_valid_literal, non_empty, quote_identifier, quote_literal, DataType,
+    Relation,
+};
+use crate::{utils::one_of, Error, Json};
+use async_graphql::{Enum, InputObject};
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+// deeper expressions are rejected rather than risking the stack
+const MAX_DEPTH: usize = 32;
+
+// set either `filters`, which must all match, or `expression`
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "FilterArgs")]
+pub struct Args {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<Vec<Filter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expression: Option<Expression>,
+}
+
+// set exactly one of `and`, `or` and `comparison`
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "FilterExpression")]
+pub struct Expression {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub and: Option<Vec<Expression>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub or: Option<Vec<Expression>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<Filter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub negate: Option<bool>,
+}
+
+// IN takes `values`, BETWEEN takes the two bounds in `values`, IS_NULL takes
+// neither, and every other comparator takes `value`
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct Filter {
+    pub column: String,
+    pub comparator: Comparator,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_scalar",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub value: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_scalars",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub values: Option<Vec<String>>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum Comparator {
+    #[serde(rename = "=")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = "IN")]
+    In,
+    #[serde(rename = "BETWEEN")]
+    Between,
+    #[serde(rename = "LIKE")]
+    Like,
+    #[serde(rename = "ILIKE")]
+    Ilike,
+    #[serde(rename = "IS NULL")]
+    IsNull,
+}
+
+impl Comparator {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::In => "IN",
+            Self::Between => "BETWEEN",
+            Self::Like => "LIKE",
+            Self::Ilike => "ILIKE",
+            Self::IsNull => "IS NULL",
+        }
+    }
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    let condition = match (&args.filters, &args.expression) {
+        (Some(filters), None) => {
+            non_empty(filters, "filters")?;
+            filters
+                .iter()
+                .map(|f| f.to_sql(parent))
+                .collect::<Result<Vec<_>, Error>>()?
+                .join("\nAND ")
+        }
+        (None, Some(expression)) => expression.to_sql(parent, 0)?,
+        _ => {
+            return Err(Error::InvalidArguments(
+                "exactly one of filters and expression must be set".to_owned(),
+            ))
+        }
+    };
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT *\nFROM {}\nWHERE {}",
+            quote_identifier(&parent.name),
+            condition
+        ),
+    ))
+}
+
+impl Expression {
+    fn to_sql(&self, parent: &Relation, depth: usize) -> Result<String, Error> {
+        if depth >= MAX_DEPTH {
+            return Err(Error::InvalidArguments(format!(
+                "filter expressions must be at most {} deep",
+                MAX_DEPTH
+            )));
+        }
+        let group = |expressions: &Vec<Expression>, op: &str| {
+            non_empty(expressions, &op.to_lowercase())?;
+            let sql = expressions
+                .iter()
+                .map(|e| e.to_sql(parent, depth + 1))
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(format!("({})", sql.join(&format!(" {} ", op))))
+        };
+        let sql = one_of(
+            "FilterExpression",
+            vec![
+                self.and.as_ref().map(|e| group(e, "AND")),
+                self.or.as_ref().map(|e| group(e, "OR")),
+                self.comparison.as_ref().map(|f| f.to_sql(parent)),
+            ],
+        )??;
+        match self.negate {
+            Some(true) => Ok(format!("NOT {}", parenthesize(sql))),
+            _ => Ok(sql),
+        }
+    }
+}
+
+impl Filter {
+    fn to_sql(&self, parent: &Relation) -> Result<String, Error> {
+        let column = parent.column(&self.column)?;
+        let data_type = DataType::of(&column.data_type);
+        let invalid = |msg: &str| {
+            Err(Error::InvalidArguments(format!(
+                "{} {}: {}",
+                self.column,
+                self.comparator.as_sql(),
+                msg
+            )))
+        };
+        let literals = match (self.comparator, &self.value, &self.values) {
+            (Comparator::IsNull, None, None) => vec![],
+            (Comparator::IsNull, _, _) => return invalid("takes no value"),
+            (Comparator::In, None, Some(values)) if !values.is_empty() => {
+                values.iter().collect()
+            }
+            (Comparator::In, _, _) => return invalid("requires values"),
+            (Comparator::Between, None, Some(values)) if values.len() == 2 => {
+                values.iter().collect()
+            }
+            (Comparator::Between, _, _) => {
+                return invalid("requires two values")
+            }
+            (_, Some(value), None) => vec![value],
+            _ => return invalid("requires a value"),
+        };
+        let pattern =
+            matches!(self.comparator, Comparator::Like | Comparator::Ilike);
+        if pattern && data_type != DataType::Text {
+            return invalid("requires a text column");
+        }
+        for v in literals.iter() {
+            if !pattern && !is_valid_literal(column, v) {
+                return invalid(&format!(
+                    "invalid {} value: {}",
+                    column.data_type, v
+                ));
+            }
+        }
+        let column = quote_identifier(&self.column);
+        let literals: Vec<String> =
+            literals.iter().map(|v| quote_literal(v)).collect();
+        Ok(match self.comparator {
+            Comparator::IsNull => format!("{} IS NULL", column),
+            Comparator::In => {
+                format!("{} IN ({})", column, literals.join(", "))
+            }
+            Comparator::Between => format!(
+                "{} BETWEEN {} AND {}",
+                column, literals[0], literals[1]
+            ),
+            c => format!("{} {} {}", column, c.as_sql(), literals[0]),
+        })
+    }
+}
+
+fn parenthesize(sql: String) -> String {
+    if sql.starts_with('(') && sql.ends_with(')') {
+        sql
+    } else {
+        format!("({})", sql)
+    }
+}
+
+fn deserialize_scalars<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Vec<Json>>::deserialize(deserializer)?
+        .map(|values| {
+            values
+                .into_iter()
+                .map(|v| deserialize_scalar(v).map_err(de::Error::custom))
+                .collect()
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn filter() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Filter,
+            json!({"filters": [
+                {"column": "sepal_length", "comparator": ">", "value": 1},
+                {"column": "species", "comparator": "=", "value": "o'neil"},
+            ]}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT *\n\
+             FROM \"dataview_parent\"\n\
+             WHERE \"sepal_length\" > '1'\n\
+             AND \"species\" = 'o''neil'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filter_invalid() {
+        let res = compile_json(
+            Operation::Filter,
+            json!({"filters": [
+                {"column": "sepal_length", "comparator": "~", "value": "1"},
+            ]}),
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        let res = compile_json(Operation::Filter, json!({"filters": []}));
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn filter_expression() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Filter,
+            json!({"expression": {"or": [
+                {"comparison": {
+                    "column": "species",
+                    "comparator": "IN",
+                    "values": ["setosa", "virginica"],
+                }},
+                {"and": [
+                    {"comparison": {
+                        "column": "sepal_length",
+                        "comparator": "BETWEEN",
+                        "values": [1, "2.5"],
+                    }},
+                    {
+                        "comparison": {
+                            "column": "species",
+                            "comparator": "ILIKE",
+                            "value": "v%",
+                        },
+                        "negate": true,
+                    },
+                ]},
+                {"comparison": {
+                    "column": "petal_width",
+                    "comparator": "IS NULL",
+                }},
+            ]}}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT *\n\
+             FROM \"dataview_parent\"\n\
+             WHERE (\"species\" IN ('setosa', 'virginica') \
+             OR (\"sepal_length\" BETWEEN '1' AND '2.5' \
+             AND NOT (\"species\" ILIKE 'v%')) \
+             OR \"petal_width\" IS NULL)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filter_expression_invalid() {
+        let comparisons = [
+            json!({"column": "sepal_length", "comparator": "=", "value": "x"}),
+            json!({"column": "sepal_length", "comparator": "LIKE", "value": "1"}),
+            json!({"column": "species", "comparator": "IN", "values": []}),
+            json!({"column": "species", "comparator": "BETWEEN", "value": "a"}),
+            json!({"column": "species", "comparator": "IS NULL", "value": "a"}),
+            json!({"column": "species", "comparator": "="}),
+        ];
+        for c in comparisons.iter() {
+            let res = compile_json(
+                Operation::Filter,
+                json!({"expression": {"comparison": c}}),
+            );
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+        let comparison = json!({"column": "species", "comparator": "IS NULL"});
+        let invalid = [
+            json!({"expression": {"and": []}}),
+            json!({"expression": {"and": [], "comparison": comparison}}),
+            json!({
+                "filters": [comparison],
+                "expression": {"comparison": comparison},
+            }),
+        ];
+        for args in invalid.iter() {
+            let res = compile_json(Operation::Filter, args.clone());
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use super::{compile, Relation};
+use crate::{models::Operation, types::ColumnDataType, Error, Json};
+use async_graphql::ID;
+
+pub fn relation(id: &str, name: &str, columns: &[(&str, &str)]) -> Relation {
+    Relation {
+        id: ID::from(id),
+        name: name.to_owned(),
+        columns: columns
+            .iter()
+            .map(|(name, data_type)| ColumnDataType {
+                column_name: name.to_string(),
+                data_type: data_type.to_string(),
+            })
+            .collect(),
+    }
+}
+
+// the parent view most operations are compiled against
+pub fn iris() -> Relation {
+    relation(
+        "RGF0YXZpZXc6cGFyZW50",
+        "dataview_parent",
+        &[
+            ("sepal_length", "double precision"),
+            ("sepal_width", "double precision"),
+            ("petal_length", "double precision"),
+            ("petal_width", "double precision"),
+            ("species", "text"),
+        ],
+    )
+}
+
+// a dataset that iris is joined or unioned with
+pub fn other() -> Relation {
+    relation(
+        "RGF0YXNldDoxMjM=",
+        "dataset_other",
+        &[
+            ("species", "text"),
+            ("sepal_length", "double precision"),
+            ("habitat", "text"),
+        ],
+    )
+}
+
+pub fn compile_json(operation: Operation, args: Json) -> Result<String, Error> {
+    compile(&operation, &args, &iris(), &[], "dataview_child")
+}
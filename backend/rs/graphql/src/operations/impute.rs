@@ -0,0 +1,213 @@
+use super::{
+    create_view, deserialize_optional_scalar, is_valid_literal, non_empty,
+    quote_identifier, quote_literal, summarize::Summarizer, DataType, Relation,
+};
+use crate::Error;
+use async_graphql::{Enum, InputObject};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "ImputeArgs")]
+pub struct Args {
+    pub imputations: Vec<Imputation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct Imputation {
+    pub column: String,
+    pub strategy: Strategy,
+    // the replacement for CONSTANT
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_scalar",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "ImputeStrategy")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Strategy {
+    Constant,
+    Mean,
+    Median,
+    Mode,
+    ForwardFill,
+}
+
+impl Strategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Constant => "constant",
+            Self::Mean => "mean",
+            Self::Median => "median",
+            Self::Mode => "mode",
+            Self::ForwardFill => "forward_fill",
+        }
+    }
+}
+
+// nulls are replaced in place, so the view has the parent's columns and types
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.imputations, "imputations")?;
+    let table = quote_identifier(&parent.name);
+    let mut imputed: HashMap<&str, String> = HashMap::new();
+    let mut groups = Vec::new();
+    for imp in args.imputations.iter() {
+        let column = parent.column(&imp.column)?;
+        let quoted = quote_identifier(&imp.column);
+        let invalid = |msg: String| {
+            Err(Error::InvalidArguments(format!(
+                "{} {}: {}",
+                imp.column,
+                imp.strategy.name(),
+                msg
+            )))
+        };
+        let sql = match (imp.strategy, &imp.value) {
+            (Strategy::Constant, Some(v)) if is_valid_literal(column, v) => {
+                coalesce(&quoted, &quote_literal(v))
+            }
+            (Strategy::Constant, Some(v)) => {
+                return invalid(format!(
+                    "invalid {} value: {}",
+                    column.data_type, v
+                ))
+            }
+            (Strategy::Constant, None) => {
+                return invalid("requires a value".to_owned())
+            }
+            (_, Some(_)) => return invalid("takes no value".to_owned()),
+            (Strategy::ForwardFill, None) => {
+                // rows after a null share its group with the last non-null
+                // row, whose value is the first in the group
+                let group =
+                    quote_identifier(&format!("__group_{}", groups.len()));
+                groups.push(format!(
+                    "COUNT({}) OVER (ORDER BY \"__row\") AS {}",
+                    quoted, group
+                ));
+                format!(
+                    "FIRST_VALUE({}) OVER \
+                     (PARTITION BY {} ORDER BY \"__row\") AS {}",
+                    quoted, group, quoted
+                )
+            }
+            (strategy, None) => {
+                let summarizer = match strategy {
+                    Strategy::Mean => Summarizer::Mean,
+                    Strategy::Median => Summarizer::Median,
+                    _ => Summarizer::Mode,
+                };
+                if summarizer.requires_numeric()
+                    && DataType::of(&column.data_type) != DataType::Numeric
+                {
+                    return invalid("requires a numeric column".to_owned());
+                }
+                // casting back keeps e.g. integer columns integers
+                coalesce(
+                    &quoted,
+                    &format!(
+                        "(SELECT CAST({} AS {}) FROM {})",
+                        summarizer.as_sql(&quoted),
+                        column.data_type,
+                        table
+                    ),
+                )
+            }
+        };
+        if imputed.insert(&imp.column, sql).is_some() {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate column: {}",
+                imp.column
+            )));
+        }
+    }
+    let columns: Vec<String> = parent
+        .columns
+        .iter()
+        .map(|c| match imputed.get(c.column_name.as_str()) {
+            Some(sql) => sql.clone(),
+            None => quote_identifier(&c.column_name),
+        })
+        .collect();
+    // forward fills follow the parent's order, which row numbers capture
+    let select = if groups.is_empty() {
+        format!("SELECT {}\nFROM {}", columns.join(", "), table)
+    } else {
+        format!(
+            "SELECT {}\n\
+             FROM (\n\
+             SELECT *, {}\n\
+             FROM (SELECT *, ROW_NUMBER() OVER () AS \"__row\" FROM {}) r\n\
+             ) p\n\
+             ORDER BY \"__row\"",
+            columns.join(", "),
+            groups.join(", "),
+            table
+        )
+    };
+    Ok(create_view(view, &select))
+}
+
+fn coalesce(column: &str, value: &str) -> String {
+    format!("COALESCE({}, {}) AS {}", column, value, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn impute() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Impute,
+            json!({"imputations": [
+                {"column": "species", "strategy": "CONSTANT", "value": "n/a"},
+                {"column": "sepal_length", "strategy": "MEDIAN"},
+                {"column": "petal_width", "strategy": "FORWARD_FILL"},
+            ]}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT COALESCE(\"sepal_length\", \
+             (SELECT CAST(PERCENTILE_CONT(0.5) WITHIN GROUP \
+             (ORDER BY \"sepal_length\") AS double precision) \
+             FROM \"dataview_parent\")) AS \"sepal_length\", \
+             \"sepal_width\", \"petal_length\", \
+             FIRST_VALUE(\"petal_width\") OVER \
+             (PARTITION BY \"__group_0\" ORDER BY \"__row\") \
+             AS \"petal_width\", \
+             COALESCE(\"species\", 'n/a') AS \"species\"\n\
+             FROM (\n\
+             SELECT *, COUNT(\"petal_width\") OVER (ORDER BY \"__row\") \
+             AS \"__group_0\"\n\
+             FROM (SELECT *, ROW_NUMBER() OVER () AS \"__row\" \
+             FROM \"dataview_parent\") r\n\
+             ) p\n\
+             ORDER BY \"__row\""
+        );
+        let invalid = [
+            json!({"column": "species", "strategy": "MEAN"}),
+            json!({"column": "sepal_length", "strategy": "CONSTANT"}),
+            json!({"column": "sepal_length", "strategy": "CONSTANT", "value": "x"}),
+            json!({"column": "species", "strategy": "MODE", "value": "x"}),
+        ];
+        for imputation in invalid.iter() {
+            let res = compile_json(
+                Operation::Impute,
+                json!({"imputations": [imputation]}),
+            );
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use super::{create_view, non_empty, quote_identifier, Relation};
+use crate::Error;
+use async_graphql::{Enum, InputObject, ID};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "JoinArgs")]
+#[serde(rename_all = "camelCase")]
+pub struct Args {
+    // a dataview or dataset in the same project
+    pub with: ID,
+    pub how: How,
+    pub on: Vec<Key>,
+    // appended to columns in both relations, defaulting to _x and _y
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_suffix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right_suffix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "JoinKey")]
+pub struct Key {
+    pub left: String,
+    pub right: String,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "JoinType")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum How {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+impl How {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Inner => "INNER JOIN",
+            Self::Left => "LEFT JOIN",
+            Self::Right => "RIGHT JOIN",
+            Self::Full => "FULL JOIN",
+        }
+    }
+}
+
+// keys with the same name on both sides are merged into a single column, as
+// are their values for outer joins; other columns in both relations are
+// suffixed
+pub fn compile(
+    args: &Args,
+    left: &Relation,
+    right: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.on, "on")?;
+    let left_suffix = args.left_suffix.as_deref().unwrap_or("_x");
+    let right_suffix = args.right_suffix.as_deref().unwrap_or("_y");
+    if left_suffix == right_suffix {
+        return Err(Error::InvalidArguments(
+            "left and right suffixes must differ".to_owned(),
+        ));
+    }
+    let mut merged = HashSet::new();
+    let mut conditions = Vec::new();
+    for key in args.on.iter() {
+        let (lt, rt) =
+            (left.data_type(&key.left)?, right.data_type(&key.right)?);
+        if lt != rt {
+            return Err(Error::InvalidArguments(format!(
+                "cannot join {} ({:?}) on {} ({:?})",
+                key.left, lt, key.right, rt
+            )));
+        }
+        conditions.push(format!(
+            "l.{} = r.{}",
+            quote_identifier(&key.left),
+            quote_identifier(&key.right)
+        ));
+        if key.left == key.right {
+            merged.insert(key.left.as_str());
+        }
+    }
+    let left_names: HashSet<&str> = left
+        .columns
+        .iter()
+        .map(|c| c.column_name.as_str())
+        .collect();
+    let right_names: HashSet<&str> = right
+        .columns
+        .iter()
+        .map(|c| c.column_name.as_str())
+        .collect();
+    let mut outputs = HashSet::new();
+    let mut columns = Vec::new();
+    let mut push = |sql: String, name: String| {
+        if !outputs.insert(name.clone()) {
+            return Err(Error::InvalidArguments(format!(
+                "joined column name is not unique: {}",
+                name
+            )));
+        }
+        columns.push(sql);
+        Ok(())
+    };
+    for c in left.columns.iter().map(|c| c.column_name.as_str()) {
+        let q = quote_identifier(c);
+        if merged.contains(c) {
+            let sql = match args.how {
+                How::Inner | How::Left => format!("l.{}", q),
+                How::Right => format!("r.{} AS {}", q, q),
+                How::Full => format!("COALESCE(l.{}, r.{}) AS {}", q, q, q),
+            };
+            push(sql, c.to_owned())?;
+        } else if right_names.contains(c) {
+            let name = format!("{}{}", c, left_suffix);
+            push(format!("l.{} AS {}", q, quote_identifier(&name)), name)?;
+        } else {
+            push(format!("l.{}", q), c.to_owned())?;
+        }
+    }
+    for c in right.columns.iter().map(|c| c.column_name.as_str()) {
+        let q = quote_identifier(c);
+        if merged.contains(c) {
+            continue;
+        } else if left_names.contains(c) {
+            let name = format!("{}{}", c, right_suffix);
+            push(format!("r.{} AS {}", q, quote_identifier(&name)), name)?;
+        } else {
+            push(format!("r.{}", q), c.to_owned())?;
+        }
+    }
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\nFROM {} l\n{} {} r\nON {}",
+            columns.join(", "),
+            quote_identifier(&left.name),
+            args.how.as_sql(),
+            quote_identifier(&right.name),
+            conditions.join(" AND ")
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::Operation,
+        operations::{
+            compile,
+            fixtures::{iris, other},
+            references,
+        },
+        types::ColumnDataType,
+        Error,
+    };
+    use async_graphql::ID;
+    use serde_json::json;
+
+    #[test]
+    fn join() -> Result<(), Error> {
+        let args = json!({
+            "with": "RGF0YXNldDoxMjM=",
+            "how": "FULL",
+            "on": [{"left": "species", "right": "species"}],
+        });
+        let sql = compile(
+            &Operation::Join,
+            &args,
+            &iris(),
+            &[other()],
+            "dataview_child",
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT l.\"sepal_length\" AS \"sepal_length_x\", \
+             l.\"sepal_width\", l.\"petal_length\", l.\"petal_width\", \
+             COALESCE(l.\"species\", r.\"species\") AS \"species\", \
+             r.\"sepal_length\" AS \"sepal_length_y\", r.\"habitat\"\n\
+             FROM \"dataview_parent\" l\n\
+             FULL JOIN \"dataset_other\" r\n\
+             ON l.\"species\" = r.\"species\""
+        );
+        assert_eq!(
+            references(&Operation::Join, &args)?,
+            vec![ID::from("RGF0YXNldDoxMjM=")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn join_invalid() {
+        let mismatched = json!({
+            "with": "RGF0YXNldDoxMjM=",
+            "how": "INNER",
+            "on": [{"left": "sepal_length", "right": "species"}],
+        });
+        let res = compile(
+            &Operation::Join,
+            &mismatched,
+            &iris(),
+            &[other()],
+            "dataview_child",
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        // habitat_x would collide with an existing column
+        let mut left = iris();
+        left.columns.push(ColumnDataType {
+            column_name: "habitat_x".to_owned(),
+            data_type: "text".to_owned(),
+        });
+        left.columns.push(ColumnDataType {
+            column_name: "habitat".to_owned(),
+            data_type: "text".to_owned(),
+        });
+        let clashing = json!({
+            "with": "RGF0YXNldDoxMjM=",
+            "how": "INNER",
+            "on": [{"left": "species", "right": "species"}],
+        });
+        let res = compile(
+            &Operation::Join,
+            &clashing,
+            &left,
+            &[other()],
+            "dataview_child",
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+    }
+}
@@ -0,0 +1,58 @@
+use super::{create_view, quote_identifier, Relation};
+use crate::Error;
+use async_graphql::InputObject;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "LimitArgs")]
+pub struct Args {
+    pub rows: i32,
+    // the number of rows skipped first, defaulting to 0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i32>,
+}
+
+// rows are taken in the parent's order, e.g. after a Sort
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    let offset = args.offset.unwrap_or(0);
+    if args.rows < 0 || offset < 0 {
+        return Err(Error::InvalidArguments(
+            "rows and offset must not be negative".to_owned(),
+        ));
+    }
+    let mut select = format!(
+        "SELECT *\nFROM {}\nLIMIT {}",
+        quote_identifier(&parent.name),
+        args.rows
+    );
+    if offset > 0 {
+        select.push_str(&format!(" OFFSET {}", offset));
+    }
+    Ok(create_view(view, &select))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn limit() -> Result<(), Error> {
+        let sql =
+            compile_json(Operation::Limit, json!({"rows": 5, "offset": 10}))?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT *\n\
+             FROM \"dataview_parent\"\n\
+             LIMIT 5 OFFSET 10"
+        );
+        let res = compile_json(Operation::Limit, json!({"rows": -1}));
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+}
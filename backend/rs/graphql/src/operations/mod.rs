@@ -0,0 +1,489 @@
+use crate::{
+    gql::{graphql_id_to_uuid, model_keys, node_id},
+    models::{Dataset, Dataview, DataviewReference, Operation},
+    types::{ColumnDataType, Db},
+    utils::{
+        columns, dataset_table_name, dataview_view_name, json_string, one_of,
+    },
+    Error, Json,
+};
+use async_graphql::{Error as GQLError, InputObject, Result as GQLResult, ID};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use sqlx::Result as SQLxResult;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+pub mod bin;
+pub mod cast;
+pub mod distinct;
+pub mod drop_nulls;
+pub mod expr;
+pub mod filter;
+#[cfg(test)]
+pub mod fixtures;
+pub mod impute;
+pub mod join;
+pub mod limit;
+pub mod mutate;
+pub mod pivot;
+pub mod sample;
+pub mod select;
+pub mod sort;
+pub mod summarize;
+pub mod transform;
+pub mod union;
+pub mod unpivot;
+pub mod window;
+
+// postgres truncates longer identifiers, which could make columns collide
+pub const MAX_COLUMN_LENGTH: usize = 63;
+
+// a dataset table or dataview view that an operation reads from
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub id: ID,
+    pub name: String,
+    pub columns: Vec<ColumnDataType>,
+}
+
+impl Relation {
+    pub async fn dataset(db: &Db, uuid: &Uuid) -> SQLxResult<Self> {
+        Self::load(db, node_id("Dataset", uuid), dataset_table_name(uuid)).await
+    }
+
+    pub async fn dataview(db: &Db, uuid: &Uuid) -> SQLxResult<Self> {
+        Self::load(db, node_id("Dataview", uuid), dataview_view_name(uuid))
+            .await
+    }
+
+    async fn load(db: &Db, id: ID, name: String) -> SQLxResult<Self> {
+        Ok(Self {
+            id,
+            columns: columns(db, &name).await?,
+            name,
+        })
+    }
+
+    pub fn column(&self, name: &str) -> Result<&ColumnDataType, Error> {
+        self.columns
+            .iter()
+            .find(|c| c.column_name == name)
+            .ok_or_else(|| {
+                Error::InvalidArguments(format!("unknown column: {}", name))
+            })
+    }
+
+    pub fn data_type(&self, name: &str) -> Result<DataType, Error> {
+        self.column(name).map(|c| DataType::of(&c.data_type))
+    }
+}
+
+// set exactly one field, which determines the operation
+#[derive(Debug, Clone, InputObject)]
+pub struct OperationArgs {
+    pub bin: Option<bin::Args>,
+    pub cast: Option<cast::Args>,
+    pub distinct: Option<distinct::Args>,
+    pub drop_nulls: Option<drop_nulls::Args>,
+    pub filter: Option<filter::Args>,
+    pub impute: Option<impute::Args>,
+    pub join: Option<join::Args>,
+    pub limit: Option<limit::Args>,
+    pub mutate: Option<mutate::Args>,
+    pub pivot: Option<pivot::Args>,
+    pub sample: Option<sample::Args>,
+    pub select: Option<select::Args>,
+    pub sort: Option<sort::Args>,
+    pub summarize: Option<summarize::Args>,
+    pub transform: Option<transform::Args>,
+    pub union: Option<union::Args>,
+    pub unpivot: Option<unpivot::Args>,
+    pub window: Option<window::Args>,
+}
+
+impl OperationArgs {
+    pub fn into_parts(self) -> Result<(Operation, Json), Error> {
+        let (operation, args) = one_of(
+            "OperationArgs",
+            vec![
+                self.bin.map(|a| (Operation::Bin, json_string(&a))),
+                self.cast.map(|a| (Operation::Cast, json_string(&a))),
+                self.distinct
+                    .map(|a| (Operation::Distinct, json_string(&a))),
+                self.drop_nulls
+                    .map(|a| (Operation::DropNulls, json_string(&a))),
+                self.filter.map(|a| (Operation::Filter, json_string(&a))),
+                self.impute.map(|a| (Operation::Impute, json_string(&a))),
+                self.join.map(|a| (Operation::Join, json_string(&a))),
+                self.limit.map(|a| (Operation::Limit, json_string(&a))),
+                self.mutate.map(|a| (Operation::Mutate, json_string(&a))),
+                self.pivot.map(|a| (Operation::Pivot, json_string(&a))),
+                self.sample.map(|a| (Operation::Sample, json_string(&a))),
+                self.select.map(|a| (Operation::Select, json_string(&a))),
+                self.sort.map(|a| (Operation::Sort, json_string(&a))),
+                self.summarize
+                    .map(|a| (Operation::Summarize, json_string(&a))),
+                self.transform
+                    .map(|a| (Operation::Transform, json_string(&a))),
+                self.union.map(|a| (Operation::Union, json_string(&a))),
+                self.unpivot.map(|a| (Operation::Unpivot, json_string(&a))),
+                self.window.map(|a| (Operation::Window, json_string(&a))),
+            ],
+        )?;
+        Ok((operation, args?))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DataType {
+    Boolean,
+    Date,
+    Numeric,
+    Text,
+    Timestamp,
+    Other,
+}
+
+impl DataType {
+    // classifies information_schema.columns.data_type
+    pub fn of(data_type: &str) -> Self {
+        match data_type {
+            "smallint" | "integer" | "bigint" | "numeric" | "real"
+            | "double precision" => Self::Numeric,
+            "text" | "character varying" | "character" => Self::Text,
+            "boolean" => Self::Boolean,
+            "date" => Self::Date,
+            "timestamp without time zone" | "timestamp with time zone" => {
+                Self::Timestamp
+            }
+            _ => Self::Other,
+        }
+    }
+}
+
+// the dataviews and datasets an operation reads from besides its parent
+pub fn references(
+    operation: &Operation,
+    args: &Json,
+) -> Result<Vec<ID>, Error> {
+    match operation {
+        Operation::Join => Ok(vec![parse_args::<join::Args>(args)?.with]),
+        Operation::Union => Ok(parse_args::<union::Args>(args)?.with),
+        _ => Ok(vec![]),
+    }
+}
+
+// resolves a reference, which must be readable by the user and in the same
+// project as the parent
+pub async fn resolve(
+    db: &Db,
+    id: &ID,
+    user_uuid: &Uuid,
+    project_uuid: &Uuid,
+) -> GQLResult<(DataviewReference, Relation)> {
+    let uuid = graphql_id_to_uuid(id)?;
+    let (reference, role, project, relation) =
+        match model_keys(id)?.model.as_str() {
+            "Dataset" => (
+                DataviewReference::Dataset(uuid),
+                Dataset::role(db, &uuid, user_uuid).await,
+                Dataset::get(db, &uuid).await.map(|d| d.project_uuid),
+                Relation::dataset(db, &uuid).await,
+            ),
+            "Dataview" => (
+                DataviewReference::Dataview(uuid),
+                Dataview::role(db, &uuid, user_uuid).await,
+                Dataview::project_uuid(db, &uuid).await,
+                Relation::dataview(db, &uuid).await,
+            ),
+            _ => {
+                return Err(Error::InvalidArguments(format!(
+                    "{} is not a dataset or dataview",
+                    id.as_str()
+                ))
+                .into())
+            }
+        };
+    role.map_err(|_| -> GQLError { Error::InvalidPermissions.into() })?;
+    if project.map_err(|e| -> GQLError { e.into() })? != *project_uuid {
+        return Err(Error::InvalidArguments(format!(
+            "{} is not in the same project",
+            id.as_str()
+        ))
+        .into());
+    }
+    let relation = relation.map_err(|e| -> GQLError { e.into() })?;
+    // datasets have no table until they are loaded
+    if relation.columns.is_empty() {
+        return Err(Error::InvalidArguments(format!(
+            "{} has not finished loading",
+            id.as_str()
+        ))
+        .into());
+    }
+    Ok((reference, relation))
+}
+
+// fills in args that depend on the parent's data, so the stored args describe
+// the view exactly, and checks args the view would only fail on when read
+pub async fn prepare(
+    db: &Db,
+    operation: &Operation,
+    args: Json,
+    parent: &Relation,
+) -> GQLResult<Json> {
+    match operation {
+        Operation::Bin => {
+            let mut a: bin::Args = parse_args(&args)?;
+            if a.edges.is_none() {
+                a.edges = Some(bin::edges(db, &a, parent).await?);
+            }
+            Ok(json_string(&a)?)
+        }
+        Operation::Cast => {
+            cast::check(db, &parse_args(&args)?, parent).await?;
+            Ok(args)
+        }
+        Operation::Pivot => {
+            let mut a: pivot::Args = parse_args(&args)?;
+            if a.values.is_none() {
+                a.values = Some(pivot::values(db, &a, parent).await?);
+            }
+            Ok(json_string(&a)?)
+        }
+        _ => Ok(args),
+    }
+}
+
+// compiles the operation into a CREATE VIEW statement for `view`, where
+// `references` are resolved in the order returned by `references`
+pub fn compile(
+    operation: &Operation,
+    args: &Json,
+    parent: &Relation,
+    references: &[Relation],
+    view: &str,
+) -> Result<String, Error> {
+    match operation {
+        Operation::Bin => bin::compile(&parse_args(args)?, parent, view),
+        Operation::Cast => cast::compile(&parse_args(args)?, parent, view),
+        Operation::Distinct => {
+            distinct::compile(&parse_args(args)?, parent, view)
+        }
+        Operation::DropNulls => {
+            drop_nulls::compile(&parse_args(args)?, parent, view)
+        }
+        Operation::Filter => filter::compile(&parse_args(args)?, parent, view),
+        Operation::Impute => impute::compile(&parse_args(args)?, parent, view),
+        Operation::Join => match references {
+            [right] => join::compile(&parse_args(args)?, parent, right, view),
+            _ => Err(Error::InvalidArguments(
+                "join requires one dataview or dataset".to_owned(),
+            )),
+        },
+        Operation::Limit => limit::compile(&parse_args(args)?, parent, view),
+        Operation::Mutate => mutate::compile(&parse_args(args)?, parent, view),
+        Operation::Pivot => pivot::compile(&parse_args(args)?, parent, view),
+        Operation::Sample => sample::compile(&parse_args(args)?, parent, view),
+        Operation::Select => select::compile(&parse_args(args)?, parent, view),
+        Operation::Sort => sort::compile(&parse_args(args)?, parent, view),
+        Operation::Summarize => {
+            summarize::compile(&parse_args(args)?, parent, view)
+        }
+        Operation::Transform => {
+            transform::compile(&parse_args(args)?, parent, view)
+        }
+        Operation::Union => {
+            union::compile(&parse_args(args)?, parent, references, view)
+        }
+        Operation::Unpivot => {
+            unpivot::compile(&parse_args(args)?, parent, view)
+        }
+        Operation::Window => window::compile(&parse_args(args)?, parent, view),
+        Operation::Create => Err(Error::UnsupportedOperation),
+    }
+}
+
+// args have historically been sent as a JSON encoded string
+pub fn parse_args<T: DeserializeOwned>(args: &Json) -> Result<T, Error> {
+    let res = match args {
+        Json::String(s) => serde_json::from_str(s),
+        v => serde_json::from_value(v.clone()),
+    };
+    res.map_err(|e| Error::InvalidArguments(e.to_string()))
+}
+
+pub fn quote_identifier(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+pub fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+// accepts numbers and booleans where a literal is expected, i.e. 1 or "1"
+pub fn deserialize_scalar<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Json::deserialize(deserializer)? {
+        Json::String(s) => Ok(s),
+        Json::Number(n) => Ok(n.to_string()),
+        Json::Bool(b) => Ok(b.to_string()),
+        v => Err(serde::de::Error::custom(format!("invalid value: {}", v))),
+    }
+}
+
+pub fn deserialize_optional_scalar<'de, D>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Json>::deserialize(deserializer)?
+        .map(|v| deserialize_scalar(v).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+// the type columns are cast to when combined, if any; postgres already
+// resolves types in the same category, e.g. integer and double precision
+pub fn common_type<'a>(
+    columns: impl Iterator<Item = &'a ColumnDataType>,
+) -> Option<&'static str> {
+    let data_types: HashSet<&str> =
+        columns.map(|c| c.data_type.as_str()).collect();
+    if data_types.len() == 1 {
+        return None;
+    }
+    let kinds: HashSet<DataType> = data_types
+        .iter()
+        .map(|t| match DataType::of(t) {
+            DataType::Date => DataType::Timestamp,
+            kind => kind,
+        })
+        .collect();
+    match kinds.into_iter().collect::<Vec<_>>().as_slice() {
+        [DataType::Other] => Some("TEXT"),
+        [_] => None,
+        _ => Some("TEXT"),
+    }
+}
+
+// whether postgres can cast the literal to the column's type, so invalid
+// args fail when the dataview is created instead of when it is read
+pub fn is_valid_literal(column: &ColumnDataType, value: &str) -> bool {
+    match DataType::of(&column.data_type) {
+        DataType::Numeric => match column.data_type.as_str() {
+            "smallint" | "integer" | "bigint" => value.parse::<i64>().is_ok(),
+            _ => value.parse::<f64>().is_ok(),
+        },
+        DataType::Boolean => matches!(
+            value.to_lowercase().as_str(),
+            "true" | "false" | "t" | "f" | "yes" | "no" | "1" | "0"
+        ),
+        DataType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        DataType::Timestamp => {
+            DateTime::parse_from_rfc3339(value).is_ok()
+                || ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+                    .iter()
+                    .any(|f| NaiveDateTime::parse_from_str(value, f).is_ok())
+                || NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+        }
+        DataType::Text | DataType::Other => true,
+    }
+}
+
+pub fn non_empty<T>(items: &[T], name: &str) -> Result<(), Error> {
+    if items.is_empty() {
+        return Err(Error::InvalidArguments(format!(
+            "{} must not be empty",
+            name
+        )));
+    }
+    Ok(())
+}
+
+// the longest prefix of `name` of at most `max` bytes
+pub fn truncate_identifier(name: &str, max: usize) -> String {
+    let mut end = name.len().min(max);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name[..end].to_owned()
+}
+
+pub fn create_view(view: &str, select: &str) -> String {
+    format!("CREATE VIEW {} AS\n{}", quote_identifier(view), select)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fixtures::compile_json, *};
+    use serde_json::json;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote_identifier(r#"a"b"#), r#""a""b""#);
+        assert_eq!(quote_literal("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn args_as_string_or_object() -> Result<(), Error> {
+        let object = json!({"columns": ["species"]});
+        let string = Json::String(object.to_string());
+        assert_eq!(
+            compile_json(Operation::Select, object)?,
+            compile_json(Operation::Select, string)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn operation_args_one_of() -> Result<(), Error> {
+        let select = select::Args {
+            columns: vec!["species".to_owned()],
+        };
+        let args = OperationArgs {
+            bin: None,
+            cast: None,
+            distinct: None,
+            drop_nulls: None,
+            filter: None,
+            impute: None,
+            join: None,
+            limit: None,
+            mutate: None,
+            pivot: None,
+            sample: None,
+            select: Some(select.clone()),
+            sort: None,
+            summarize: None,
+            transform: None,
+            union: None,
+            unpivot: None,
+            window: None,
+        };
+        let (operation, json) = args.clone().into_parts()?;
+        assert_eq!(operation, Operation::Select);
+        // stored as a JSON encoded string for backward compatibility
+        assert_eq!(json, Json::String(r#"{"columns":["species"]}"#.to_owned()));
+        let none = OperationArgs {
+            select: None,
+            ..args.clone()
+        };
+        assert!(matches!(none.into_parts(), Err(Error::InvalidArguments(_))));
+        let both = OperationArgs {
+            sort: Some(sort::Args { sorts: vec![] }),
+            ..args
+        };
+        assert!(matches!(both.into_parts(), Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported() {
+        let res = compile_json(Operation::Create, json!({}));
+        assert!(matches!(res, Err(Error::UnsupportedOperation)));
+    }
+}
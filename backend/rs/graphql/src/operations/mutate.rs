@@ -0,0 +1,113 @@
+use super::{
+    create_view, expr, non_empty, quote_identifier, Relation, MAX_COLUMN_LENGTH,
+};
+use crate::Error;
+use async_graphql::InputObject;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "MutateArgs")]
+pub struct Args {
+    pub mutations: Vec<Mutation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "ColumnMutation")]
+pub struct Mutation {
+    pub column: String,
+    pub expression: String,
+}
+
+// expressions only reference the parent's columns; a mutation with the name
+// of an existing column replaces it in place
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.mutations, "mutations")?;
+    let mut compiled: HashMap<&str, String> = HashMap::new();
+    let mut added = Vec::new();
+    for m in args.mutations.iter() {
+        if m.column.is_empty() || m.column.len() > MAX_COLUMN_LENGTH {
+            return Err(Error::InvalidArguments(format!(
+                "column names must be 1 to {} bytes: {}",
+                MAX_COLUMN_LENGTH, m.column
+            )));
+        }
+        let (sql, _) =
+            expr::compile(&m.expression, parent).map_err(|e| match e {
+                Error::InvalidArguments(msg) => {
+                    Error::InvalidArguments(format!("{}: {}", m.column, msg))
+                }
+                e => e,
+            })?;
+        let column = format!("{} AS {}", sql, quote_identifier(&m.column));
+        if compiled.insert(&m.column, column).is_some() {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate column: {}",
+                m.column
+            )));
+        }
+        if parent.column(&m.column).is_err() {
+            added.push(m.column.as_str());
+        }
+    }
+    let columns: Vec<String> = parent
+        .columns
+        .iter()
+        .map(|c| c.column_name.as_str())
+        .chain(added)
+        .map(|c| match compiled.get(c) {
+            Some(sql) => sql.clone(),
+            None => quote_identifier(c),
+        })
+        .collect();
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\nFROM {}",
+            columns.join(", "),
+            quote_identifier(&parent.name)
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn mutate() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Mutate,
+            json!({"mutations": [
+                {"column": "ratio", "expression": "sepal_length / sepal_width"},
+                {"column": "species", "expression": "upper(species)"},
+            ]}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT \"sepal_length\", \"sepal_width\", \"petal_length\", \
+             \"petal_width\", UPPER(\"species\") AS \"species\", \
+             (CAST(\"sepal_length\" AS DOUBLE PRECISION) / \
+             NULLIF(\"sepal_width\", 0)) AS \"ratio\"\n\
+             FROM \"dataview_parent\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mutate_invalid() {
+        let res = compile_json(
+            Operation::Mutate,
+            json!({"mutations": [
+                {"column": "twice", "expression": "species * 2"},
+            ]}),
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+    }
+}
@@ -0,0 +1,198 @@
+use super::{
+    create_view, quote_identifier, quote_literal, summarize::Summarizer,
+    DataType, Relation, MAX_COLUMN_LENGTH,
+};
+use crate::{types::Db, Error};
+use async_graphql::{InputObject, Result as GQLResult};
+use serde::{Deserialize, Serialize};
+use sqlx::query_scalar;
+use std::collections::HashSet;
+
+// a pivot creates a column per value, so wide views are rejected
+pub const MAX_COLUMNS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "PivotArgs")]
+pub struct Args {
+    // the columns identifying each row of the result
+    pub index: Vec<String>,
+    // the column whose values become columns
+    pub column: String,
+    // the column aggregated into each new column
+    pub value: String,
+    pub summarizer: Summarizer,
+    // the values of `column` to create columns for, which default to all of
+    // them when the dataview is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<String>>,
+}
+
+// the distinct values of the pivot column, as text
+pub async fn values(
+    db: &Db,
+    args: &Args,
+    parent: &Relation,
+) -> GQLResult<Vec<String>> {
+    parent.column(&args.column)?;
+    let column = quote_identifier(&args.column);
+    let values: Vec<String> = query_scalar(&format!(
+        r#"
+        SELECT DISTINCT CAST({} AS TEXT)
+        FROM {}
+        WHERE {} IS NOT NULL
+        ORDER BY 1
+        LIMIT {}
+        "#,
+        column,
+        quote_identifier(&parent.name),
+        column,
+        MAX_COLUMNS + 1
+    ))
+    .fetch_all(&db.data)
+    .await?;
+    Ok(values)
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    let values = args.values.as_ref().ok_or_else(|| {
+        Error::InvalidArguments("pivot values are required".to_owned())
+    })?;
+    if values.is_empty() || values.len() > MAX_COLUMNS {
+        return Err(Error::InvalidArguments(format!(
+            "pivots must create 1 to {} columns, not {}",
+            MAX_COLUMNS,
+            values.len()
+        )));
+    }
+    let mut names = HashSet::new();
+    for c in args.index.iter() {
+        parent.column(c)?;
+        if !names.insert(c.as_str()) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate index column: {}",
+                c
+            )));
+        }
+    }
+    parent.column(&args.column)?;
+    let value_type = parent.data_type(&args.value)?;
+    if names.contains(args.column.as_str())
+        || names.contains(args.value.as_str())
+        || args.column == args.value
+    {
+        return Err(Error::InvalidArguments(
+            "index, column, and value must be different columns".to_owned(),
+        ));
+    }
+    if args.summarizer.requires_numeric() && value_type != DataType::Numeric {
+        return Err(Error::InvalidArguments(format!(
+            "{} requires a numeric column: {}",
+            args.summarizer.name(),
+            args.value
+        )));
+    }
+    let mut columns: Vec<String> =
+        args.index.iter().map(|c| quote_identifier(c)).collect();
+    for v in values.iter() {
+        if v.is_empty() || v.len() > MAX_COLUMN_LENGTH || !names.insert(v) {
+            return Err(Error::InvalidArguments(format!(
+                "cannot create pivot column: {}",
+                v
+            )));
+        }
+        columns.push(format!(
+            "{} FILTER (WHERE CAST({} AS TEXT) = {}) AS {}",
+            args.summarizer.as_sql(&quote_identifier(&args.value)),
+            quote_identifier(&args.column),
+            quote_literal(v),
+            quote_identifier(v)
+        ));
+    }
+    let mut select = format!(
+        "SELECT {}\nFROM {}",
+        columns.join(", "),
+        quote_identifier(&parent.name)
+    );
+    if !args.index.is_empty() {
+        let index: Vec<String> =
+            args.index.iter().map(|c| quote_identifier(c)).collect();
+        select.push_str(&format!("\nGROUP BY {}", index.join(", ")));
+    }
+    Ok(create_view(view, &select))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::Operation,
+        operations::{compile, fixtures::iris},
+        Error,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn pivot() -> Result<(), Error> {
+        let args = json!({
+            "index": ["petal_width"],
+            "column": "species",
+            "value": "sepal_length",
+            "summarizer": "MEAN",
+            "values": ["setosa", "o'neil"],
+        });
+        let sql =
+            compile(&Operation::Pivot, &args, &iris(), &[], "dataview_child")?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT \"petal_width\", \
+             AVG(\"sepal_length\") FILTER \
+             (WHERE CAST(\"species\" AS TEXT) = 'setosa') AS \"setosa\", \
+             AVG(\"sepal_length\") FILTER \
+             (WHERE CAST(\"species\" AS TEXT) = 'o''neil') AS \"o'neil\"\n\
+             FROM \"dataview_parent\"\n\
+             GROUP BY \"petal_width\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pivot_invalid() {
+        let values: Vec<String> =
+            (0..=pivot::MAX_COLUMNS).map(|i| i.to_string()).collect();
+        let too_wide = json!({
+            "index": [],
+            "column": "species",
+            "value": "sepal_length",
+            "summarizer": "SUM",
+            "values": values,
+        });
+        let clashing = json!({
+            "index": ["petal_width"],
+            "column": "species",
+            "value": "sepal_length",
+            "summarizer": "SUM",
+            "values": ["petal_width"],
+        });
+        let not_numeric = json!({
+            "index": [],
+            "column": "sepal_length",
+            "value": "species",
+            "summarizer": "SUM",
+            "values": ["1"],
+        });
+        for args in [too_wide, clashing, not_numeric].iter() {
+            let res = compile(
+                &Operation::Pivot,
+                args,
+                &iris(),
+                &[],
+                "dataview_child",
+            );
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+    }
+}
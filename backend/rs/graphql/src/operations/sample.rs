@@ -0,0 +1,143 @@
+use super::{create_view, quote_identifier, Relation};
+use crate::{utils::one_of, Error};
+use async_graphql::InputObject;
+use serde::{Deserialize, Serialize};
+
+// hashes are masked to 53 bits, which a double represents exactly
+const HASH_RANGE: f64 = 9007199254740992.0;
+
+// set exactly one of `fraction` and `rows`
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "SampleArgs")]
+pub struct Args {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fraction: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<i32>,
+    // defaults to 0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+}
+
+// views are evaluated on every read, so rows are sampled by hashing their
+// contents and copy number with the seed rather than with random(), which
+// keeps the sample stable
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    let hash = row_hash("p", args.seed.unwrap_or(0));
+    let columns = columns("p", parent);
+    let from = format!("FROM {}", numbered(parent));
+    let select = one_of(
+        "SampleArgs",
+        vec![
+            args.fraction.map(|f| {
+                if !(f > 0.0 && f <= 1.0) {
+                    return Err(Error::InvalidArguments(format!(
+                        "fraction must be in (0, 1]: {}",
+                        f
+                    )));
+                }
+                Ok(format!(
+                    "SELECT {}\n{}\nWHERE ({} & {}) < {}",
+                    columns,
+                    from,
+                    hash,
+                    HASH_RANGE as u64 - 1,
+                    (f * HASH_RANGE).round() as u64
+                ))
+            }),
+            args.rows.map(|n| {
+                if n < 1 {
+                    return Err(Error::InvalidArguments(format!(
+                        "rows must be positive: {}",
+                        n
+                    )));
+                }
+                Ok(format!(
+                    "SELECT {}\n{}\nORDER BY {}\nLIMIT {}",
+                    columns, from, hash, n
+                ))
+            }),
+        ],
+    )??;
+    Ok(create_view(view, &select))
+}
+
+// the relation as `p` with identical rows numbered, so hashing its rows
+// samples each copy independently rather than all or none of them; which copy
+// gets which number doesn't matter, as they're identical
+pub fn numbered(relation: &Relation) -> String {
+    format!(
+        "(SELECT p.*, ROW_NUMBER() OVER (PARTITION BY CAST(p AS TEXT)) \
+         AS \"__copy\" FROM {} p) p",
+        quote_identifier(&relation.name)
+    )
+}
+
+// the relation's own columns of `alias`, without the copy number
+pub fn columns(alias: &str, relation: &Relation) -> String {
+    relation
+        .columns
+        .iter()
+        .map(|c| format!("{}.{}", alias, quote_identifier(&c.column_name)))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// a seeded hash of a numbered row, which orders rows reproducibly
+pub fn row_hash(alias: &str, seed: i32) -> String {
+    format!("hashtextextended(CAST({} AS TEXT), {})", alias, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn sample() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Sample,
+            json!({"fraction": 0.25, "seed": 7}),
+        )?;
+        let select = "SELECT p.\"sepal_length\", p.\"sepal_width\", \
+                      p.\"petal_length\", p.\"petal_width\", p.\"species\"\n\
+                      FROM (SELECT p.*, ROW_NUMBER() OVER \
+                      (PARTITION BY CAST(p AS TEXT)) AS \"__copy\" \
+                      FROM \"dataview_parent\" p) p\n";
+        assert_eq!(
+            sql,
+            format!(
+                "CREATE VIEW \"dataview_child\" AS\n\
+                 {}WHERE (hashtextextended(CAST(p AS TEXT), 7) \
+                 & 9007199254740991) < 2251799813685248",
+                select
+            )
+        );
+        let sql = compile_json(Operation::Sample, json!({"rows": 10}))?;
+        assert_eq!(
+            sql,
+            format!(
+                "CREATE VIEW \"dataview_child\" AS\n\
+                 {}ORDER BY hashtextextended(CAST(p AS TEXT), 0)\n\
+                 LIMIT 10",
+                select
+            )
+        );
+        for args in [
+            json!({"fraction": 1.5}),
+            json!({"rows": 0}),
+            json!({"fraction": 0.5, "rows": 10}),
+            json!({}),
+        ]
+        .iter()
+        {
+            let res = compile_json(Operation::Sample, args.clone());
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use super::{create_view, non_empty, quote_identifier, Relation};
+use crate::Error;
+use async_graphql::InputObject;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "SelectArgs")]
+pub struct Args {
+    pub columns: Vec<String>,
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.columns, "columns")?;
+    let mut seen = HashSet::new();
+    for column in args.columns.iter() {
+        parent.column(column)?;
+        if !seen.insert(column) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate column: {}",
+                column
+            )));
+        }
+    }
+    let columns: Vec<String> =
+        args.columns.iter().map(|c| quote_identifier(c)).collect();
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\nFROM {}",
+            columns.join(", "),
+            quote_identifier(&parent.name)
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn select() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Select,
+            json!({"columns": ["species", "sepal_width"]}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT \"species\", \"sepal_width\"\n\
+             FROM \"dataview_parent\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn select_unknown_column() {
+        let res =
+            compile_json(Operation::Select, json!({"columns": ["petals"]}));
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+    }
+}
@@ -0,0 +1,86 @@
+use super::{create_view, non_empty, quote_identifier, Relation};
+use crate::Error;
+use async_graphql::{Enum, InputObject};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "SortArgs")]
+pub struct Args {
+    pub sorts: Vec<Sort>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct Sort {
+    pub column: String,
+    pub order: Order,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "SortOrder")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Order {
+    Ascending,
+    Descending,
+}
+
+impl Order {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Ascending => "ASC",
+            Self::Descending => "DESC",
+        }
+    }
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.sorts, "sorts")?;
+    let clauses = args
+        .sorts
+        .iter()
+        .map(|s| {
+            parent.column(&s.column)?;
+            Ok(format!(
+                "{} {}",
+                quote_identifier(&s.column),
+                s.order.as_sql()
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT *\nFROM {}\nORDER BY {}",
+            quote_identifier(&parent.name),
+            clauses.join(", ")
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn sort() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Sort,
+            json!({"sorts": [
+                {"column": "sepal_length", "order": "ASCENDING"},
+                {"column": "species", "order": "DESCENDING"},
+            ]}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT *\n\
+             FROM \"dataview_parent\"\n\
+             ORDER BY \"sepal_length\" ASC, \"species\" DESC"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use super::{
+    create_view, non_empty, quote_identifier, truncate_identifier, DataType,
+    Relation, MAX_COLUMN_LENGTH,
+};
+use crate::Error;
+use async_graphql::{Enum, InputObject};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "SummarizeArgs")]
+#[serde(rename_all = "camelCase")]
+pub struct Args {
+    pub summaries: Vec<Summary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_bys: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct Summary {
+    pub column: String,
+    pub summarizer: Summarizer,
+}
+
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Enum,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Summarizer {
+    Count,
+    Mean,
+    Median,
+    Mode,
+    Min,
+    Max,
+    Sum,
+    Stddev,
+}
+
+impl Summarizer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Mean => "mean",
+            Self::Median => "median",
+            Self::Mode => "mode",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Sum => "sum",
+            Self::Stddev => "stddev",
+        }
+    }
+
+    pub fn requires_numeric(&self) -> bool {
+        matches!(self, Self::Mean | Self::Median | Self::Sum | Self::Stddev)
+    }
+
+    pub fn as_sql(&self, column: &str) -> String {
+        match self {
+            Self::Count => format!("COUNT({})", column),
+            Self::Mean => format!("AVG({})", column),
+            Self::Median => format!(
+                "PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY {})",
+                column
+            ),
+            Self::Mode => format!("MODE() WITHIN GROUP (ORDER BY {})", column),
+            Self::Min => format!("MIN({})", column),
+            Self::Max => format!("MAX({})", column),
+            Self::Sum => format!("SUM({})", column),
+            Self::Stddev => format!("STDDEV({})", column),
+        }
+    }
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.summaries, "summaries")?;
+    let group_bys = args.group_bys.clone().unwrap_or_default();
+    let mut names: HashSet<String> = HashSet::new();
+    let mut summaries: HashSet<(&str, Summarizer)> = HashSet::new();
+    let mut clauses = Vec::new();
+    for column in group_bys.iter() {
+        parent.column(column)?;
+        if !names.insert(column.clone()) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate group by: {}",
+                column
+            )));
+        }
+        clauses.push(quote_identifier(column));
+    }
+    for s in args.summaries.iter() {
+        let data_type = parent.data_type(&s.column)?;
+        if s.summarizer.requires_numeric() && data_type != DataType::Numeric {
+            return Err(Error::InvalidArguments(format!(
+                "{} requires a numeric column: {}",
+                s.summarizer.name(),
+                s.column
+            )));
+        }
+        if !summaries.insert((&s.column, s.summarizer)) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate summary: {} {}",
+                s.summarizer.name(),
+                s.column
+            )));
+        }
+        let name = output_name(&s.column, s.summarizer, &names);
+        if !names.insert(name.clone()) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate output column: {}",
+                name
+            )));
+        }
+        clauses.push(format!(
+            "{} AS {}",
+            s.summarizer.as_sql(&quote_identifier(&s.column)),
+            quote_identifier(&name)
+        ));
+    }
+    let mut select = format!(
+        "SELECT {}\nFROM {}",
+        clauses.join(", "),
+        quote_identifier(&parent.name)
+    );
+    if !group_bys.is_empty() {
+        let group_bys: Vec<String> =
+            group_bys.iter().map(|c| quote_identifier(c)).collect();
+        select.push_str(&format!("\nGROUP BY {}", group_bys.join(", ")));
+    }
+    Ok(create_view(view, &select))
+}
+
+// output columns are named <column>_<summarizer>, where long columns are
+// shortened to what postgres keeps and numbered when that makes them collide
+fn output_name(
+    column: &str,
+    summarizer: Summarizer,
+    taken: &HashSet<String>,
+) -> String {
+    let suffix = format!("_{}", summarizer.name());
+    let name = format!("{}{}", column, suffix);
+    if name.len() <= MAX_COLUMN_LENGTH {
+        return name;
+    }
+    let mut k = 1;
+    loop {
+        let n = match k {
+            1 => String::new(),
+            k => format!("_{}", k),
+        };
+        let max = MAX_COLUMN_LENGTH - n.len() - suffix.len();
+        let name =
+            format!("{}{}{}", truncate_identifier(column, max), n, suffix);
+        if !taken.contains(&name) {
+            return name;
+        }
+        k += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::Operation,
+        operations::{
+            compile,
+            fixtures::{compile_json, iris},
+            MAX_COLUMN_LENGTH,
+        },
+        types::ColumnDataType,
+        Error,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn summarize() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Summarize,
+            json!({
+                "summaries": [
+                    {"column": "sepal_length", "summarizer": "MEAN"},
+                    {"column": "petal_width", "summarizer": "MEDIAN"},
+                    {"column": "species", "summarizer": "MODE"},
+                ],
+                "groupBys": ["species"],
+            }),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT \"species\", \
+             AVG(\"sepal_length\") AS \"sepal_length_mean\", \
+             PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY \"petal_width\") \
+             AS \"petal_width_median\", \
+             MODE() WITHIN GROUP (ORDER BY \"species\") AS \"species_mode\"\n\
+             FROM \"dataview_parent\"\n\
+             GROUP BY \"species\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn summarize_shortens_long_names() -> Result<(), Error> {
+        let long = "x".repeat(MAX_COLUMN_LENGTH);
+        let mut parent = iris();
+        for name in [long.clone(), format!("{}y", long)].iter() {
+            parent.columns.push(ColumnDataType {
+                column_name: name.clone(),
+                data_type: "double precision".to_owned(),
+            });
+        }
+        let sql = compile(
+            &Operation::Summarize,
+            &json!({"summaries": [
+                {"column": long, "summarizer": "MEAN"},
+                {"column": format!("{}y", long), "summarizer": "MEAN"},
+            ]}),
+            &parent,
+            &[],
+            "dataview_child",
+        )?;
+        let first = format!("{}_mean", "x".repeat(MAX_COLUMN_LENGTH - 5));
+        let second = format!("{}_2_mean", "x".repeat(MAX_COLUMN_LENGTH - 7));
+        assert!(sql.contains(&format!("AS \"{}\", ", first)));
+        assert!(sql.contains(&format!("AS \"{}\"\n", second)));
+        Ok(())
+    }
+
+    #[test]
+    fn summarize_requires_numeric() {
+        let res = compile_json(
+            Operation::Summarize,
+            json!({"summaries": [{"column": "species", "summarizer": "SUM"}]}),
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+    }
+}
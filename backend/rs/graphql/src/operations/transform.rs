@@ -0,0 +1,289 @@
+use super::{
+    create_view, non_empty, quote_identifier, quote_literal, DataType,
+    Relation, MAX_COLUMN_LENGTH,
+};
+use crate::Error;
+use async_graphql::{Enum, InputObject};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "TransformArgs")]
+pub struct Args {
+    pub transforms: Vec<Transform>,
+}
+
+// transforms apply in order, so a column can be e.g. trimmed then recoded
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "TextTransform")]
+pub struct Transform {
+    pub column: String,
+    pub function: TextFunction,
+    // the regular expression for EXTRACT and REPLACE
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    // the replacement for REPLACE, where \1 refers to the first group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+    // the delimiter for SPLIT
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<String>,
+    // the 1-based part SPLIT keeps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<i32>,
+    // the recoded values for RECODE, which keeps unmapped values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<Vec<Recode>>,
+    // defaults to column, which is replaced in place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct Recode {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TextFunction {
+    Trim,
+    Lower,
+    Upper,
+    Extract,
+    Replace,
+    Split,
+    Recode,
+}
+
+impl TextFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Trim => "trim",
+            Self::Lower => "lower",
+            Self::Upper => "upper",
+            Self::Extract => "extract",
+            Self::Replace => "replace",
+            Self::Split => "split",
+            Self::Recode => "recode",
+        }
+    }
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.transforms, "transforms")?;
+    let mut order: Vec<String> = Vec::new();
+    let mut sql: HashMap<String, String> = HashMap::new();
+    let mut text: HashSet<String> = HashSet::new();
+    for c in parent.columns.iter() {
+        order.push(c.column_name.clone());
+        sql.insert(c.column_name.clone(), quote_identifier(&c.column_name));
+        if DataType::of(&c.data_type) == DataType::Text {
+            text.insert(c.column_name.clone());
+        }
+    }
+    for t in args.transforms.iter() {
+        let input = match sql.get(&t.column) {
+            Some(input) if text.contains(&t.column) => input,
+            Some(_) => {
+                return Err(Error::InvalidArguments(format!(
+                    "{} requires a text column: {}",
+                    t.function.name(),
+                    t.column
+                )))
+            }
+            None => {
+                return Err(Error::InvalidArguments(format!(
+                    "unknown column: {}",
+                    t.column
+                )))
+            }
+        };
+        let output = transform_sql(t, input)?;
+        let name = t.name.as_ref().unwrap_or(&t.column);
+        if name.is_empty() || name.len() > MAX_COLUMN_LENGTH {
+            return Err(Error::InvalidArguments(format!(
+                "column names must be 1 to {} bytes: {}",
+                MAX_COLUMN_LENGTH, name
+            )));
+        }
+        if sql.insert(name.clone(), output).is_none() {
+            order.push(name.clone());
+        }
+        text.insert(name.clone());
+    }
+    let columns: Vec<String> = order
+        .iter()
+        .map(|c| {
+            let quoted = quote_identifier(c);
+            if sql[c] == quoted {
+                quoted
+            } else {
+                format!("{} AS {}", sql[c], quoted)
+            }
+        })
+        .collect();
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\nFROM {}",
+            columns.join(", "),
+            quote_identifier(&parent.name)
+        ),
+    ))
+}
+
+fn transform_sql(t: &Transform, input: &str) -> Result<String, Error> {
+    let f = t.function;
+    let invalid = |msg: &str| {
+        Err(Error::InvalidArguments(format!(
+            "{} {}: {}",
+            t.column,
+            f.name(),
+            msg
+        )))
+    };
+    let takes = |field: &str| match f {
+        TextFunction::Extract => field == "pattern",
+        TextFunction::Replace => field == "pattern" || field == "replacement",
+        TextFunction::Split => field == "delimiter" || field == "index",
+        TextFunction::Recode => field == "mapping",
+        _ => false,
+    };
+    let fields = [
+        ("pattern", t.pattern.is_some()),
+        ("replacement", t.replacement.is_some()),
+        ("delimiter", t.delimiter.is_some()),
+        ("index", t.index.is_some()),
+        ("mapping", t.mapping.is_some()),
+    ];
+    for (field, set) in fields.iter() {
+        if *set != takes(field) {
+            let msg = match set {
+                true => format!("does not take {}", field),
+                false => format!("requires {}", field),
+            };
+            return invalid(&msg);
+        }
+    }
+    // postgres regular expressions mostly share the regex crate's syntax, so
+    // this catches invalid patterns before the view is read
+    let pattern = match &t.pattern {
+        Some(p) => match Regex::new(p) {
+            Ok(_) => quote_literal(p),
+            Err(e) => return invalid(&e.to_string()),
+        },
+        None => String::new(),
+    };
+    Ok(match f {
+        TextFunction::Trim => format!("BTRIM({})", input),
+        TextFunction::Lower => format!("LOWER({})", input),
+        TextFunction::Upper => format!("UPPER({})", input),
+        // the first group, or the whole match without groups
+        TextFunction::Extract => {
+            format!("(REGEXP_MATCH({}, {}))[1]", input, pattern)
+        }
+        TextFunction::Replace => format!(
+            "REGEXP_REPLACE({}, {}, {}, 'g')",
+            input,
+            pattern,
+            quote_literal(t.replacement.as_deref().unwrap_or_default())
+        ),
+        TextFunction::Split => {
+            let delimiter = t.delimiter.as_deref().unwrap_or_default();
+            let index = t.index.unwrap_or_default();
+            if delimiter.is_empty() || index < 1 {
+                return invalid("requires a delimiter and a positive index");
+            }
+            format!(
+                "SPLIT_PART({}, {}, {})",
+                input,
+                quote_literal(delimiter),
+                index
+            )
+        }
+        TextFunction::Recode => {
+            let mapping = t.mapping.as_deref().unwrap_or_default();
+            if mapping.is_empty() {
+                return invalid("requires a mapping");
+            }
+            let mut seen = HashSet::new();
+            let mut cases = Vec::new();
+            for r in mapping.iter() {
+                if !seen.insert(&r.from) {
+                    return invalid(&format!("duplicate value: {}", r.from));
+                }
+                cases.push(format!(
+                    "WHEN {} THEN {}",
+                    quote_literal(&r.from),
+                    quote_literal(&r.to)
+                ));
+            }
+            format!("CASE {} {} ELSE {} END", input, cases.join(" "), input)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn transform() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Transform,
+            json!({"transforms": [
+                {"column": "species", "function": "TRIM"},
+                {"column": "species", "function": "LOWER"},
+                {
+                    "column": "species",
+                    "function": "RECODE",
+                    "mapping": [{"from": "setosa", "to": "s"}],
+                    "name": "code",
+                },
+                {
+                    "column": "code",
+                    "function": "REPLACE",
+                    "pattern": "^(\\w)",
+                    "replacement": "x\\1",
+                },
+            ]}),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT \"sepal_length\", \"sepal_width\", \
+             \"petal_length\", \"petal_width\", \
+             LOWER(BTRIM(\"species\")) AS \"species\", \
+             REGEXP_REPLACE(CASE LOWER(BTRIM(\"species\")) \
+             WHEN 'setosa' THEN 's' ELSE LOWER(BTRIM(\"species\")) END, \
+             '^(\\w)', 'x\\1', 'g') AS \"code\"\n\
+             FROM \"dataview_parent\""
+        );
+        let invalid = [
+            json!({"column": "sepal_length", "function": "TRIM"}),
+            json!({"column": "species", "function": "EXTRACT"}),
+            json!({"column": "species", "function": "EXTRACT", "pattern": "("}),
+            json!({"column": "species", "function": "LOWER", "index": 1}),
+            json!({
+                "column": "species",
+                "function": "SPLIT",
+                "delimiter": ",",
+                "index": 0,
+            }),
+        ];
+        for t in invalid.iter() {
+            let res =
+                compile_json(Operation::Transform, json!({"transforms": [t]}));
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use super::{
+    common_type, create_view, non_empty, quote_identifier, quote_literal,
+    Relation,
+};
+use crate::{types::ColumnDataType, Error};
+use async_graphql::{Enum, InputObject, ID};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "UnionArgs")]
+#[serde(rename_all = "camelCase")]
+pub struct Args {
+    // dataviews or datasets in the same project, appended in order, which
+    // can't be deleted while the union reads from them
+    pub with: Vec<ID>,
+    pub by: By,
+    // adds a column with the id of the dataview or dataset each row is from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_column: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[graphql(name = "UnionBy")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum By {
+    // columns are matched by name and must be the same in every relation
+    Name,
+    // columns are matched by position and named after the parent's
+    Position,
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    others: &[Relation],
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(others, "with")?;
+    let relations: Vec<&Relation> =
+        std::iter::once(parent).chain(others.iter()).collect();
+    // the matching column of each relation for each of the parent's columns
+    let mut matched: Vec<Vec<&ColumnDataType>> = Vec::new();
+    for r in relations.iter() {
+        matched.push(match args.by {
+            By::Name => by_name(parent, r)?,
+            By::Position => by_position(parent, r)?,
+        });
+    }
+    if let Some(source) = &args.source_column {
+        if parent.column(source).is_ok() {
+            return Err(Error::InvalidArguments(format!(
+                "source column already exists: {}",
+                source
+            )));
+        }
+    }
+    let casts: Vec<Option<&'static str>> = (0..parent.columns.len())
+        .map(|i| common_type(matched.iter().map(|cols| cols[i])))
+        .collect();
+    let selects: Vec<String> = relations
+        .iter()
+        .zip(matched.iter())
+        .map(|(r, cols)| {
+            let mut columns: Vec<String> = cols
+                .iter()
+                .zip(parent.columns.iter())
+                .zip(casts.iter())
+                .map(|((c, p), cast)| {
+                    let q = quote_identifier(&c.column_name);
+                    match cast {
+                        Some(t) => format!(
+                            "CAST({} AS {}) AS {}",
+                            q,
+                            t,
+                            quote_identifier(&p.column_name)
+                        ),
+                        None if c.column_name != p.column_name => format!(
+                            "{} AS {}",
+                            q,
+                            quote_identifier(&p.column_name)
+                        ),
+                        None => q,
+                    }
+                })
+                .collect();
+            if let Some(source) = &args.source_column {
+                columns.push(format!(
+                    "{} AS {}",
+                    quote_literal(r.id.as_str()),
+                    quote_identifier(source)
+                ));
+            }
+            format!(
+                "SELECT {}\nFROM {}",
+                columns.join(", "),
+                quote_identifier(&r.name)
+            )
+        })
+        .collect();
+    Ok(create_view(view, &selects.join("\nUNION ALL\n")))
+}
+
+fn by_name<'a>(
+    parent: &Relation,
+    other: &'a Relation,
+) -> Result<Vec<&'a ColumnDataType>, Error> {
+    let names: HashSet<&str> = parent
+        .columns
+        .iter()
+        .map(|c| c.column_name.as_str())
+        .collect();
+    let extra: Vec<&str> = other
+        .columns
+        .iter()
+        .map(|c| c.column_name.as_str())
+        .filter(|c| !names.contains(c))
+        .collect();
+    if !extra.is_empty() {
+        return Err(Error::InvalidArguments(format!(
+            "{} has columns not in the dataview: {}",
+            other.id.as_str(),
+            extra.join(", ")
+        )));
+    }
+    parent
+        .columns
+        .iter()
+        .map(|c| {
+            other.column(&c.column_name).map_err(|_| {
+                Error::InvalidArguments(format!(
+                    "{} is missing column: {}",
+                    other.id.as_str(),
+                    c.column_name
+                ))
+            })
+        })
+        .collect()
+}
+
+fn by_position<'a>(
+    parent: &Relation,
+    other: &'a Relation,
+) -> Result<Vec<&'a ColumnDataType>, Error> {
+    if other.columns.len() != parent.columns.len() {
+        return Err(Error::InvalidArguments(format!(
+            "{} has {} columns but the dataview has {}",
+            other.id.as_str(),
+            other.columns.len(),
+            parent.columns.len()
+        )));
+    }
+    Ok(other.columns.iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::Operation,
+        operations::{
+            compile,
+            fixtures::{iris, other},
+            references,
+        },
+        Error,
+    };
+    use async_graphql::ID;
+    use serde_json::json;
+
+    #[test]
+    fn union() -> Result<(), Error> {
+        let mut month = iris();
+        month.id = ID::from("RGF0YXNldDptb250aA==");
+        month.name = "dataset_month".to_owned();
+        month.columns.reverse();
+        month.columns[0].data_type = "integer".to_owned();
+        month.columns[1].data_type = "text".to_owned();
+        let args = json!({
+            "with": ["RGF0YXNldDptb250aA=="],
+            "by": "NAME",
+            "sourceColumn": "source",
+        });
+        assert_eq!(
+            references(&Operation::Union, &args)?,
+            vec![ID::from("RGF0YXNldDptb250aA==")]
+        );
+        let sql = compile(
+            &Operation::Union,
+            &args,
+            &iris(),
+            &[month.clone()],
+            "dataview_child",
+        )?;
+        // integer and double precision are both numeric, but text is not
+        let select = |source: &str| {
+            format!(
+                "SELECT \"sepal_length\", \"sepal_width\", \"petal_length\", \
+                 CAST(\"petal_width\" AS TEXT) AS \"petal_width\", \
+                 CAST(\"species\" AS TEXT) AS \"species\", \
+                 '{}' AS \"source\"",
+                source
+            )
+        };
+        assert_eq!(
+            sql,
+            format!(
+                "CREATE VIEW \"dataview_child\" AS\n\
+                 {}\nFROM \"dataview_parent\"\n\
+                 UNION ALL\n\
+                 {}\nFROM \"dataset_month\"",
+                select("RGF0YXZpZXc6cGFyZW50"),
+                select("RGF0YXNldDptb250aA==")
+            )
+        );
+        // by position, columns take the parent's names even when the
+        // month's are in a different order
+        let res = compile(
+            &Operation::Union,
+            &json!({"with": ["RGF0YXNldDptb250aA=="], "by": "POSITION"}),
+            &iris(),
+            &[month],
+            "dataview_child",
+        )?;
+        assert!(res.contains("\"species\" AS \"sepal_length\""));
+        assert!(
+            res.contains("CAST(\"petal_width\" AS TEXT) AS \"sepal_width\"")
+        );
+        let res = compile(
+            &Operation::Union,
+            &json!({"with": ["RGF0YXNldDoxMjM="], "by": "NAME"}),
+            &iris(),
+            &[other()],
+            "dataview_child",
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use super::{
+    common_type, create_view, non_empty, quote_identifier, quote_literal,
+    Relation,
+};
+use crate::Error;
+use async_graphql::InputObject;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "UnpivotArgs")]
+#[serde(rename_all = "camelCase")]
+pub struct Args {
+    // the columns stacked into rows; the rest are kept on every row
+    pub columns: Vec<String>,
+    // defaults to name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_column: Option<String>,
+    // defaults to value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_column: Option<String>,
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.columns, "columns")?;
+    let name_column = args.name_column.as_deref().unwrap_or("name");
+    let value_column = args.value_column.as_deref().unwrap_or("value");
+    let mut stacked = HashSet::new();
+    let mut unpivoted = Vec::new();
+    for c in args.columns.iter() {
+        unpivoted.push(parent.column(c)?);
+        if !stacked.insert(c.as_str()) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate column: {}",
+                c
+            )));
+        }
+    }
+    let kept: Vec<&str> = parent
+        .columns
+        .iter()
+        .map(|c| c.column_name.as_str())
+        .filter(|c| !stacked.contains(c))
+        .collect();
+    for c in [name_column, value_column].iter() {
+        if c.is_empty() || kept.contains(c) {
+            return Err(Error::InvalidArguments(format!(
+                "invalid name or value column: {}",
+                c
+            )));
+        }
+    }
+    if name_column == value_column {
+        return Err(Error::InvalidArguments(
+            "name and value columns must differ".to_owned(),
+        ));
+    }
+    // values of different types are stacked as text
+    let cast = common_type(unpivoted.iter().copied());
+    let rows: Vec<String> = unpivoted
+        .iter()
+        .map(|c| {
+            let q = format!("p.{}", quote_identifier(&c.column_name));
+            let value = match cast {
+                Some(t) => format!("CAST({} AS {})", q, t),
+                None => q,
+            };
+            format!("({}, {})", quote_literal(&c.column_name), value)
+        })
+        .collect();
+    let mut columns: Vec<String> = kept
+        .iter()
+        .map(|c| format!("p.{}", quote_identifier(c)))
+        .collect();
+    columns.push(format!("u.{}", quote_identifier(name_column)));
+    columns.push(format!("u.{}", quote_identifier(value_column)));
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\nFROM {} p\nCROSS JOIN LATERAL (VALUES {}) AS u({}, {})",
+            columns.join(", "),
+            quote_identifier(&parent.name),
+            rows.join(", "),
+            quote_identifier(name_column),
+            quote_identifier(value_column)
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::Operation,
+        operations::{compile, fixtures::iris},
+        Error,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn unpivot() -> Result<(), Error> {
+        let sql = compile(
+            &Operation::Unpivot,
+            &json!({
+                "columns": ["petal_length", "species"],
+                "nameColumn": "measure",
+            }),
+            &iris(),
+            &[],
+            "dataview_child",
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT p.\"sepal_length\", p.\"sepal_width\", \
+             p.\"petal_width\", u.\"measure\", u.\"value\"\n\
+             FROM \"dataview_parent\" p\n\
+             CROSS JOIN LATERAL (VALUES \
+             ('petal_length', CAST(p.\"petal_length\" AS TEXT)), \
+             ('species', CAST(p.\"species\" AS TEXT))) \
+             AS u(\"measure\", \"value\")"
+        );
+        let res = compile(
+            &Operation::Unpivot,
+            &json!({"columns": ["petal_length"], "valueColumn": "species"}),
+            &iris(),
+            &[],
+            "dataview_child",
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use super::{
+    create_view, non_empty, quote_identifier, sort::Sort, DataType, Relation,
+    MAX_COLUMN_LENGTH,
+};
+use crate::Error;
+use async_graphql::{Enum, InputObject};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+#[graphql(name = "WindowArgs")]
+#[serde(rename_all = "camelCase")]
+pub struct Args {
+    pub windows: Vec<Window>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition_by: Option<Vec<String>>,
+    pub order_by: Vec<Sort>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct Window {
+    pub function: WindowFunction,
+    // the column the function reads, which ranks do not take
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    // rows back for LAG and forward for LEAD, defaulting to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i32>,
+    // the number of rows ROLLING_MEAN averages, including the current one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<i32>,
+    // defaults to <column>_<function>, or <function> for ranks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WindowFunction {
+    Rank,
+    RowNumber,
+    Lag,
+    Lead,
+    CumulativeSum,
+    RollingMean,
+}
+
+impl WindowFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rank => "rank",
+            Self::RowNumber => "row_number",
+            Self::Lag => "lag",
+            Self::Lead => "lead",
+            Self::CumulativeSum => "cumulative_sum",
+            Self::RollingMean => "rolling_mean",
+        }
+    }
+
+    fn takes_column(&self) -> bool {
+        !matches!(self, Self::Rank | Self::RowNumber)
+    }
+
+    fn requires_numeric(&self) -> bool {
+        matches!(self, Self::CumulativeSum | Self::RollingMean)
+    }
+}
+
+pub fn compile(
+    args: &Args,
+    parent: &Relation,
+    view: &str,
+) -> Result<String, Error> {
+    non_empty(&args.windows, "windows")?;
+    // without an order, ranks and running values are arbitrary
+    non_empty(&args.order_by, "orderBy")?;
+    let partition_by = args.partition_by.clone().unwrap_or_default();
+    let mut sorts: Vec<String> = Vec::new();
+    for c in partition_by.iter() {
+        parent.column(c)?;
+        sorts.push(quote_identifier(c));
+    }
+    let mut order_by: Vec<String> = Vec::new();
+    for s in args.order_by.iter() {
+        parent.column(&s.column)?;
+        order_by.push(format!(
+            "{} {}",
+            quote_identifier(&s.column),
+            s.order.as_sql()
+        ));
+    }
+    sorts.extend(order_by.iter().cloned());
+    let over = if partition_by.is_empty() {
+        format!("ORDER BY {}", order_by.join(", "))
+    } else {
+        let partition_by: Vec<String> =
+            partition_by.iter().map(|c| quote_identifier(c)).collect();
+        format!(
+            "PARTITION BY {} ORDER BY {}",
+            partition_by.join(", "),
+            order_by.join(", ")
+        )
+    };
+    let mut names: HashSet<String> = parent
+        .columns
+        .iter()
+        .map(|c| c.column_name.clone())
+        .collect();
+    let mut columns = vec!["*".to_owned()];
+    for w in args.windows.iter() {
+        let sql = window_sql(w, parent, &over)?;
+        let name = match (&w.name, &w.column) {
+            (Some(name), _) => name.clone(),
+            (None, Some(c)) => format!("{}_{}", c, w.function.name()),
+            (None, None) => w.function.name().to_owned(),
+        };
+        if name.is_empty() || name.len() > MAX_COLUMN_LENGTH {
+            return Err(Error::InvalidArguments(format!(
+                "column names must be 1 to {} bytes: {}",
+                MAX_COLUMN_LENGTH, name
+            )));
+        }
+        columns.push(format!("{} AS {}", sql, quote_identifier(&name)));
+        if !names.insert(name.clone()) {
+            return Err(Error::InvalidArguments(format!(
+                "duplicate output column: {}",
+                name
+            )));
+        }
+    }
+    Ok(create_view(
+        view,
+        &format!(
+            "SELECT {}\nFROM {}\nORDER BY {}",
+            columns.join(", "),
+            quote_identifier(&parent.name),
+            sorts.join(", ")
+        ),
+    ))
+}
+
+fn window_sql(
+    w: &Window,
+    parent: &Relation,
+    over: &str,
+) -> Result<String, Error> {
+    let f = w.function;
+    let column = match (&w.column, f.takes_column()) {
+        (Some(c), true) => {
+            let data_type = parent.data_type(c)?;
+            if f.requires_numeric() && data_type != DataType::Numeric {
+                return Err(Error::InvalidArguments(format!(
+                    "{} requires a numeric column: {}",
+                    f.name(),
+                    c
+                )));
+            }
+            quote_identifier(c)
+        }
+        (None, true) => {
+            return Err(Error::InvalidArguments(format!(
+                "{} requires a column",
+                f.name()
+            )))
+        }
+        (Some(_), false) => {
+            return Err(Error::InvalidArguments(format!(
+                "{} does not take a column",
+                f.name()
+            )))
+        }
+        (None, false) => String::new(),
+    };
+    if w.offset.is_some()
+        && !matches!(f, WindowFunction::Lag | WindowFunction::Lead)
+    {
+        return Err(Error::InvalidArguments(format!(
+            "{} does not take an offset",
+            f.name()
+        )));
+    }
+    if w.rows.is_some() && f != WindowFunction::RollingMean {
+        return Err(Error::InvalidArguments(format!(
+            "{} does not take rows",
+            f.name()
+        )));
+    }
+    let positive = |n: Option<i32>, name: &str| match n {
+        Some(n) if n < 1 => Err(Error::InvalidArguments(format!(
+            "{} must be positive",
+            name
+        ))),
+        n => Ok(n.unwrap_or(1)),
+    };
+    let offset = positive(w.offset, "offset")?;
+    let sql = match f {
+        WindowFunction::Rank => format!("RANK() OVER ({})", over),
+        WindowFunction::RowNumber => format!("ROW_NUMBER() OVER ({})", over),
+        WindowFunction::Lag => {
+            format!("LAG({}, {}) OVER ({})", column, offset, over)
+        }
+        WindowFunction::Lead => {
+            format!("LEAD({}, {}) OVER ({})", column, offset, over)
+        }
+        // the default RANGE frame would sum rows with equal order values
+        // together, so frames are counted in rows
+        WindowFunction::CumulativeSum => format!(
+            "SUM({}) OVER ({} ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW)",
+            column, over
+        ),
+        WindowFunction::RollingMean => {
+            if w.rows.is_none() {
+                return Err(Error::InvalidArguments(
+                    "rolling_mean requires rows".to_owned(),
+                ));
+            }
+            let rows = positive(w.rows, "rows")?;
+            format!(
+                "AVG({}) OVER ({} ROWS BETWEEN {} PRECEDING AND CURRENT ROW)",
+                column,
+                over,
+                rows - 1
+            )
+        }
+    };
+    Ok(sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Operation, operations::fixtures::compile_json, Error};
+    use serde_json::json;
+
+    #[test]
+    fn window() -> Result<(), Error> {
+        let sql = compile_json(
+            Operation::Window,
+            json!({
+                "windows": [
+                    {"function": "RANK"},
+                    {"function": "LAG", "column": "species", "offset": 2},
+                    {
+                        "function": "ROLLING_MEAN",
+                        "column": "sepal_length",
+                        "rows": 3,
+                        "name": "smoothed",
+                    },
+                ],
+                "partitionBy": ["species"],
+                "orderBy": [{"column": "petal_width", "order": "DESCENDING"}],
+            }),
+        )?;
+        assert_eq!(
+            sql,
+            "CREATE VIEW \"dataview_child\" AS\n\
+             SELECT *, \
+             RANK() OVER (PARTITION BY \"species\" \
+             ORDER BY \"petal_width\" DESC) AS \"rank\", \
+             LAG(\"species\", 2) OVER (PARTITION BY \"species\" \
+             ORDER BY \"petal_width\" DESC) AS \"species_lag\", \
+             AVG(\"sepal_length\") OVER (PARTITION BY \"species\" \
+             ORDER BY \"petal_width\" DESC \
+             ROWS BETWEEN 2 PRECEDING AND CURRENT ROW) AS \"smoothed\"\n\
+             FROM \"dataview_parent\"\n\
+             ORDER BY \"species\", \"petal_width\" DESC"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn window_invalid() {
+        let order_by = json!([{"column": "petal_width", "order": "ASCENDING"}]);
+        let windows = [
+            json!({"function": "CUMULATIVE_SUM", "column": "species"}),
+            json!({"function": "ROLLING_MEAN", "column": "sepal_length"}),
+            json!({"function": "LEAD", "column": "species", "offset": 0}),
+            json!({"function": "ROW_NUMBER", "column": "species"}),
+            json!({"function": "LAG", "column": "species", "name": "species"}),
+        ];
+        for w in windows.iter() {
+            let res = compile_json(
+                Operation::Window,
+                json!({"windows": [w], "orderBy": order_by}),
+            );
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+        let res = compile_json(
+            Operation::Window,
+            json!({"windows": [{"function": "RANK"}], "orderBy": []}),
+        );
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+    }
+}
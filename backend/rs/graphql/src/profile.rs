@@ -0,0 +1,350 @@
+use crate::{
+    operations::{bin::MAX_BINS, quote_identifier, DataType, Relation},
+    types::{ColumnDataType, Db},
+    Error,
+};
+use async_graphql::{Error as GQLError, Result as GQLResult, SimpleObject};
+use sqlx::{query, query_as, Postgres, Row, Transaction};
+use std::time::Duration;
+use tokio::time::timeout;
+
+pub const DEFAULT_TOP_VALUES: usize = 10;
+pub const MAX_TOP_VALUES: usize = 100;
+pub const DEFAULT_BINS: usize = 10;
+// profiles are computed while the client waits, so large relations fail
+// fast rather than holding a connection; the whole profile is limited, and
+// each statement so that postgres stops work the client has given up on
+pub const STATEMENT_TIMEOUT_MS: u32 = 10000;
+// postgres selects at most 1664 values, and each column's stats are 7
+const STATS_COLUMNS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, SimpleObject)]
+pub struct ColumnProfile {
+    pub column: String,
+    pub data_type: String,
+    pub n_rows: i64,
+    pub null_count: i64,
+    pub distinct_count: i64,
+    // as text, for columns with an order other than booleans
+    pub min: Option<String>,
+    pub max: Option<String>,
+    // numeric columns only
+    pub mean: Option<f64>,
+    pub top_values: Vec<ValueCount>,
+    // equal width bins, for numeric columns only
+    pub histogram: Vec<HistogramBin>,
+}
+
+#[derive(Debug, Clone, PartialEq, SimpleObject)]
+pub struct ValueCount {
+    // as text, or null for nulls
+    pub value: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, SimpleObject)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: i64,
+}
+
+// profiles `columns`, or every column of the relation
+pub async fn profile(
+    db: &Db,
+    relation: &Relation,
+    columns: Option<Vec<String>>,
+    top_values: Option<i32>,
+    bins: Option<i32>,
+) -> GQLResult<Vec<ColumnProfile>> {
+    let top_values = limit(top_values, DEFAULT_TOP_VALUES, MAX_TOP_VALUES)
+        .ok_or_else(|| {
+            Error::InvalidArguments(format!(
+                "topValues must be 1 to {}",
+                MAX_TOP_VALUES
+            ))
+        })?;
+    let bins = limit(bins, DEFAULT_BINS, MAX_BINS).ok_or_else(|| {
+        Error::InvalidArguments(format!("bins must be 1 to {}", MAX_BINS))
+    })?;
+    let columns = match columns {
+        Some(columns) => columns
+            .iter()
+            .map(|c| relation.column(c).cloned())
+            .collect::<Result<Vec<_>, Error>>()?,
+        None => relation.columns.clone(),
+    };
+    let profiles = async {
+        let mut tx = db.data.begin().await?;
+        // SET LOCAL only lasts until the transaction ends
+        query(&format!(
+            "SET LOCAL statement_timeout = {}",
+            STATEMENT_TIMEOUT_MS
+        ))
+        .execute(&mut tx)
+        .await?;
+        let mut stats = Vec::with_capacity(columns.len());
+        for chunk in columns.chunks(STATS_COLUMNS) {
+            let row = query(&stats_sql(relation, chunk))
+                .fetch_one(&mut tx)
+                .await?;
+            let n_rows: i64 = row.try_get(0)?;
+            for i in 0..chunk.len() {
+                let at = |k: usize| 1 + 7 * i + k;
+                stats.push(ColumnStats {
+                    n_rows,
+                    null_count: row.try_get(at(0))?,
+                    distinct_count: row.try_get(at(1))?,
+                    min: row.try_get(at(2))?,
+                    max: row.try_get(at(3))?,
+                    mean: row.try_get(at(4))?,
+                    lower: row.try_get(at(5))?,
+                    upper: row.try_get(at(6))?,
+                });
+            }
+        }
+        let mut profiles = Vec::with_capacity(columns.len());
+        for (c, stats) in columns.iter().zip(stats) {
+            profiles.push(
+                profile_column(&mut tx, relation, c, stats, top_values, bins)
+                    .await?,
+            );
+        }
+        tx.commit().await?;
+        Ok::<_, GQLError>(profiles)
+    };
+    let within = Duration::from_millis(STATEMENT_TIMEOUT_MS.into());
+    match timeout(within, profiles).await {
+        Ok(profiles) => profiles,
+        Err(_) => Err(Error::Timeout(format!(
+            "profiling took longer than {}ms; profile fewer columns",
+            STATEMENT_TIMEOUT_MS
+        ))
+        .into()),
+    }
+}
+
+struct ColumnStats {
+    n_rows: i64,
+    null_count: i64,
+    distinct_count: i64,
+    min: Option<String>,
+    max: Option<String>,
+    mean: Option<f64>,
+    lower: Option<f64>,
+    upper: Option<f64>,
+}
+
+async fn profile_column(
+    tx: &mut Transaction<'_, Postgres>,
+    relation: &Relation,
+    column: &ColumnDataType,
+    stats: ColumnStats,
+    top_values: usize,
+    bins: usize,
+) -> GQLResult<ColumnProfile> {
+    let ColumnStats {
+        n_rows,
+        null_count,
+        distinct_count,
+        min,
+        max,
+        mean,
+        lower,
+        upper,
+    } = stats;
+    let top_values = query_as::<_, (Option<String>, i64)>(&top_values_sql(
+        relation, column, top_values,
+    ))
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|(value, count)| ValueCount { value, count })
+    .collect();
+    let histogram = match (lower, upper) {
+        (Some(lower), Some(upper)) if lower < upper => {
+            let counts: Vec<(i32, i64)> =
+                query_as(&histogram_sql(relation, column, bins))
+                    .bind(lower)
+                    .bind(upper)
+                    .fetch_all(&mut *tx)
+                    .await?;
+            let width = (upper - lower) / bins as f64;
+            (1..=bins)
+                .map(|i| HistogramBin {
+                    lower: lower + (i - 1) as f64 * width,
+                    upper: match i == bins {
+                        true => upper,
+                        false => lower + i as f64 * width,
+                    },
+                    count: counts
+                        .iter()
+                        .find(|(b, _)| *b as usize == i)
+                        .map(|(_, n)| *n)
+                        .unwrap_or(0),
+                })
+                .collect()
+        }
+        // a column with one value gets one bin of zero width
+        (Some(lower), Some(upper)) => vec![HistogramBin {
+            lower,
+            upper,
+            count: n_rows - null_count,
+        }],
+        _ => vec![],
+    };
+    Ok(ColumnProfile {
+        column: column.column_name.clone(),
+        data_type: column.data_type.clone(),
+        n_rows,
+        null_count,
+        distinct_count,
+        min,
+        max,
+        mean,
+        top_values,
+        histogram,
+    })
+}
+
+fn limit(n: Option<i32>, default: usize, max: usize) -> Option<usize> {
+    match n {
+        None => Some(default),
+        Some(n) if n > 0 && n as usize <= max => Some(n as usize),
+        Some(_) => None,
+    }
+}
+
+// the row count, then each column's null count, distinct count, min and max
+// as text, mean, and numeric min and max; columns without equality, like
+// json, are counted by their text
+pub fn stats_sql(relation: &Relation, columns: &[ColumnDataType]) -> String {
+    let mut stats = vec!["COUNT(*)".to_owned()];
+    for column in columns.iter() {
+        let c = quote_identifier(&column.column_name);
+        let data_type = DataType::of(&column.data_type);
+        let distinct = match data_type {
+            DataType::Other => format!("CAST({} AS TEXT)", c),
+            _ => c.clone(),
+        };
+        stats.push(format!("COUNT(*) - COUNT({})", c));
+        stats.push(format!("COUNT(DISTINCT {})", distinct));
+        match data_type {
+            DataType::Boolean | DataType::Other => {
+                stats.push("CAST(NULL AS TEXT)".to_owned());
+                stats.push("CAST(NULL AS TEXT)".to_owned());
+            }
+            _ => {
+                stats.push(format!("CAST(MIN({}) AS TEXT)", c));
+                stats.push(format!("CAST(MAX({}) AS TEXT)", c));
+            }
+        }
+        match data_type {
+            DataType::Numeric => {
+                stats.push(format!("CAST(AVG({}) AS DOUBLE PRECISION)", c));
+                stats.push(format!("CAST(MIN({}) AS DOUBLE PRECISION)", c));
+                stats.push(format!("CAST(MAX({}) AS DOUBLE PRECISION)", c));
+            }
+            _ => {
+                for _ in 0..3 {
+                    stats.push("CAST(NULL AS DOUBLE PRECISION)".to_owned());
+                }
+            }
+        }
+    }
+    format!(
+        "SELECT {}\nFROM {}",
+        stats.join(", "),
+        quote_identifier(&relation.name)
+    )
+}
+
+pub fn top_values_sql(
+    relation: &Relation,
+    column: &ColumnDataType,
+    top_values: usize,
+) -> String {
+    format!(
+        "SELECT CAST({} AS TEXT) AS value, COUNT(*) AS count\n\
+         FROM {}\n\
+         GROUP BY 1\n\
+         ORDER BY 2 DESC, 1\n\
+         LIMIT {}",
+        quote_identifier(&column.column_name),
+        quote_identifier(&relation.name),
+        top_values
+    )
+}
+
+// bins are numbered from 1, with the maximum, bound as $2, in the last bin
+pub fn histogram_sql(
+    relation: &Relation,
+    column: &ColumnDataType,
+    bins: usize,
+) -> String {
+    let c = format!(
+        "CAST({} AS DOUBLE PRECISION)",
+        quote_identifier(&column.column_name)
+    );
+    format!(
+        "SELECT LEAST(WIDTH_BUCKET({}, $1, $2, {}), {}) AS bin, \
+         COUNT(*) AS count\n\
+         FROM {}\n\
+         WHERE {} IS NOT NULL\n\
+         GROUP BY 1",
+        c,
+        bins,
+        bins,
+        quote_identifier(&relation.name),
+        c
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::fixtures;
+
+    fn relation() -> Relation {
+        fixtures::relation(
+            "RGF0YXNldDpwcm9maWxl",
+            "dataset_profile",
+            &[("x", "integer"), ("tags", "json"), ("y", "boolean")],
+        )
+    }
+
+    #[test]
+    fn stats_sql_by_type() {
+        let relation = relation();
+        assert_eq!(
+            stats_sql(&relation, &relation.columns[..2]),
+            "SELECT COUNT(*), COUNT(*) - COUNT(\"x\"), COUNT(DISTINCT \"x\"), \
+             CAST(MIN(\"x\") AS TEXT), CAST(MAX(\"x\") AS TEXT), \
+             CAST(AVG(\"x\") AS DOUBLE PRECISION), \
+             CAST(MIN(\"x\") AS DOUBLE PRECISION), \
+             CAST(MAX(\"x\") AS DOUBLE PRECISION), \
+             COUNT(*) - COUNT(\"tags\"), \
+             COUNT(DISTINCT CAST(\"tags\" AS TEXT)), \
+             CAST(NULL AS TEXT), CAST(NULL AS TEXT), \
+             CAST(NULL AS DOUBLE PRECISION), \
+             CAST(NULL AS DOUBLE PRECISION), \
+             CAST(NULL AS DOUBLE PRECISION)\n\
+             FROM \"dataset_profile\""
+        );
+    }
+
+    #[test]
+    fn histogram_sql_bins() {
+        let relation = relation();
+        assert_eq!(
+            histogram_sql(&relation, &relation.columns[0], 5),
+            "SELECT LEAST(WIDTH_BUCKET(CAST(\"x\" AS DOUBLE PRECISION), \
+             $1, $2, 5), 5) AS bin, COUNT(*) AS count\n\
+             FROM \"dataset_profile\"\n\
+             WHERE CAST(\"x\" AS DOUBLE PRECISION) IS NOT NULL\n\
+             GROUP BY 1"
+        );
+        assert_eq!(limit(None, 10, 100), Some(10));
+        assert_eq!(limit(Some(0), 10, 100), None);
+    }
+}
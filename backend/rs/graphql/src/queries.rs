@@ -1,6 +1,6 @@
 use crate::{
     models::{Operation, PlotType, StatisticType, Status},
-    ColumnDataType, Json, Vars,
+    Column, Json, Vars,
 };
 use async_graphql::{Request, Variables};
 use chrono::{DateTime, Utc};
@@ -101,7 +101,7 @@ pub struct DatasetResponse {
     pub updated_at: DateTime<Utc>,
     pub name: String,
     pub status: Status,
-    pub schema: Vec<ColumnDataType>,
+    pub schema: Vec<Column>,
     pub sample_rows: Json,
 }
 
@@ -115,6 +115,9 @@ const DATASET_FRAGMENT: &'static str = r#"
     schema {
         columnName
         dataType
+        ordinalPosition
+        isNullable
+        semanticType
     }
     sampleRows
 "#;
@@ -123,8 +126,13 @@ pub fn create_dataset(vars: &Vars) -> Request {
     make_request(
         format!(
             r#"
-        mutation CreateDataset($projectId: ID!, $name: String!, $uri: String!) {{
-            createDataset(projectId: $projectId, name: $name, uri: $uri) {{
+        mutation CreateDataset(
+            $projectId: ID!, $name: String!, $uri: String!,
+            $format: DatasetFormat
+        ) {{
+            createDataset(
+                projectId: $projectId, name: $name, uri: $uri, format: $format
+            ) {{
                 {}
             }}
         }}
@@ -180,6 +188,9 @@ const ANALYSIS_FRAGMENT: &'static str = r#"
         schema {
             columnName
             dataType
+            ordinalPosition
+            isNullable
+            semanticType
         }
         sampleRows
     }
@@ -237,32 +248,58 @@ pub struct DataviewResponse {
     pub operation: Operation,
     pub args: Option<Json>,
     pub status: Status,
+    pub materialized: bool,
+    pub refreshed_at: Option<DateTime<Utc>>,
 }
 
+const DATAVIEW_FRAGMENT: &'static str = r#"
+    __typename
+    id
+    createdAt
+    updatedAt
+    operation
+    args
+    status
+    materialized
+    refreshedAt
+"#;
+
 pub fn create_dataview(vars: &Vars) -> Request {
     make_request(
-        r#"
+        format!(
+            r#"
         mutation CreateDataview(
             $analysisId: ID!,
-            $operation: Operation!,
-            $args: JSON!,
-        ) {
+            $args: OperationArgs!,
+            $materialize: Boolean,
+        ) {{
             createDataview(
                 analysisId: $analysisId,
-                operation: $operation,
                 args: $args,
-            ) {
-                __typename
-                id
-                createdAt
-                updatedAt
-                operation
-                args
-                status
-            }
-        }
-        "#
-        .to_owned(),
+                materialize: $materialize,
+            ) {{
+                {}
+            }}
+        }}
+        "#,
+            DATAVIEW_FRAGMENT,
+        ),
+        vars,
+    )
+}
+
+pub fn refresh_dataview(vars: &Vars) -> Request {
+    make_request(
+        format!(
+            r#"
+        mutation RefreshDataview($dataviewId: ID!) {{
+            refreshDataview(dataviewId: $dataviewId) {{
+                {}
+            }}
+        }}
+        "#,
+            DATAVIEW_FRAGMENT,
+        ),
         vars,
     )
 }
@@ -299,12 +336,10 @@ pub fn create_statistic(vars: &Vars) -> Request {
             r#"
         mutation CreateStatistic(
             $dataviewId: ID!,
-            $type: StatisticType!,
-            $args: JSON!,
+            $args: StatisticArgs!,
         ) {{
             createStatistic(
                 dataviewId: $dataviewId,
-                type: $type,
                 args: $args,
             ) {{
                 {}
@@ -350,13 +385,11 @@ pub fn create_plot(vars: &Vars) -> Request {
         mutation CreatePlot(
             $dataviewId: ID!,
             $name: String!,
-            $type: PlotType!,
-            $args: JSON!,
+            $args: PlotArgs!,
         ) {{
             createPlot(
                 dataviewId: $dataviewId,
                 name: $name,
-                type: $type,
                 args: $args,
             ) {{
                 {}
@@ -460,6 +493,172 @@ pub fn rename_model(vars: &Vars) -> Request {
     )
 }
 
+// the fields the frontend's analysis page requests for each dataview
+pub fn analysis_dataviews(vars: &Vars) -> Request {
+    make_request(
+        r#"
+        query AnalysisDataviews($analysisId: ID!) {
+            dataviews(analysisId: $analysisId) {
+                edges {
+                    node {
+                        id
+                        analysis {
+                            id
+                            dataset {
+                                id
+                                name
+                            }
+                        }
+                        parent {
+                            id
+                        }
+                        operation
+                        status
+                    }
+                }
+            }
+        }
+        "#
+        .to_owned(),
+        vars,
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnProfileResponse {
+    pub column_profile: ColumnProfile,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnProfile {
+    pub n_rows: i64,
+    pub null_count: i64,
+    pub distinct_count: i64,
+    pub mean: Option<f64>,
+    pub top_values: Vec<Json>,
+    pub histogram: Vec<Json>,
+}
+
+pub fn dataview_column_profile(vars: &Vars) -> Request {
+    make_request(
+        r#"
+        query DataviewColumnProfile($id: ID!, $column: String!, $bins: Int) {
+            node(id: $id) {
+                ... on Dataview {
+                    columnProfile(column: $column, bins: $bins) {
+                        nRows
+                        nullCount
+                        distinctCount
+                        mean
+                        topValues {
+                            value
+                            count
+                        }
+                        histogram {
+                            lower
+                            upper
+                            count
+                        }
+                    }
+                }
+            }
+        }
+        "#
+        .to_owned(),
+        vars,
+    )
+}
+
+#[derive(Deserialize)]
+pub struct DataviewRowsResponse {
+    pub rows: RowsResponse,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowsResponse {
+    pub edges: Vec<RowEdge>,
+    pub page_info: PageInfo,
+}
+
+#[derive(Deserialize)]
+pub struct RowEdge {
+    pub cursor: String,
+    pub node: Json,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+pub fn dataview_rows(vars: &Vars) -> Request {
+    make_request(
+        r#"
+        query DataviewRows(
+            $id: ID!,
+            $first: Int,
+            $after: String,
+            $orderBy: [Sort!],
+        ) {
+            node(id: $id) {
+                ... on Dataview {
+                    rows(first: $first, after: $after, orderBy: $orderBy) {
+                        edges {
+                            cursor
+                            node
+                        }
+                        pageInfo {
+                            hasNextPage
+                            endCursor
+                        }
+                    }
+                }
+            }
+        }
+        "#
+        .to_owned(),
+        vars,
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleRowsResponse {
+    pub sample_rows: Vec<Json>,
+}
+
+pub fn dataview_sample_rows(vars: &Vars) -> Request {
+    make_request(
+        r#"
+        query DataviewSampleRows(
+            $id: ID!,
+            $n: Int,
+            $strategy: SampleStrategy,
+            $seed: Int,
+            $column: String,
+        ) {
+            node(id: $id) {
+                ... on Dataview {
+                    sampleRows(
+                        n: $n,
+                        strategy: $strategy,
+                        seed: $seed,
+                        column: $column,
+                    )
+                }
+            }
+        }
+        "#
+        .to_owned(),
+        vars,
+    )
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusResponse {
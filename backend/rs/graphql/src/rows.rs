@@ -0,0 +1,508 @@
+use crate::{
+    operations::{
+        non_empty, quote_identifier,
+        sample::{columns, numbered, row_hash},
+        sort::{Order, Sort},
+        DataType, Relation,
+    },
+    types::{ColumnDataType, Db},
+    Error, Json,
+};
+use async_graphql::{
+    connection::{query, Connection, CursorType, Edge, EmptyFields},
+    Enum, Json as GQLJson, Result as GQLResult,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, query_scalar};
+use std::collections::HashSet;
+
+pub const DEFAULT_ROWS: usize = 100;
+pub const MAX_ROWS: usize = 1000;
+
+pub type Rows = Connection<RowCursor, GQLJson<Json>, EmptyFields, EmptyFields>;
+
+// the row's sort key as postgres text, and how many rows with that key have
+// been paged through, since views have no key to break ties with and rows
+// can tie on every column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RowCursor {
+    pub values: Vec<Option<String>>,
+    pub skip: usize,
+}
+
+impl CursorType for RowCursor {
+    type Error = Error;
+
+    fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+        let invalid =
+            || Error::InvalidArguments(format!("invalid cursor: {}", s));
+        let decoded = base64::decode(s).map_err(|_| invalid())?;
+        serde_json::from_slice(&decoded).map_err(|_| invalid())
+    }
+
+    fn encode_cursor(&self) -> String {
+        base64::encode(serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+pub async fn rows(
+    db: &Db,
+    relation: &Relation,
+    first: Option<i32>,
+    after: Option<String>,
+    order_by: Option<Vec<Sort>>,
+    columns: Option<Vec<String>>,
+) -> GQLResult<Rows> {
+    query(
+        after,
+        None,
+        first,
+        None,
+        |after: Option<RowCursor>, _, first, _| async move {
+            let first = first.unwrap_or(DEFAULT_ROWS).min(MAX_ROWS);
+            let sql = rows_sql(
+                relation,
+                first + 1,
+                after.as_ref(),
+                order_by.as_deref().unwrap_or_default(),
+                columns.as_deref(),
+            )?;
+            let mut q = query_as(&sql);
+            if let Some(after) = &after {
+                for value in after.values.iter() {
+                    q = q.bind(value);
+                }
+            }
+            let mut rows: Vec<(Json, Json)> = q.fetch_all(&db.data).await?;
+            let has_next = rows.len() > first;
+            rows.truncate(first);
+            let mut connection = Connection::new(after.is_some(), has_next);
+            let mut previous = after;
+            for (row, key) in rows.into_iter() {
+                let values: Vec<Option<String>> =
+                    serde_json::from_value(key).map_err(|_| Error::Serde)?;
+                let skip = match &previous {
+                    Some(p) if p.values == values => p.skip + 1,
+                    _ => 1,
+                };
+                let cursor = RowCursor { values, skip };
+                previous = Some(cursor.clone());
+                connection.append(Some(Edge::new(cursor, GQLJson(row))));
+            }
+            Ok(connection)
+        },
+    )
+    .await
+}
+
+// the order rows are paged and sampled in: `order_by`, then every other
+// orderable column, then the rest by their text, with nulls last; as
+// (expression of `alias`, type of its values, order)
+pub fn sort_key(
+    alias: &str,
+    relation: &Relation,
+    order_by: &[Sort],
+) -> Result<Vec<(String, String, Order)>, Error> {
+    let orderable =
+        |c: &&ColumnDataType| DataType::of(&c.data_type) != DataType::Other;
+    let rest = relation
+        .columns
+        .iter()
+        .filter(orderable)
+        .chain(relation.columns.iter().filter(|c| !orderable(c)))
+        .map(|c| (c.column_name.as_str(), Order::Ascending));
+    let sorts = order_by.iter().map(|s| (s.column.as_str(), s.order));
+    let mut sorted = HashSet::new();
+    let mut key = Vec::new();
+    for (column, order) in sorts.chain(rest) {
+        let data_type = &relation.column(column)?.data_type;
+        if !sorted.insert(column) {
+            continue;
+        }
+        let e = format!("{}.{}", alias, quote_identifier(column));
+        key.push(match DataType::of(data_type) {
+            DataType::Text => (e, "TEXT".to_owned(), order),
+            DataType::Other => {
+                (format!("CAST({} AS TEXT)", e), "TEXT".to_owned(), order)
+            }
+            _ => (e, data_type.to_uppercase(), order),
+        });
+    }
+    Ok(key)
+}
+
+pub fn order_by_key(key: &[(String, String, Order)]) -> String {
+    key.iter()
+        .map(|(e, _, order)| format!("{} {} NULLS LAST", e, order.as_sql()))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// pages by keyset on the sort key, which rows `after` are at or past, then
+// skips the rows tied with it that were already paged through; the key's
+// values are bound as $1, $2, ...
+pub fn rows_sql(
+    relation: &Relation,
+    limit: usize,
+    after: Option<&RowCursor>,
+    order_by: &[Sort],
+    columns: Option<&[String]>,
+) -> Result<String, Error> {
+    let projected: Vec<&str> = match columns {
+        Some(columns) => {
+            non_empty(columns, "columns")?;
+            let mut seen = HashSet::new();
+            for c in columns.iter() {
+                relation.column(c)?;
+                if !seen.insert(c) {
+                    return Err(Error::InvalidArguments(format!(
+                        "duplicate column: {}",
+                        c
+                    )));
+                }
+            }
+            columns.iter().map(|c| c.as_str()).collect()
+        }
+        None => relation
+            .columns
+            .iter()
+            .map(|c| c.column_name.as_str())
+            .collect(),
+    };
+    let key = sort_key("t", relation, order_by)?;
+    let (condition, skip) = match after {
+        Some(after) if after.values.len() != key.len() => {
+            return Err(Error::InvalidArguments(
+                "cursor is for a different order".to_owned(),
+            ))
+        }
+        Some(after) => {
+            let condition = at_or_past(&key, 0);
+            (format!("\nWHERE {}", condition), after.skip)
+        }
+        None => (String::new(), 0),
+    };
+    let projected: Vec<String> = projected
+        .iter()
+        .map(|c| format!("t.{}", quote_identifier(c)))
+        .collect();
+    let values: Vec<String> = key
+        .iter()
+        .map(|(e, _, _)| format!("CAST({} AS TEXT)", e))
+        .collect();
+    // the subquery keeps the column order, as does Json with serde_json's
+    // preserve_order feature
+    Ok(format!(
+        "SELECT (SELECT TO_JSON(c) FROM (SELECT {}) c), TO_JSON(ARRAY[{}])\n\
+         FROM {} t{}\n\
+         ORDER BY {}\n\
+         OFFSET {}\n\
+         LIMIT {}",
+        projected.join(", "),
+        values.join(", "),
+        quote_identifier(&relation.name),
+        condition,
+        order_by_key(&key),
+        skip,
+        limit
+    ))
+}
+
+// whether a row's key from the `i`th column on is at or past the cursor's,
+// with nulls last
+fn at_or_past(key: &[(String, String, Order)], i: usize) -> String {
+    let (e, data_type, order) = match key.get(i) {
+        Some(k) => k,
+        None => return "TRUE".to_owned(),
+    };
+    let value = format!("CAST(${} AS {})", i + 1, data_type);
+    let comparator = match order {
+        Order::Ascending => ">",
+        Order::Descending => "<",
+    };
+    format!(
+        "({} {} {} OR ({} IS NULL AND {} IS NOT NULL) \
+         OR ({} IS NOT DISTINCT FROM {} AND {}))",
+        e,
+        comparator,
+        value,
+        e,
+        value,
+        e,
+        value,
+        at_or_past(key, i + 1)
+    )
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SampleStrategy {
+    // the first rows in the order rows are paged in
+    Head,
+    // rows ordered by a seeded hash of their contents and copy number
+    Uniform,
+    // uniform within each value of a column, in proportion to its count
+    Stratified,
+}
+
+pub async fn sample_rows(
+    db: &Db,
+    relation: &Relation,
+    n: Option<i32>,
+    strategy: Option<SampleStrategy>,
+    seed: Option<i32>,
+    column: Option<String>,
+) -> GQLResult<Option<GQLJson<Json>>> {
+    // there's nothing to order or hash rows by, and no values to sample
+    if relation.columns.is_empty() {
+        return Ok(None);
+    }
+    let sql = sample_sql(
+        relation,
+        n,
+        strategy.unwrap_or(SampleStrategy::Head),
+        seed,
+        column.as_deref(),
+    )?;
+    // JSON_AGG is null without rows
+    let rows: Option<Json> = query_scalar(&sql).fetch_one(&db.data).await?;
+    Ok(rows.map(GQLJson))
+}
+
+// samples are deterministic for a given seed, since rows are hashed rather
+// than drawn with random() or TABLESAMPLE
+pub fn sample_sql(
+    relation: &Relation,
+    n: Option<i32>,
+    strategy: SampleStrategy,
+    seed: Option<i32>,
+    column: Option<&str>,
+) -> Result<String, Error> {
+    let n = match n {
+        Some(n) if n < 1 || n as usize > MAX_ROWS => {
+            return Err(Error::InvalidArguments(format!(
+                "n must be 1 to {}: {}",
+                MAX_ROWS, n
+            )))
+        }
+        Some(n) => n as usize,
+        None => DEFAULT_ROWS,
+    };
+    let from = format!("FROM {} p", quote_identifier(&relation.name));
+    let numbered = format!("FROM {}", numbered(relation));
+    let seed = match (strategy, seed) {
+        (SampleStrategy::Head, Some(_)) => {
+            return Err(Error::InvalidArguments(
+                "HEAD does not take a seed".to_owned(),
+            ))
+        }
+        (_, seed) => seed.unwrap_or(0),
+    };
+    let sample = match (strategy, column) {
+        (SampleStrategy::Stratified, Some(c)) => {
+            relation.column(c)?;
+            let c = quote_identifier(c);
+            // ordering by each row's relative rank within its stratum takes
+            // one row from every stratum before a second from any, then
+            // keeps strata in proportion
+            let hash = row_hash("p", seed);
+            format!(
+                "SELECT {}\n\
+                 FROM (\n\
+                 SELECT p.*, {} AS \"__hash\", \
+                 ROW_NUMBER() OVER (PARTITION BY p.{} ORDER BY {}) \
+                 AS \"__rank\", \
+                 COUNT(*) OVER (PARTITION BY p.{}) AS \"__stratum\"\n\
+                 {}\n\
+                 ) p\n\
+                 ORDER BY CAST(p.\"__rank\" - 1 AS DOUBLE PRECISION) \
+                 / p.\"__stratum\", p.\"__hash\"\n\
+                 LIMIT {}",
+                columns("p", relation),
+                hash,
+                c,
+                hash,
+                c,
+                numbered,
+                n
+            )
+        }
+        (SampleStrategy::Stratified, None) => {
+            return Err(Error::InvalidArguments(
+                "STRATIFIED requires a column".to_owned(),
+            ))
+        }
+        (_, Some(_)) => {
+            return Err(Error::InvalidArguments(
+                "only STRATIFIED takes a column".to_owned(),
+            ))
+        }
+        (SampleStrategy::Head, None) => {
+            // in the order rows are paged in, since views need not keep an
+            // order of their own
+            let key = sort_key("p", relation, &[])?;
+            format!(
+                "SELECT p.*\n{}\nORDER BY {}\nLIMIT {}",
+                from,
+                order_by_key(&key),
+                n
+            )
+        }
+        (SampleStrategy::Uniform, None) => format!(
+            "SELECT {}\n{}\nORDER BY {}\nLIMIT {}",
+            columns("p", relation),
+            numbered,
+            row_hash("p", seed),
+            n
+        ),
+    };
+    Ok(format!("SELECT JSON_AGG(t)\nFROM (\n{}\n) t", sample))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::fixtures;
+
+    fn relation() -> Relation {
+        fixtures::relation(
+            "RGF0YXZpZXc6cGFyZW50",
+            "dataview_parent",
+            &[("x", "integer"), ("tags", "json"), ("y", "text")],
+        )
+    }
+
+    #[test]
+    fn cursor_round_trip() -> Result<(), Error> {
+        let cursor = RowCursor {
+            values: vec![Some("iris".to_owned()), None],
+            skip: 2,
+        };
+        assert_eq!(RowCursor::decode_cursor(&cursor.encode_cursor())?, cursor);
+        assert!(RowCursor::decode_cursor("not a cursor").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rows_sql_pages() -> Result<(), Error> {
+        let relation = relation();
+        let order_by = [Sort {
+            column: "y".to_owned(),
+            order: Order::Descending,
+        }];
+        let columns = ["y".to_owned(), "tags".to_owned()];
+        let after = RowCursor {
+            values: vec![Some("b".to_owned()), Some("1".to_owned()), None],
+            skip: 2,
+        };
+        assert_eq!(
+            rows_sql(&relation, 11, Some(&after), &order_by, Some(&columns))?,
+            "SELECT (SELECT TO_JSON(c) FROM (SELECT t.\"y\", t.\"tags\") c), \
+             TO_JSON(ARRAY[CAST(t.\"y\" AS TEXT), CAST(t.\"x\" AS TEXT), \
+             CAST(CAST(t.\"tags\" AS TEXT) AS TEXT)])\n\
+             FROM \"dataview_parent\" t\n\
+             WHERE (t.\"y\" < CAST($1 AS TEXT) \
+             OR (t.\"y\" IS NULL AND CAST($1 AS TEXT) IS NOT NULL) \
+             OR (t.\"y\" IS NOT DISTINCT FROM CAST($1 AS TEXT) \
+             AND (t.\"x\" > CAST($2 AS INTEGER) \
+             OR (t.\"x\" IS NULL AND CAST($2 AS INTEGER) IS NOT NULL) \
+             OR (t.\"x\" IS NOT DISTINCT FROM CAST($2 AS INTEGER) \
+             AND (CAST(t.\"tags\" AS TEXT) > CAST($3 AS TEXT) \
+             OR (CAST(t.\"tags\" AS TEXT) IS NULL \
+             AND CAST($3 AS TEXT) IS NOT NULL) \
+             OR (CAST(t.\"tags\" AS TEXT) IS NOT DISTINCT FROM \
+             CAST($3 AS TEXT) AND TRUE))))))\n\
+             ORDER BY t.\"y\" DESC NULLS LAST, t.\"x\" ASC NULLS LAST, \
+             CAST(t.\"tags\" AS TEXT) ASC NULLS LAST\n\
+             OFFSET 2\n\
+             LIMIT 11"
+        );
+        let stale = RowCursor {
+            values: vec![None],
+            skip: 1,
+        };
+        let res = rows_sql(&relation, 11, Some(&stale), &[], None);
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        let unknown = ["z".to_owned()];
+        let res = rows_sql(&relation, 11, None, &[], Some(&unknown));
+        assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn sample_sql_strategies() -> Result<(), Error> {
+        let relation = relation();
+        assert_eq!(
+            sample_sql(&relation, None, SampleStrategy::Head, None, None)?,
+            "SELECT JSON_AGG(t)\nFROM (\n\
+             SELECT p.*\nFROM \"dataview_parent\" p\n\
+             ORDER BY p.\"x\" ASC NULLS LAST, p.\"y\" ASC NULLS LAST, \
+             CAST(p.\"tags\" AS TEXT) ASC NULLS LAST\n\
+             LIMIT 100\n\
+             ) t"
+        );
+        assert_eq!(
+            sample_sql(
+                &relation,
+                Some(5),
+                SampleStrategy::Uniform,
+                None,
+                None
+            )?,
+            "SELECT JSON_AGG(t)\nFROM (\n\
+             SELECT p.\"x\", p.\"tags\", p.\"y\"\n\
+             FROM (SELECT p.*, ROW_NUMBER() OVER \
+             (PARTITION BY CAST(p AS TEXT)) AS \"__copy\" \
+             FROM \"dataview_parent\" p) p\n\
+             ORDER BY hashtextextended(CAST(p AS TEXT), 0)\nLIMIT 5\n\
+             ) t"
+        );
+        assert_eq!(
+            sample_sql(
+                &relation,
+                Some(5),
+                SampleStrategy::Stratified,
+                Some(7),
+                Some("y")
+            )?,
+            "SELECT JSON_AGG(t)\nFROM (\n\
+             SELECT p.\"x\", p.\"tags\", p.\"y\"\n\
+             FROM (\n\
+             SELECT p.*, hashtextextended(CAST(p AS TEXT), 7) AS \"__hash\", \
+             ROW_NUMBER() OVER (PARTITION BY p.\"y\" \
+             ORDER BY hashtextextended(CAST(p AS TEXT), 7)) AS \"__rank\", \
+             COUNT(*) OVER (PARTITION BY p.\"y\") AS \"__stratum\"\n\
+             FROM (SELECT p.*, ROW_NUMBER() OVER \
+             (PARTITION BY CAST(p AS TEXT)) AS \"__copy\" \
+             FROM \"dataview_parent\" p) p\n\
+             ) p\n\
+             ORDER BY CAST(p.\"__rank\" - 1 AS DOUBLE PRECISION) \
+             / p.\"__stratum\", p.\"__hash\"\n\
+             LIMIT 5\n\
+             ) t"
+        );
+        let invalid = [
+            sample_sql(&relation, Some(0), SampleStrategy::Head, None, None),
+            sample_sql(&relation, None, SampleStrategy::Head, Some(1), None),
+            sample_sql(&relation, None, SampleStrategy::Stratified, None, None),
+            sample_sql(
+                &relation,
+                None,
+                SampleStrategy::Uniform,
+                None,
+                Some("y"),
+            ),
+            sample_sql(
+                &relation,
+                None,
+                SampleStrategy::Stratified,
+                None,
+                Some("z"),
+            ),
+        ];
+        for res in invalid.iter() {
+            assert!(matches!(res, Err(Error::InvalidArguments(_))));
+        }
+        Ok(())
+    }
+}
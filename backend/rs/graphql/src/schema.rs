@@ -0,0 +1,172 @@
+use crate::{
+    operations::{quote_identifier, DataType},
+    types::{Column, Db, SemanticType},
+};
+use sqlx::{query_as, query_scalar, Result as SQLxResult};
+
+// schemas are read on most screens, so semantic types are inferred from the
+// first rows rather than the whole relation
+pub const SAMPLE_ROWS: usize = 10000;
+// text columns with at most this many distinct values are categorical
+pub const MAX_CATEGORIES: i64 = 50;
+
+// the counts semantic types are inferred from, over the sampled rows
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Counts {
+    pub non_null: i64,
+    pub distinct: i64,
+}
+
+pub async fn schema(db: &Db, relation: &str) -> SQLxResult<Vec<Column>> {
+    let columns: Vec<(String, String, i32, bool)> = query_as(
+        r#"
+        SELECT
+            column_name,
+            data_type,
+            CAST(ordinal_position AS INTEGER),
+            is_nullable = 'YES'
+        FROM information_schema.columns
+        WHERE table_schema = 'public'
+        AND table_name = $1
+        ORDER BY ordinal_position
+        "#,
+    )
+    .bind(relation)
+    .fetch_all(&db.data)
+    .await?;
+    let counted: Vec<&str> = columns
+        .iter()
+        .filter(|(_, data_type, _, _)| is_counted(data_type))
+        .map(|(name, _, _, _)| name.as_str())
+        .collect();
+    let mut counts = match counted.is_empty() {
+        true => vec![],
+        false => query_scalar::<_, Vec<i64>>(&counts_sql(relation, &counted))
+            .fetch_one(&db.data)
+            .await?
+            .chunks(2)
+            .map(|c| Counts {
+                non_null: c[0],
+                distinct: c[1],
+            })
+            .collect(),
+    }
+    .into_iter();
+    Ok(columns
+        .into_iter()
+        .map(|(column_name, data_type, ordinal_position, is_nullable)| {
+            let counts = match is_counted(&data_type) {
+                true => counts.next(),
+                false => None,
+            };
+            Column {
+                semantic_type: semantic_type(&column_name, &data_type, counts),
+                column_name,
+                data_type,
+                ordinal_position,
+                is_nullable,
+            }
+        })
+        .collect())
+}
+
+// integer and text columns, which could be identifiers or categories
+fn is_counted(data_type: &str) -> bool {
+    matches!(data_type, "smallint" | "integer" | "bigint")
+        || DataType::of(data_type) == DataType::Text
+}
+
+pub fn counts_sql(relation: &str, columns: &[&str]) -> String {
+    let counts: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            let c = quote_identifier(c);
+            format!("COUNT({}), COUNT(DISTINCT {})", c, c)
+        })
+        .collect();
+    format!(
+        "SELECT ARRAY[{}]\nFROM (SELECT * FROM {} LIMIT {}) s",
+        counts.join(", "),
+        quote_identifier(relation),
+        SAMPLE_ROWS
+    )
+}
+
+pub fn semantic_type(
+    column_name: &str,
+    data_type: &str,
+    counts: Option<Counts>,
+) -> SemanticType {
+    let name = column_name.to_lowercase();
+    let named_id =
+        name == "id" || name.ends_with("_id") || name.ends_with("uuid");
+    // columns without values aren't unique, so empty relations have no
+    // identifiers besides those named or typed as such
+    let unique = match counts {
+        Some(c) => c.non_null > 0 && c.distinct == c.non_null,
+        None => false,
+    };
+    match DataType::of(data_type) {
+        DataType::Boolean => SemanticType::Boolean,
+        DataType::Date | DataType::Timestamp => SemanticType::Datetime,
+        DataType::Numeric if named_id && unique => SemanticType::Identifier,
+        DataType::Numeric => SemanticType::Numeric,
+        DataType::Text => match counts {
+            _ if named_id => SemanticType::Identifier,
+            Some(c) if c.non_null == 0 => SemanticType::Text,
+            Some(c) if c.distinct <= MAX_CATEGORIES && !unique => {
+                SemanticType::Categorical
+            }
+            Some(c) if unique && c.non_null > MAX_CATEGORIES => {
+                SemanticType::Identifier
+            }
+            _ => SemanticType::Text,
+        },
+        DataType::Other if data_type == "uuid" => SemanticType::Identifier,
+        DataType::Other => SemanticType::Text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semantic_types() {
+        let counts = |non_null, distinct| Some(Counts { non_null, distinct });
+        let cases = [
+            ("flag", "boolean", None, SemanticType::Boolean),
+            ("day", "date", None, SemanticType::Datetime),
+            ("x", "double precision", None, SemanticType::Numeric),
+            ("id", "integer", counts(100, 100), SemanticType::Identifier),
+            ("id", "integer", counts(100, 90), SemanticType::Numeric),
+            ("id", "integer", counts(0, 0), SemanticType::Numeric),
+            ("n", "bigint", counts(100, 100), SemanticType::Numeric),
+            ("species", "text", counts(150, 3), SemanticType::Categorical),
+            ("note", "text", counts(150, 120), SemanticType::Text),
+            ("note", "text", counts(0, 0), SemanticType::Text),
+            ("email", "text", counts(150, 150), SemanticType::Identifier),
+            ("user_id", "text", counts(150, 3), SemanticType::Identifier),
+            ("key", "uuid", None, SemanticType::Identifier),
+            ("tags", "json", None, SemanticType::Text),
+        ];
+        for (name, data_type, counts, expected) in cases.iter() {
+            assert_eq!(
+                semantic_type(name, data_type, *counts),
+                *expected,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn counts_sql_samples() {
+        assert_eq!(
+            counts_sql("dataset_x", &["id", "species"]),
+            "SELECT ARRAY[COUNT(\"id\"), COUNT(DISTINCT \"id\"), \
+             COUNT(\"species\"), COUNT(DISTINCT \"species\")]\n\
+             FROM (SELECT * FROM \"dataset_x\" LIMIT 10000) s"
+        );
+    }
+}
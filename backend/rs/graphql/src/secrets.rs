@@ -9,6 +9,10 @@ use std::env;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Secrets {
     pub data_db_url: String,
+    // a PEM certificate that the data db's is signed by, for CAs that aren't
+    // publicly trusted, like RDS's
+    #[serde(default)]
+    pub data_db_ca_cert: Option<String>,
     pub meta_db_url: String,
     pub google_oauth2_client_id_android: String,
     pub google_oauth2_client_id_ios: String,
@@ -21,6 +25,7 @@ impl Secrets {
     pub fn local() -> Self {
         Self {
             data_db_url: "postgres://postgres@localhost/motoko_data".to_owned(),
+            data_db_ca_cert: None,
             meta_db_url: "postgres://postgres@localhost/motoko_meta".to_owned(),
             google_oauth2_client_id_android: "dummy".to_owned(),
             google_oauth2_client_id_ios: "dummy".to_owned(),
@@ -37,6 +42,7 @@ impl Secrets {
         }
         Self {
             data_db_url: format!("postgres://postgres@{}:5432/motoko_data", ip),
+            data_db_ca_cert: None,
             meta_db_url: format!("postgres://postgres@{}:5432/motoko_meta", ip),
             google_oauth2_client_id_android: "dummy".to_owned(),
             google_oauth2_client_id_ios: "dummy".to_owned(),
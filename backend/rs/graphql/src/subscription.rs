@@ -0,0 +1,195 @@
+use crate::{
+    auth::user_from_authorization_header,
+    gql::{data, graphql_id_to_uuid, model_keys, node_id},
+    models::{
+        Analysis, Dataset, Dataview, Model, Plot, Statistic, Status, User,
+    },
+    Db, Error, Json,
+};
+use async_graphql::{
+    Context, Error as GQLError, Result as GQLResult, SimpleObject, ID,
+};
+use futures::{future, stream, Stream, StreamExt};
+use serde::Deserialize;
+use sqlx::postgres::{PgListener, PgPool};
+use std::sync::Arc;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    Mutex,
+};
+use tokio_compat_02::FutureExt;
+use uuid::Uuid;
+
+pub const STATUS_CHANNEL: &'static str = "status_changes";
+
+// notifications buffered for each subscriber before it lags
+const STATUS_CAPACITY: usize = 256;
+
+pub struct Subscription;
+
+/// The `connection_init` payload of a websocket connection, which carries
+/// the `Authorization` header for clients that cannot set one on the upgrade
+/// request.
+pub struct ConnectionInit(pub Json);
+
+#[derive(Debug, Clone, Eq, PartialEq, SimpleObject)]
+pub struct StatusChange {
+    pub id: ID,
+    pub status: Status,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Notification {
+    table: String,
+    uuid: Uuid,
+    analysis_uuid: Option<Uuid>,
+    status: Status,
+}
+
+impl From<Notification> for StatusChange {
+    fn from(n: Notification) -> Self {
+        let model = match n.table.as_str() {
+            "datasets" => "Dataset",
+            "dataviews" => "Dataview",
+            "statistics" => "Statistic",
+            "plots" => "Plot",
+            _ => "Model",
+        };
+        Self {
+            id: node_id(model, &n.uuid),
+            status: n.status,
+        }
+    }
+}
+
+#[async_graphql::Subscription]
+impl Subscription {
+    async fn status_changes(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+    ) -> GQLResult<impl Stream<Item = StatusChange>> {
+        let d = data(ctx)?;
+        let user = subscriber(ctx).await?;
+        let mkeys = model_keys(&id)?;
+        let uuid = graphql_id_to_uuid(&id)?;
+        let role = match mkeys.model.as_str() {
+            "Dataset" => Dataset::role(&d.db, &uuid, &user.uuid).await,
+            "Dataview" => Dataview::role(&d.db, &uuid, &user.uuid).await,
+            "Statistic" => Statistic::role(&d.db, &uuid, &user.uuid).await,
+            "Plot" => Plot::role(&d.db, &uuid, &user.uuid).await,
+            "Model" => Model::role(&d.db, &uuid, &user.uuid).await,
+            _ => return Err(Error::UnsupportedOperation.into()),
+        };
+        role.map_err(|_| -> GQLError { Error::InvalidPermissions.into() })?;
+        Ok(d.status_changes
+            .subscribe()
+            .await?
+            .filter(move |n| future::ready(n.uuid == uuid))
+            .map(StatusChange::from))
+    }
+
+    async fn analysis_status_changes(
+        &self,
+        ctx: &Context<'_>,
+        analysis_id: ID,
+    ) -> GQLResult<impl Stream<Item = StatusChange>> {
+        let d = data(ctx)?;
+        let user = subscriber(ctx).await?;
+        let analysis_uuid = graphql_id_to_uuid(&analysis_id)?;
+        Analysis::role(&d.db, &analysis_uuid, &user.uuid)
+            .await
+            .map_err(|_| -> GQLError { Error::InvalidPermissions.into() })?;
+        Ok(d.status_changes
+            .subscribe()
+            .await?
+            .filter(move |n| {
+                future::ready(n.analysis_uuid == Some(analysis_uuid))
+            })
+            .map(StatusChange::from))
+    }
+}
+
+async fn subscriber(ctx: &Context<'_>) -> GQLResult<User> {
+    let d = data(ctx)?;
+    if let Some(user) = &d.user {
+        return Ok(user.clone());
+    }
+    let auth_header = ctx.data_opt::<ConnectionInit>().and_then(|init| {
+        init.0
+            .get("Authorization")
+            .or(init.0.get("authorization"))
+            .and_then(|v| v.as_str())
+    });
+    user_from_authorization_header(auth_header, &d.auth.jwt_secret, &d.db)
+        .await
+        .ok_or(Error::InvalidPermissions.into())
+}
+
+/// Fans the status notifications out to every subscription from one
+/// listener per process, which is started by the first subscription.
+#[derive(Clone)]
+pub struct StatusChanges {
+    meta: PgPool,
+    sender: Arc<Mutex<Option<broadcast::Sender<Notification>>>>,
+}
+
+impl StatusChanges {
+    pub fn new(db: &Db) -> Self {
+        Self {
+            meta: db.meta.clone(),
+            sender: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    async fn subscribe(&self) -> GQLResult<impl Stream<Item = Notification>> {
+        let mut sender = self.sender.lock().await;
+        let receiver = match &*sender {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let mut listener =
+                    PgListener::connect_with(&self.meta).compat().await?;
+                listener.listen(STATUS_CHANNEL).compat().await?;
+                let (tx, rx) = broadcast::channel(STATUS_CAPACITY);
+                *sender = Some(tx.clone());
+                tokio::spawn(listen(listener, tx, self.sender.clone()));
+                rx
+            }
+        };
+        // a subscriber that lags skips the changes it missed
+        Ok(stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(n) => return Some((n, receiver)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }))
+    }
+}
+
+// sqlx runs on tokio 0.2, so each receive is wrapped individually; if the
+// listener fails to reconnect, the subscriptions end and the next one starts
+// a new listener
+async fn listen(
+    mut listener: PgListener,
+    tx: broadcast::Sender<Notification>,
+    sender: Arc<Mutex<Option<broadcast::Sender<Notification>>>>,
+) {
+    loop {
+        match listener.recv().compat().await {
+            Ok(n) => {
+                if let Ok(n) = serde_json::from_str(n.payload()) {
+                    // only fails when there are no subscriptions
+                    let _ = tx.send(n);
+                }
+            }
+            Err(e) => {
+                eprintln!("status listener failed: {}", e);
+                break;
+            }
+        }
+    }
+    *sender.lock().await = None;
+}
@@ -1,5 +1,8 @@
-use crate::models::{Operation, PlotType, StatisticType};
-use async_graphql::SimpleObject;
+use crate::{
+    ingest::format::DatasetFormat,
+    models::{PlotType, StatisticType},
+};
+use async_graphql::{Enum, SimpleObject};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -23,15 +26,9 @@ pub struct ModelKeys {
 pub struct UploadDatasetPayload {
     pub uri: String,
     pub uuid: Uuid,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct CreateDataviewPayload {
-    pub parent_view: String,
-    pub view: String,
-    pub uuid: Uuid,
-    pub operation: Operation,
-    pub args: Json,
+    // detected from the file when not given
+    #[serde(default)]
+    pub format: Option<DatasetFormat>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -69,3 +66,26 @@ pub struct ColumnDataType {
     pub column_name: String,
     pub data_type: String,
 }
+
+// a column as the schema fields describe it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct Column {
+    pub column_name: String,
+    pub data_type: String,
+    // 1-based, in the relation's column order
+    pub ordinal_position: i32,
+    pub is_nullable: bool,
+    pub semantic_type: SemanticType,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SemanticType {
+    Numeric,
+    Categorical,
+    Datetime,
+    Boolean,
+    Text,
+    Identifier,
+}
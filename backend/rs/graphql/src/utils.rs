@@ -1,4 +1,4 @@
-use crate::GenericError;
+use crate::{ColumnDataType, Db, Error, GenericError, Json};
 use bytes::Bytes;
 use rusoto_core::Region;
 use rusoto_credential::AwsCredentials;
@@ -6,6 +6,7 @@ use rusoto_s3::{
     util::PreSignedRequest, util::PreSignedRequestOption, GetObjectRequest,
 };
 use serde::Serialize;
+use sqlx::{query_as, Result as SQLxResult};
 use std::{env, str, time::Duration};
 use uuid::Uuid;
 
@@ -13,6 +14,24 @@ pub fn as_bytes<T: Serialize>(v: &T) -> Result<Bytes, GenericError> {
     Ok(Bytes::from(serde_json::to_vec(v)?))
 }
 
+pub async fn columns(
+    db: &Db,
+    relation: &str,
+) -> SQLxResult<Vec<ColumnDataType>> {
+    query_as(
+        r#"
+        SELECT column_name, data_type
+        FROM information_schema.columns
+        WHERE table_schema = 'public'
+        AND table_name = $1
+        ORDER BY ordinal_position
+        "#,
+    )
+    .bind(relation)
+    .fetch_all(&db.data)
+    .await
+}
+
 pub fn dataset_table_name(uuid: &Uuid) -> String {
     let uuid_str = str::replace(&uuid.to_string(), "-", "_");
     format!("dataset_{}", uuid_str)
@@ -23,6 +42,11 @@ pub fn dataview_view_name(uuid: &Uuid) -> String {
     format!("dataview_{}", uuid_str)
 }
 
+// the materialized copy a dataview's view selects from once materialized
+pub fn materialized_view_name(uuid: &Uuid) -> String {
+    format!("materialized_{}", dataview_view_name(uuid))
+}
+
 pub fn get_presigned_url(
     region: &Region,
     bucket: &str,
@@ -43,6 +67,25 @@ pub fn get_presigned_url(
     req.get_presigned_url(region, aws_credentials, &opt)
 }
 
+// args are stored as JSON encoded strings, which clients and the lambdas decode
+pub fn json_string<T: Serialize>(v: &T) -> Result<Json, Error> {
+    serde_json::to_string(v)
+        .map(Json::String)
+        .map_err(|_| Error::Serde)
+}
+
+// exactly one field of a oneOf style input object must be set
+pub fn one_of<T>(input: &str, fields: Vec<Option<T>>) -> Result<T, Error> {
+    let mut set = fields.into_iter().flatten();
+    match (set.next(), set.next()) {
+        (Some(v), None) => Ok(v),
+        _ => Err(Error::InvalidArguments(format!(
+            "exactly one field of {} must be set",
+            input
+        ))),
+    }
+}
+
 pub fn run_mode() -> String {
     env::var("RUN_MODE").expect("RUN_MODE not defined")
 }
@@ -76,11 +76,19 @@ class _ContentState extends State<Content> {
         builder: (QueryResult result,
             {VoidCallback refetch, FetchMore fetchMore}) {
           var values = [];
+          Map pageInfo;
           if (!result.loading) {
             if (result.hasException) {
               showErrorDialog(context, result.exception.toString());
             } else {
-              values = (result.data as Map).values.first;
+              // most list queries return connections of their nodes
+              final list = (result.data as Map).values.first;
+              values = list is Map
+                  ? list['edges'].map((e) => e['node']).toList()
+                  : list;
+              if (list is Map) {
+                pageInfo = list['pageInfo'];
+              }
               if (values.isNotEmpty) {
                 if (widget.orderBy != null) {
                   values.sort(
@@ -237,6 +245,25 @@ class _ContentState extends State<Content> {
             _refetchDelaySeconds = widget.minRefetchDelaySeconds;
           }
 
+          // connections are paged, so the next page is fetched after the
+          // current page's last cursor and appended to its edges
+          Widget loadMore;
+          if (pageInfo != null && pageInfo['hasNextPage'] == true) {
+            loadMore = Center(
+                child: FlatButton.icon(
+                    icon: Icon(Icons.expand_more),
+                    label: Text('load more'),
+                    onPressed: () => fetchMore(FetchMoreOptions(
+                        variables: {'after': pageInfo['endCursor']},
+                        updateQuery: (previous, next) {
+                          final key = (next as Map).keys.first;
+                          final connection = Map.from(next[key]);
+                          connection['edges'] = previous[key]['edges'] +
+                              next[key]['edges'];
+                          return Map.from(next)..[key] = connection;
+                        }))));
+          }
+
           String searchableText(dynamic v) {
             var s = v.child.children[0].title.data;
             if (v.child.children[0].subtitle != null) {
@@ -260,6 +287,7 @@ class _ContentState extends State<Content> {
                     items: items,
                     searchableText: searchableText,
                     loading: result.loading,
+                    footer: loadMore,
                     bottomPadding: 150),
                 preview: Expanded(
                     child: _selectedId == null
@@ -271,7 +299,8 @@ class _ContentState extends State<Content> {
           return SearchableList(
               items: items,
               searchableText: searchableText,
-              loading: result.loading);
+              loading: result.loading,
+              footer: loadMore);
         });
   }
 }
@@ -5,10 +5,13 @@ class SearchableList extends StatefulWidget {
       {@required this.items,
       @required this.searchableText,
       this.loading = false,
+      this.footer,
       this.bottomPadding = 0});
   final List<Widget> items;
   final String Function(dynamic v) searchableText;
   final bool loading;
+  // shown after the items, whether or not they're filtered
+  final Widget footer;
   final double bottomPadding;
   @override
   _SearchableListState createState() => _SearchableListState();
@@ -63,7 +66,10 @@ class _SearchableListState extends State<SearchableList> {
                   child: ListView(
                       padding: EdgeInsets.fromLTRB(
                           10, 0, 10, 85 + widget.bottomPadding),
-                      children: hasFiltered ? filtered : widget.items)),
+                      children: [
+                        ...(hasFiltered ? filtered : widget.items),
+                        if (widget.footer != null) widget.footer,
+                      ])),
         ]));
   }
 }
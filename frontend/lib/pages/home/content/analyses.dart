@@ -8,20 +8,28 @@ class Analyses extends StatelessWidget {
   Analyses(this.projectId);
   final String projectId;
   final String query = '''
-    query Analyses(\$projectId: ID!) {
-      analyses(projectId: \$projectId) {
-        __typename
-        id
-        createdAt
-        updatedAt
-        name
-        dataset {
-          __typename
-          id
+    query Analyses(\$projectId: ID!, \$after: String) {
+      analyses(projectId: \$projectId, after: \$after) {
+        edges {
+          node {
+            __typename
+            id
+            createdAt
+            updatedAt
+            name
+            dataset {
+              __typename
+              id
+            }
+            dataview {
+              __typename
+              id
+            }
+          }
         }
-        dataview {
-          __typename
-          id
+        pageInfo {
+          hasNextPage
+          endCursor
         }
       }
     }
@@ -71,12 +79,16 @@ class CreateAnalysisForm extends StatefulWidget {
   final String datasets = '''
     query Datasets(\$projectId: ID!) {
       datasets(projectId: \$projectId) {
-        __typename
-        id
-        createdAt
-        updatedAt
-        name
-        status
+        edges {
+          node {
+            __typename
+            id
+            createdAt
+            updatedAt
+            name
+            status
+          }
+        }
       }
     }
   ''';
@@ -117,7 +129,10 @@ class _CreateAnalysisFormState extends State<CreateAnalysisForm> {
                   validator: FormBuilderValidators.required(context),
                   decoration: InputDecoration(
                       hintText: 'dataset', labelText: 'dataset'),
-                  items: (result.loading ? [] : result.data['datasets'])
+                  items: (result.loading
+                          ? []
+                          : result.data['datasets']['edges']
+                              .map((e) => e['node']))
                       .map<DropdownMenuItem<String>>((v) => DropdownMenuItem(
                           value: v['id'].toString(),
                           child: Text(v['name'].toString())))
@@ -12,37 +12,43 @@ class Analysis extends StatelessWidget {
   Analysis(this.id);
   final String id;
   final query = '''
-    query Dataviews(\$analysisId: ID!) {
-      dataviews(analysisId: \$analysisId) {
-        __typename
-        id
-        createdAt
-        updatedAt
-        analysis {
-          __typename
-          id
-          dataset {
+    query Dataviews(\$analysisId: ID!, \$after: String) {
+      dataviews(analysisId: \$analysisId, after: \$after) {
+        edges {
+          node {
             __typename
             id
-            name
+            createdAt
+            updatedAt
+            analysis {
+              __typename
+              id
+              dataset {
+                __typename
+                id
+                name
+              }
+            }
+            operation
+            args
+            status
+            nRows
           }
         }
-        operation
-        args
-        status
-        nRows
+        pageInfo {
+          hasNextPage
+          endCursor
+        }
       }
     }
   ''';
   final mutation = '''
     mutation CreateDataview(
       \$analysisId: ID!,
-      \$operation: Operation!,
-      \$args: JSON!,
+      \$args: OperationArgs!,
     ) {
       createDataview(
         analysisId: \$analysisId,
-        operation: \$operation,
         args: \$args,
       ) {
         __typename
@@ -124,8 +130,12 @@ class Analysis extends StatelessWidget {
           CreateDataviewForm(setFormState, id),
       createMutation: mutation,
       createFieldsToVariables: (fields) {
-        var op = fields.remove('operation').toString().toUpperCase();
-        return {'analysisId': id, 'operation': op, 'args': jsonEncode(fields)};
+        // the args set only the field of their operation
+        var op = fields.remove('operation').toString().toLowerCase();
+        return {
+          'analysisId': id,
+          'args': {op: fields},
+        };
       },
       onCreate: (_v, _c, refetch) => refetch(),
       createOnLastFailureMessage:
@@ -9,15 +9,23 @@ class Datasets extends StatelessWidget {
   final String projectId;
   final String name = 'Datasets';
   final query = '''
-    query Datasets(\$projectId: ID!) {
-      datasets(projectId: \$projectId) {
-        __typename
-        id
-        createdAt
-        updatedAt
-        name
-        status
-        nRows
+    query Datasets(\$projectId: ID!, \$after: String) {
+      datasets(projectId: \$projectId, after: \$after) {
+        edges {
+          node {
+            __typename
+            id
+            createdAt
+            updatedAt
+            name
+            status
+            nRows
+          }
+        }
+        pageInfo {
+          hasNextPage
+          endCursor
+        }
       }
     }
   ''';
@@ -23,7 +23,7 @@ class _CreateDataviewFilterFormState extends State<CreateDataviewFilterForm> {
   Widget build(BuildContext context) {
     final color = Theme.of(context).colorScheme.secondary;
     final defaultFilter =
-        () => Filter(widget.schema[0]['columnName'].toString(), '=', null);
+        () => Filter(widget.schema[0]['columnName'].toString(), 'EQ', null);
     if (_filters.isEmpty) {
       setState(() => _filters = [defaultFilter()]);
     }
@@ -74,9 +74,10 @@ class _CreateDataviewFilterFormState extends State<CreateDataviewFilterForm> {
                                     IntrinsicWidth(
                                         child: DropdownButtonFormField<String>(
                                       value: _filters[idx].comparator,
-                                      items: ['=', '>', '>=', '<', '<=']
+                                      items: comparators.entries
                                           .map((v) => DropdownMenuItem<String>(
-                                              value: v, child: Text(v)))
+                                              value: v.key,
+                                              child: Text(v.value)))
                                           .toList(),
                                       onChanged: (String v) => setState(
                                           () => _filters[idx].comparator = v),
@@ -137,6 +138,16 @@ class _CreateDataviewFilterFormState extends State<CreateDataviewFilterForm> {
   }
 }
 
+// the comparators the form offers, by their GraphQL names
+const comparators = {
+  'EQ': '=',
+  'NE': '!=',
+  'GT': '>',
+  'GE': '>=',
+  'LT': '<',
+  'LE': '<=',
+};
+
 class Filter {
   Filter(this.column, this.comparator, this.value);
   String column;
@@ -144,12 +155,15 @@ class Filter {
   String value;
   @override
   String toString() {
-    return '$column $comparator $value';
+    return '$column ${comparators[comparator]} $value';
   }
 
   Filter.fromJson(Map<String, dynamic> json)
       : column = json['column'],
-        comparator = json['comparator'],
+        // stored args have the comparators' symbols
+        comparator = comparators.keys.firstWhere(
+            (k) => comparators[k] == json['comparator'],
+            orElse: () => json['comparator']),
         value = json['value'];
   Map<String, dynamic> toJson() {
     return {
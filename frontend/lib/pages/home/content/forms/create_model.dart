@@ -17,6 +17,7 @@ class CreateModelForm extends StatefulWidget {
           schema {
             columnName
             dataType
+            semanticType
           }
         }
       }
@@ -42,7 +43,10 @@ class _CreateModelFormState extends State<CreateModelForm> {
           if (result.hasException) {
             showErrorDialog(context, result.exception.toString());
           } else if (!result.loading) {
-            schema = result.data['node']['schema'] ?? [];
+            // identifiers are unique per row, so they are never useful to fit
+            schema = (result.data['node']['schema'] ?? [])
+                .where((v) => v['semanticType'] != 'IDENTIFIER')
+                .toList();
           }
           return FormBuilder(
               key: _formKey,
@@ -1,5 +1,4 @@
 import '../../../../common/dialogs.dart';
-import '../../../../common/utils.dart';
 import 'package:flutter/material.dart';
 import 'package:flutter_form_builder/flutter_form_builder.dart';
 import 'package:graphql_flutter/graphql_flutter.dart';
@@ -17,6 +16,7 @@ class CreatePlotForm extends StatefulWidget {
           schema {
             columnName
             dataType
+            semanticType
           }
         }
       }
@@ -82,9 +82,9 @@ class _CreatePlotFormState extends State<CreatePlotForm> {
                         .where((v) {
                           if (['Histogram', 'Line', 'Scatter', 'Smooth']
                               .contains(_plotType)) {
-                            return isNumericDataType(v['dataType']);
+                            return v['semanticType'] == 'NUMERIC';
                           } else if (_plotType == 'Bar') {
-                            return isCategoricalDataType(v['dataType']);
+                            return v['semanticType'] == 'CATEGORICAL';
                           }
                           return false;
                         })
@@ -101,7 +101,7 @@ class _CreatePlotFormState extends State<CreatePlotForm> {
                             hintText: 'y-axis', labelText: 'y-axis'),
                         validator: FormBuilderValidators.required(context),
                         items: schema
-                            .where((v) => isNumericDataType(v['dataType']))
+                            .where((v) => v['semanticType'] == 'NUMERIC')
                             .map((v) => DropdownMenuItem(
                                 value: v['columnName'].toString(),
                                 child: Text(v['columnName'])))
@@ -115,7 +115,7 @@ class _CreatePlotFormState extends State<CreatePlotForm> {
                             hintText: '[color]', labelText: '[color]'),
                         allowClear: true,
                         items: schema
-                            .where((v) => isCategoricalDataType(v['dataType']))
+                            .where((v) => v['semanticType'] == 'CATEGORICAL')
                             .map((v) => DropdownMenuItem(
                                 value: v['columnName'].toString(),
                                 child: Text(v['columnName'])))
@@ -129,11 +129,33 @@ class _CreatePlotFormState extends State<CreatePlotForm> {
                             hintText: '[shape]', labelText: '[shape]'),
                         allowClear: true,
                         items: schema
-                            .where((v) => isCategoricalDataType(v['dataType']))
+                            .where((v) => v['semanticType'] == 'CATEGORICAL')
                             .map((v) => DropdownMenuItem(
                                 value: v['columnName'].toString(),
                                 child: Text(v['columnName'])))
                             .toList())),
+                FormBuilderDropdown<String>(
+                    name: 'facetX',
+                    decoration: InputDecoration(
+                        hintText: '[facet columns]', labelText: '[facet columns]'),
+                    allowClear: true,
+                    items: schema
+                        .where((v) => v['semanticType'] == 'CATEGORICAL')
+                        .map((v) => DropdownMenuItem(
+                            value: v['columnName'].toString(),
+                            child: Text(v['columnName'])))
+                        .toList()),
+                FormBuilderDropdown<String>(
+                    name: 'facetY',
+                    decoration: InputDecoration(
+                        hintText: '[facet rows]', labelText: '[facet rows]'),
+                    allowClear: true,
+                    items: schema
+                        .where((v) => v['semanticType'] == 'CATEGORICAL')
+                        .map((v) => DropdownMenuItem(
+                            value: v['columnName'].toString(),
+                            child: Text(v['columnName'])))
+                        .toList()),
               ]));
         });
   }
@@ -7,19 +7,27 @@ class Models extends StatelessWidget {
   Models(this.dataviewId);
   final String dataviewId;
   final query = '''
-    query Models(\$dataviewId: ID!) {
-      models(dataviewId: \$dataviewId) {
-        __typename
-        id
-        createdAt
-        updatedAt
-        name
-        target
-        features
-        args
-        status
-        evaluation
-        decisions
+    query Models(\$dataviewId: ID!, \$after: String) {
+      models(dataviewId: \$dataviewId, after: \$after) {
+        edges {
+          node {
+            __typename
+            id
+            createdAt
+            updatedAt
+            name
+            target
+            features
+            args
+            status
+            evaluation
+            decisions
+          }
+        }
+        pageInfo {
+          hasNextPage
+          endCursor
+        }
       }
     }
   ''';
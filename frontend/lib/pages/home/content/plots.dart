@@ -8,16 +8,24 @@ class Plots extends StatelessWidget {
   Plots(this.dataviewId);
   final String dataviewId;
   final query = '''
-    query Plots(\$dataviewId: ID!) {
-      plots(dataviewId: \$dataviewId) {
-        __typename
-        id
-        createdAt
-        updatedAt
-        name
-        type
-        args
-        status
+    query Plots(\$dataviewId: ID!, \$after: String) {
+      plots(dataviewId: \$dataviewId, after: \$after) {
+        edges {
+          node {
+            __typename
+            id
+            createdAt
+            updatedAt
+            name
+            type
+            args
+            status
+          }
+        }
+        pageInfo {
+          hasNextPage
+          endCursor
+        }
       }
     }
   ''';
@@ -25,13 +33,11 @@ class Plots extends StatelessWidget {
     mutation CreatePlot(
       \$dataviewId: ID!,
       \$name: String!,
-      \$type: PlotType!,
-      \$args: JSON!,
+      \$args: PlotArgs!,
     ) {
       createPlot(
         dataviewId: \$dataviewId,
         name: \$name,
-        type: \$type,
         args: \$args,
       ) {
         __typename
@@ -69,12 +75,12 @@ class Plots extends StatelessWidget {
           CreatePlotForm(setFormState, dataviewId),
       createMutation: mutation,
       createFieldsToVariables: (fields) {
-        var type = fields.remove('type').toUpperCase();
+        var type = fields.remove('type').toLowerCase();
+        fields.removeWhere((_k, v) => v == null);
         return {
           'dataviewId': dataviewId,
           'name': fields['title'],
-          'type': type,
-          'args': jsonEncode(fields),
+          'args': {type: fields},
         };
       },
       onCreate: (_v, _c, refetch) => refetch(),
@@ -5,13 +5,21 @@ import 'package:flutter_form_builder/flutter_form_builder.dart';
 
 class Projects extends StatelessWidget {
   final String query = '''
-    query {
-      projects {
-        __typename
-        id
-        createdAt
-        updatedAt
-        name
+    query Projects(\$after: String) {
+      projects(after: \$after) {
+        edges {
+          node {
+            __typename
+            id
+            createdAt
+            updatedAt
+            name
+          }
+        }
+        pageInfo {
+          hasNextPage
+          endCursor
+        }
       }
     }
   ''';
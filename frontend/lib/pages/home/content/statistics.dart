@@ -8,28 +8,34 @@ class Statistics extends StatelessWidget {
   Statistics(this.dataviewId);
   final String dataviewId;
   final query = '''
-    query Statistics(\$dataviewId: ID!) {
-      statistics(dataviewId: \$dataviewId) {
-        __typename
-        id
-        createdAt
-        updatedAt
-        type
-        args
-        status
-        value
+    query Statistics(\$dataviewId: ID!, \$after: String) {
+      statistics(dataviewId: \$dataviewId, after: \$after) {
+        edges {
+          node {
+            __typename
+            id
+            createdAt
+            updatedAt
+            type
+            args
+            status
+            value
+          }
+        }
+        pageInfo {
+          hasNextPage
+          endCursor
+        }
       }
     }
   ''';
   final mutation = '''
     mutation CreateStatistic(
       \$dataviewId: ID!,
-      \$type: StatisticType!,
-      \$args: JSON!,
+      \$args: StatisticArgs!,
     ) {
       createStatistic(
         dataviewId: \$dataviewId,
-        type: \$type,
         args: \$args,
       ) {
         __typename
@@ -77,11 +83,11 @@ class Statistics extends StatelessWidget {
           CreateStatisticForm(setFormState, dataviewId),
       createMutation: mutation,
       createFieldsToVariables: (fields) {
-        var type = fields.remove('type').toUpperCase();
+        var type = fields.remove('type').toLowerCase();
+        fields.removeWhere((_k, v) => v == null);
         return {
           'dataviewId': dataviewId,
-          'type': type,
-          'args': jsonEncode(fields),
+          'args': {type: fields},
         };
       },
       onCreate: (_v, _c, refetch) => refetch(),
@@ -129,6 +129,9 @@ fn build(args: &ArgMatches) {
         Some(("garbage-collect", _)) => {
             build_rust_lambda("backend/rs/graphql", "garbage-collect")
         }
+        Some(("ingest", _)) => {
+            build_rust_lambda("backend/rs/graphql", "ingest")
+        }
         Some(("ios", _)) => build_ios(),
         Some(("sam", _)) => build_sam(),
         Some(("web", _)) => build_web(),
@@ -371,13 +374,15 @@ fn deploy(args: &ArgMatches) {
         Some(("graphql", _)) => {
             build_and_deploy_rust_lambda("backend/rs/graphql", "graphql")
         }
+        Some(("ingest", _)) => {
+            build_and_deploy_rust_lambda("backend/rs/graphql", "ingest")
+        }
         Some(("ios", _)) => deploy_ios(),
         Some(("invalidate-cache", _)) => {
             deploy_python_lambda("invalidate-cache")
         }
         Some(("plot", _)) => deploy_python_lambda("plot"),
         Some(("statistic", _)) => deploy_python_lambda("statistic"),
-        Some(("uri-to-sql-db", _)) => deploy_python_lambda("uri-to-sql-db"),
         Some(("web", _)) => deploy_web(),
         _ => quit("invalid deploy target!"),
     }